@@ -1,6 +1,10 @@
 use anyhow::{Context, Result};
+use chat::model_options::Options;
+use configs::profile::Profile;
+use configs::secret::Secret;
+use configs::settings::settings;
 use log::debug;
-use vectordb::EmbeddingStore;
+use vectordb::{ChunkPolicy, ChunkSpec, EmbedOptions, EmbeddingStore, LogFormat, WalkOptions};
 
 // @TODO implement this trait
 #[allow(dead_code)]
@@ -12,11 +16,28 @@ pub trait AgentTrait {
 pub struct ModelAPIProvider {
     pub provider: String, // TODO use LLMProvider enum
     pub api_url: String,
-    pub api_key: String,
+    pub api_key: Secret,
+    pub options: Option<Options>,
 }
 
 impl ModelAPIProvider {
     pub fn get_provider() {}
+
+    /// Create the provider from a named profile resolving its API key and model options
+    pub fn from_profile(profile: &Profile) -> Result<Self> {
+        let options = profile
+            .options
+            .clone()
+            .map(Options::from_value)
+            .transpose()
+            .context("Failed to parse profile options")?;
+        Ok(ModelAPIProvider {
+            provider: profile.provider.to_lowercase(),
+            api_url: profile.api_url.clone(),
+            api_key: profile.resolve_api_key()?.unwrap_or_default(),
+            options,
+        })
+    }
 }
 
 pub struct EmbeddingProvider {
@@ -66,8 +87,9 @@ impl LLMAgent {
             &self.https_client,
             self.llm_provider.provider.as_str(),
             &self.llm_provider.api_url,
-            &self.llm_provider.api_key,
+            self.llm_provider.api_key.expose(),
             &self.model,
+            self.llm_provider.options.clone(),
         ))
         .context("Failed to run chat")?;
 
@@ -89,12 +111,20 @@ impl EmbedAgent {
     }
 
     pub async fn load_embeddings(&self, path: &str, chunk_size: usize) -> Result<EmbeddingStore> {
+        // the chunk size of the agent applies to files without a chunking rule
+        let chunk_spec = ChunkSpec {
+            size: chunk_size,
+            ..ChunkSpec::from_settings()?
+        };
         let embedding_store = vectordb::run_embedding_pipeline(
             path,
-            chunk_size,
+            &ChunkPolicy::with_rules(chunk_spec, &settings().chunking.value)?
+                .with_log_format(LogFormat::from_settings()?),
+            &WalkOptions::from_settings(),
+            &EmbedOptions::from_settings()?,
             &self.embedding_provider.llm_provider.provider,
             &self.embedding_provider.llm_provider.api_url,
-            &self.embedding_provider.llm_provider.api_key,
+            self.embedding_provider.llm_provider.api_key.expose(),
             &self.embedding_provider.model,
             &self.https_client,
         )
@@ -123,13 +153,15 @@ impl EmbedAgent {
             &mut db,
             &self.embedding_provider.llm_provider.provider,
             &self.embedding_provider.llm_provider.api_url,
-            &self.embedding_provider.llm_provider.api_key,
+            self.embedding_provider.llm_provider.api_key.expose(),
             &self.embedding_provider.model,
             &input,
             &embedding_store.table,
             &self.https_client,
             whole_query,
             file_context,
+            None,
+            None,
         )
         .await
         .context("Failed to run lance query")?;
@@ -159,6 +191,7 @@ impl RagAgent {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn rag_query(
         &self,
         rt: &tokio::runtime::Runtime,
@@ -195,8 +228,9 @@ impl RagAgent {
             &self.https_client,
             &self.ai_model.llm_provider.provider,
             &self.ai_model.llm_provider.api_url,
-            &self.ai_model.llm_provider.api_key,
+            self.ai_model.llm_provider.api_key.expose(),
             &self.ai_model.model,
+            self.ai_model.llm_provider.options.clone(),
             chat::get_chat_input,
             continue_chat,
         ))
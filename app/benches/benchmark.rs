@@ -18,6 +18,7 @@ pub fn bench_command_execution(c: &mut Criterion) {
                 log_level: None,
                 interactive: None,
                 agent_mode: None,
+                profile: None,
             };
             run_app(args, rt).unwrap();
             black_box(())
@@ -32,10 +33,19 @@ pub fn bench_load_embeddings(c: &mut Criterion) {
             let commands = Commands::Load {
                 path: "tests/resources/sample".to_string(),
                 chunk_size: "1000".to_string(),
+                chunk_sizer: "characters".to_string(),
+                chunk_overlap: None,
+                embed_mode: "chunk".to_string(),
+                batch_size: "16".to_string(),
+                max_in_flight: "4".to_string(),
+                include: vec![],
+                exclude: vec![],
+                max_file_size: "0".to_string(),
+                parallelism: "0".to_string(),
                 llm_provider: "ollama".to_string(),
                 embed_model: "nomic-embed-text".to_string(),
                 api_url: "http://localhost:11434".to_string(),
-                api_key: "".to_string(),
+                api_key: None,
             };
 
             match cli(commands, rt).context("Failed to run load command") {
@@ -56,12 +66,16 @@ pub fn bench_rag_query(c: &mut Criterion) {
                 llm_provider: "ollama".to_string(),
                 embed_model: "nomic-embed-text".to_string(),
                 api_url: "http://localhost:11434".to_string(),
-                api_key: "".to_string(),
+                api_key: None,
                 ai_model: "qwen2:7b".to_string(),
                 table: "sample_table".to_string(),
                 database: "sample_db".to_string(),
                 whole_query: "false".to_string(),
                 file_context: "false".to_string(),
+                symbol: None,
+                symbol_match: "boost".to_string(),
+                since: None,
+                until: None,
                 system_prompt: "tests/resources/rag_prompt.txt".to_string(),
                 continue_chat: "false".to_string(),
             };
@@ -1,9 +1,10 @@
 use agent::ai_agent::{EmbedAgent, EmbeddingProvider, LLMAgent, ModelAPIProvider, RagAgent};
 use anyhow::{anyhow, Context, Ok, Result};
 use chat::chat_config::LLMProvider;
-use configs::constants::{
-    AI_MODEL, CHAT_API_KEY, EMBEDDING_MODEL, SYSTEM_PROMPT_PATH,
-};
+use chat::model_options::Options;
+use configs::profile::Profile;
+use configs::secret::Secret;
+use configs::settings::settings;
 use dialoguer::{console::Term, theme::ColorfulTheme, Confirm, Input, Select};
 
 pub fn interactive_cli(rt: &tokio::runtime::Runtime) -> Result<()> {
@@ -28,35 +29,36 @@ pub fn interactive_cli(rt: &tokio::runtime::Runtime) -> Result<()> {
         "Load" => {
             let https_client =
                 configs::get_https_client().context("Failed to initialize https client")?;
-            let llm_provider = fetch_llm_config(&theme, "Embed Provider:")?;
+            let (llm_provider, profile) = fetch_llm_config(&theme, "Embed Provider:")?;
             let model = Input::with_theme(&theme)
                 .with_prompt("Embedding model")
-                .default(EMBEDDING_MODEL.to_string())
+                .default(default_embed_model(profile.as_ref()))
                 .interact_text()?;
 
             let embedding_provider = EmbeddingProvider::new(llm_provider, model);
-            let agent = EmbedAgent::new(https_client, embedding_provider);
+            let agent = EmbedAgent::new(https_client.clone(), embedding_provider);
 
             let path: String = Input::with_theme(&theme)
                 .with_prompt("Enter file path")
                 .interact_text()?;
             let chunk_size: usize = Input::with_theme(&theme)
                 .with_prompt("Enter chunk size")
-                .default("1024".to_string())
+                .default(settings().chunk_size.value.to_string())
                 .interact_text()?
                 .parse::<usize>()?;
 
             let embedding_store = rt.block_on(agent.load_embeddings(path.as_str(), chunk_size))?;
             println!("Embedding store: {:?}", embedding_store);
+            println!("{}", https_client.stats());
         }
 
         "LanceQuery" => {
             let https_client =
                 configs::get_https_client().context("Failed to initialize https client")?;
-            let llm_provider = fetch_llm_config(&theme, "Embed Provider:")?;
+            let (llm_provider, profile) = fetch_llm_config(&theme, "Embed Provider:")?;
             let model = Input::with_theme(&theme)
                 .with_prompt("Embedding model")
-                .default(EMBEDDING_MODEL.to_string())
+                .default(default_embed_model(profile.as_ref()))
                 .interact_text()?;
 
             let embedding_provider = EmbeddingProvider::new(llm_provider, model);
@@ -99,19 +101,19 @@ pub fn interactive_cli(rt: &tokio::runtime::Runtime) -> Result<()> {
             let https_client =
                 configs::get_https_client().context("Failed to initialize https client")?;
 
-            let embed_provider = fetch_llm_config(&theme, "Embed provider:")?;
+            let (embed_provider, profile) = fetch_llm_config(&theme, "Embed provider:")?;
             let embeding_model = Input::with_theme(&theme)
                 .with_prompt("Embedding model")
-                .default(EMBEDDING_MODEL.to_string())
+                .default(default_embed_model(profile.as_ref()))
                 .interact_text()?;
 
             let embedding_provider = EmbeddingProvider::new(embed_provider, embeding_model);
             let embed_agent = EmbedAgent::new(https_client.clone(), embedding_provider);
 
-            let llm_provider = fetch_llm_config(&theme, "LLM Provider:")?;
+            let (llm_provider, profile) = fetch_llm_config(&theme, "LLM Provider:")?;
             let ai_model = Input::with_theme(&theme)
                 .with_prompt("AI Model")
-                .default(AI_MODEL.to_string())
+                .default(default_chat_model(profile.as_ref()))
                 .interact_text()?;
 
             let ai_model = LLMAgent::new(https_client.clone(), llm_provider, ai_model);
@@ -122,14 +124,14 @@ pub fn interactive_cli(rt: &tokio::runtime::Runtime) -> Result<()> {
                 .interact_text()?;
             let system_prompt: String = Input::with_theme(&theme)
                 .with_prompt("System prompt file path")
-                .default(SYSTEM_PROMPT_PATH.into())
+                .default(settings().system_prompt.value.clone())
                 .interact_text()?;
             agent.rag_query(
                 rt,
                 &path,
                 Input::with_theme(&theme)
                     .with_prompt("Enter chunk size")
-                    .default("1024".to_string())
+                    .default(settings().chunk_size.value.to_string())
                     .interact_text()?
                     .parse::<usize>()?,
                 Input::<String>::with_theme(&theme)
@@ -157,14 +159,14 @@ pub fn interactive_cli(rt: &tokio::runtime::Runtime) -> Result<()> {
         "Generate" => {
             let https_client =
                 configs::get_https_client().context("Failed to initialize https client")?;
-            let llm_provider = fetch_llm_config(&theme, "LLM Provider:")?;
+            let (llm_provider, profile) = fetch_llm_config(&theme, "LLM Provider:")?;
 
             let ai_agent = LLMAgent {
                 https_client,
                 llm_provider,
                 model: Input::with_theme(&theme)
                     .with_prompt("AI Model")
-                    .default(AI_MODEL.to_string())
+                    .default(default_chat_model(profile.as_ref()))
                     .interact_text()?,
             };
 
@@ -173,7 +175,7 @@ pub fn interactive_cli(rt: &tokio::runtime::Runtime) -> Result<()> {
                 &Input::<String>::with_theme(&theme)
                     .with_prompt("Enter your prompt")
                     .interact_text()?,
-                SYSTEM_PROMPT_PATH,
+                &settings().system_prompt.value,
             )?;
         }
 
@@ -201,7 +203,34 @@ pub fn interactive_cli(rt: &tokio::runtime::Runtime) -> Result<()> {
     Ok(())
 }
 
-fn fetch_llm_config(theme: &ColorfulTheme, prompt: &str) -> Result<ModelAPIProvider> {
+/// Select a profile or enter the provider, url and key manually with "custom"
+fn fetch_llm_config(
+    theme: &ColorfulTheme,
+    prompt: &str,
+) -> Result<(ModelAPIProvider, Option<Profile>)> {
+    let config = settings();
+    let mut profiles: Vec<&str> = config.profiles.keys().map(|s| s.as_str()).collect();
+    profiles.push("custom");
+    // default to the --profile selection otherwise to custom
+    let default_index = config
+        .profile
+        .value
+        .as_deref()
+        .and_then(|p| profiles.iter().position(|name| *name == p))
+        .unwrap_or(profiles.len() - 1);
+
+    let profile_index = Select::with_theme(theme)
+        .with_prompt(format!("{} profile", prompt))
+        .items(&profiles)
+        .default(default_index)
+        .interact_on(&Term::stdout())?;
+
+    if profiles[profile_index] != "custom" {
+        let profile = config.get_profile(profiles[profile_index])?.clone();
+        let llm_provider = ModelAPIProvider::from_profile(&profile)?;
+        return Ok((llm_provider, Some(profile)));
+    }
+
     let provider = Input::with_theme(theme)
         .with_prompt(prompt)
         .validate_with(|input: &String| -> core::result::Result<(), &str> {
@@ -210,7 +239,7 @@ fn fetch_llm_config(theme: &ColorfulTheme, prompt: &str) -> Result<ModelAPIProvi
                 _ => Err("Please input one of the supported providers: ollama, openai"),
             }
         })
-        .default("ollama".to_string())
+        .default(settings().llm_provider.value.clone())
         .interact_text()?;
 
     let api_url = Input::with_theme(theme)
@@ -219,17 +248,35 @@ fn fetch_llm_config(theme: &ColorfulTheme, prompt: &str) -> Result<ModelAPIProvi
         .default(configs::LLMProvider::get_api_url(&provider)?.to_string())
         .interact_text()?;
 
-    let api_key = Input::with_theme(theme)
-        .with_prompt("API Key")
-        .default(CHAT_API_KEY.to_string())
+    // the key is not echoed as a default, empty input keeps the configured key
+    let api_key_spec: String = Input::with_theme(theme)
+        .with_prompt("API Key (empty for configured key, env:VAR, file:PATH or cmd:COMMAND)")
+        .allow_empty(true)
         .interact_text()?;
+    let api_key = if api_key_spec.is_empty() {
+        settings().resolve_api_key()?
+    } else {
+        Secret::resolve(&api_key_spec)?
+    };
 
     let llm_provider = ModelAPIProvider {
         provider,
         api_url,
         api_key,
+        options: Options::from_settings()?,
     };
 
-    Ok(llm_provider)
+    Ok((llm_provider, None))
 }
 
+fn default_embed_model(profile: Option<&Profile>) -> String {
+    profile
+        .and_then(|p| p.embed_model.clone())
+        .unwrap_or_else(|| settings().embed_model.value.clone())
+}
+
+fn default_chat_model(profile: Option<&Profile>) -> String {
+    profile
+        .and_then(|p| p.chat_model.clone())
+        .unwrap_or_else(|| settings().ai_model.value.clone())
+}
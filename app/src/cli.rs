@@ -1,20 +1,35 @@
-use crate::commands::Commands;
+use crate::commands::{CacheCommands, Commands, ConfigCommands, ProfileCommands};
 use anyhow::Result;
 use anyhow::{Context, Ok};
-use configs::constants::SYSTEM_PROMPT_PATH;
+use chat::model_options::Options;
+use configs::constants::{OLLAMA_VERSION_API, OPEN_AI_MODELS_API};
+use configs::profile::Profile;
+use configs::secret::Secret;
+use configs::settings::{settings, Settings};
+use configs::HttpsClient;
+use configs::LLMProvider;
+use embedder::embed_cache::{self, cache_dir};
 use http_body_util::Full;
 use hyper::body::Bytes;
-use hyper::client::connect::HttpInfo;
-use hyper_rustls::HttpsConnector;
-use hyper_util::client::legacy::connect::HttpConnector;
-use hyper_util::client::legacy::Client as LegacyClient;
+use hyper_util::client::legacy::connect::HttpInfo;
 use log::{debug, info};
+use vectordb::query::{SymbolMatch, SymbolQuery, TimeWindow};
+use vectordb::{ChunkPolicy, ChunkSpec, EmbedMode, EmbedOptions, LogFormat, Sizer, WalkOptions};
 
 pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
     match commands {
         Commands::Load {
             path,
             chunk_size,
+            chunk_sizer,
+            chunk_overlap,
+            embed_mode,
+            batch_size,
+            max_in_flight,
+            include,
+            exclude,
+            max_file_size,
+            parallelism,
             llm_provider,
             embed_model,
             api_url,
@@ -23,6 +38,15 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             info!("Using the Load arguments below:");
             info!(" Path: {:?}", path);
             info!(" Chunk Size: {:?}", chunk_size);
+            info!(" Chunk Sizer: {:?}", chunk_sizer);
+            info!(" Chunk Overlap: {:?}", chunk_overlap);
+            info!(" Embed Mode: {:?}", embed_mode);
+            info!(" Batch Size: {:?}", batch_size);
+            info!(" Max In Flight: {:?}", max_in_flight);
+            info!(" Include: {:?}", include);
+            info!(" Exclude: {:?}", exclude);
+            info!(" Max File Size: {:?}", max_file_size);
+            info!(" Parallelism: {:?}", parallelism);
             info!(" LLM Provider: {:?}", llm_provider);
             info!(" Embedding Model: {:?}", embed_model);
             info!(" API URL: {:?}", api_url);
@@ -30,15 +54,50 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             let chunk_size = chunk_size
                 .parse::<usize>()
                 .context("Failed to parse chunk size")?;
+            let chunk_overlap = match chunk_overlap {
+                Some(overlap) => Some(
+                    overlap
+                        .parse::<usize>()
+                        .context("Failed to parse chunk overlap")?,
+                ),
+                None => settings().chunk_overlap.value,
+            };
+            // the flags set the chunking of files without a rule in the config file
+            let chunk_spec = ChunkSpec {
+                overlap: chunk_overlap,
+                ..ChunkSpec::new(chunk_size, Sizer::parse_sizer(&chunk_sizer)?)
+            };
+            let chunk_policy = ChunkPolicy::with_rules(chunk_spec, &settings().chunking.value)
+                .context("Invalid chunking rules")?
+                .with_log_format(LogFormat::from_settings().context("Invalid log patterns")?);
+            let embed_options = EmbedOptions {
+                mode: EmbedMode::parse_mode(&embed_mode)?,
+                batch_size: batch_size
+                    .parse::<usize>()
+                    .context("Failed to parse batch size")?,
+                max_in_flight: max_in_flight
+                    .parse::<usize>()
+                    .context("Failed to parse max in flight")?,
+            };
+            let walk_options = WalkOptions {
+                include,
+                exclude,
+                max_file_size: max_file_size
+                    .parse::<u64>()
+                    .context("Failed to parse max file size")?,
+                parallelism: parallelism
+                    .parse::<usize>()
+                    .context("Failed to parse parallelism")?,
+                ..WalkOptions::from_settings()
+            };
+            let api_key = api_key_or_settings(api_key.as_deref())?;
             let https_client =
                 configs::get_https_client().context("Failed to create HTTPS client")?;
             // let embed_url = format!("{}/{}", constants::CHAT_API_URL, "api/embed");
 
-            rt.block_on(check_connection(
-                &https_client,
-                &format!("{}/{}", api_url, "api/version"),
-            ))
-            .context("Failed to check connection")?;
+            let provider = LLMProvider::get_provider(&llm_provider)?;
+            rt.block_on(check_endpoint(&https_client, &provider, &api_url, &api_key))
+                .context("Failed to check connection")?;
 
             // rt.block_on(check_client(
             //     &http_client,
@@ -48,10 +107,12 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
 
             rt.block_on(vectordb::run_embedding_pipeline(
                 &path,
-                chunk_size,
+                &chunk_policy,
+                &walk_options,
+                &embed_options,
                 llm_provider.as_str(),
                 &api_url,
-                &api_key,
+                api_key.expose(),
                 embed_model.as_str(),
                 &https_client,
             ))
@@ -59,6 +120,7 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
 
             // shutdown the runtime after the embedding is done
             println!("Finished Loading the embedding");
+            println!("{}", https_client.stats());
             rt.shutdown_timeout(std::time::Duration::from_secs(1));
         }
         Commands::LanceQuery {
@@ -71,6 +133,10 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             database,
             whole_query,
             file_context,
+            symbol,
+            symbol_match,
+            since,
+            until,
         } => {
             let input_list = Commands::fetch_prompt_from_cli(input.clone(), "Enter query: ");
             // let embed_model = model.to_string();
@@ -82,6 +148,10 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             let file_context: bool = file_context
                 .parse()
                 .context("Failed to parse file_query flag")?;
+            let symbol = symbol_query(symbol, &symbol_match)?;
+            let time_window = TimeWindow::parse_window(since.as_deref(), until.as_deref())
+                .context("Invalid time window")?;
+            let api_key = api_key_or_settings(api_key.as_deref())?;
 
             info!(" Query: {:?}", input_list);
             info!(" LLM Provider: {:?}", llm_provider);
@@ -90,6 +160,8 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             info!(" Table: {:?}", table);
             info!(" Whole Query: {:?}", whole_query);
             info!(" File Query: {:?}", file_context);
+            info!(" Symbol: {:?}", symbol);
+            info!(" Time Window: {:?}", time_window);
 
             // Initialize the http client outside the thread // TODO wrap in Arc<Mutex>
             let https_client =
@@ -106,13 +178,15 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
                     &mut db,
                     llm_provider.as_str(),
                     api_url.as_str(),
-                    api_key.as_str(),
+                    api_key.expose(),
                     model.as_str(),
                     &input_list,
                     &table,
                     &https_client,
                     whole_query,
                     file_context,
+                    symbol.as_ref(),
+                    time_window.as_ref(),
                 ))
                 .context("Failed to run query")?;
 
@@ -129,6 +203,10 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             database,
             whole_query,
             file_context,
+            symbol,
+            symbol_match,
+            since,
+            until,
             system_prompt,
             continue_chat,
         } => {
@@ -142,6 +220,10 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             let file_context: bool = file_context
                 .parse()
                 .context("Failed to parse file_query flag")?;
+            let symbol = symbol_query(symbol, &symbol_match)?;
+            let time_window = TimeWindow::parse_window(since.as_deref(), until.as_deref())
+                .context("Invalid time window")?;
+            let api_key = api_key_or_settings(api_key.as_deref())?;
             // let system_prompt = system_prompt.as_str();
             // let provider = llm_provider.as_str();
             let continue_chat: bool = continue_chat
@@ -155,6 +237,8 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             println!(" Embedding Model: {:?}", embed_model);
             println!(" AI Model: {:?}", ai_model);
             println!(" Table: {:?}", table);
+            println!(" Symbol: {:?}", symbol);
+            println!(" Time Window: {:?}", time_window);
             println!(" Continous Chat: {:?}", continue_chat);
 
             // Initialize the http client outside the thread // TODO wrap in Arc<Mutex>
@@ -173,13 +257,15 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
                     &mut db,
                     llm_provider.as_str(),
                     api_url.as_str(),
-                    api_key.as_str(),
+                    api_key.expose(),
                     embed_model.as_str(),
                     &input_list,
                     &table,
                     &https_client,
                     whole_query,
                     file_context,
+                    symbol.as_ref(),
+                    time_window.as_ref(),
                 ))
                 .context("Failed to run query")?;
 
@@ -198,12 +284,14 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
                 &https_client,
                 llm_provider.as_str(),
                 &api_url,
-                &api_key,
+                api_key.expose(),
                 &ai_model,
+                Options::from_settings()?,
                 chat::get_chat_input,
                 continue_chat,
             ))
             .context("Failed to run chat")?;
+            info!("{}", https_client.stats());
 
             rt.shutdown_timeout(std::time::Duration::from_secs(1));
         }
@@ -215,17 +303,18 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
             ai_model,
         } => {
             // let prompt = Commands::fetch_prompt_from_cli(Vec::new(), "Enter prompt: ");
+            let api_key = api_key_or_settings(api_key.as_deref())?;
             println!("Chat command is run with below arguments:");
             println!(" Prompt: {:?}", prompt);
             println!(" LLM Provider: {:?}", llm_provider);
             println!(" API URL: {:?}", api_url);
-            println!(" API Key: {:?}", api_key);
+            println!(" API Key: {}", api_key);
             println!(" AI Model: {:?}", ai_model);
 
             let context: Option<&str> = None;
             let client = configs::get_https_client().context("Failed to create HTTPS client")?;
 
-            let system_prompt = SYSTEM_PROMPT_PATH;
+            let system_prompt = configs::settings::settings().system_prompt.value.as_str();
             rt.block_on(chat::run_chat(
                 system_prompt,
                 &prompt,
@@ -233,13 +322,102 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
                 &client,
                 llm_provider.as_str(),
                 &api_url,
-                &api_key,
+                api_key.expose(),
                 &ai_model,
+                Options::from_settings()?,
             ))
             .context("Failed to run chat")?;
+            info!("{}", client.stats());
 
             rt.shutdown_timeout(std::time::Duration::from_secs(1));
         }
+        Commands::Config { command } => match command {
+            ConfigCommands::Show {
+                llm_provider,
+                api_url,
+                api_key,
+                embed_model,
+                ai_model,
+                system_prompt,
+                chunk_size,
+            } => {
+                let mut settings: Settings = configs::settings::settings().clone();
+                settings.merge_cli("llm_provider", llm_provider)?;
+                settings.merge_cli("api_url", api_url)?;
+                settings.merge_cli("api_key", api_key)?;
+                settings.merge_cli("embed_model", embed_model)?;
+                settings.merge_cli("ai_model", ai_model)?;
+                settings.merge_cli("system_prompt", system_prompt)?;
+                settings.merge_cli("chunk_size", chunk_size)?;
+
+                println!("Effective configuration:");
+                for (key, value, source) in settings.entries() {
+                    println!(" {:<14} = {:<40} ({})", key, value, source);
+                }
+            }
+        },
+        Commands::Profiles { command } => match command {
+            ProfileCommands::List => {
+                let settings = configs::settings::settings();
+                let selected = settings.profile.value.clone().unwrap_or_default();
+                println!("Profiles:");
+                for (name, profile) in &settings.profiles {
+                    let marker = if *name == selected { "*" } else { " " };
+                    println!(
+                        "{} {:<14} provider: {:<8} url: {:<28} key: {:<22} embed: {:<24} chat: {}",
+                        marker,
+                        name,
+                        profile.provider,
+                        profile.api_url,
+                        profile.key_source(),
+                        profile.embed_model.as_deref().unwrap_or("-"),
+                        profile.chat_model.as_deref().unwrap_or("-"),
+                    );
+                }
+            }
+            ProfileCommands::Test { name } => {
+                let profile = configs::settings::settings().get_profile(&name)?;
+                profile.validate(&name)?;
+                let https_client =
+                    configs::get_https_client().context("Failed to create HTTPS client")?;
+                rt.block_on(check_profile(&https_client, profile))
+                    .with_context(|| format!("Profile {} failed the connection check", name))?;
+                println!("Profile {} is reachable at {}", name, profile.api_url);
+            }
+        },
+        Commands::Cache { command } => match command {
+            CacheCommands::Stats { database } => {
+                let dir = cache_dir(&database);
+                let summaries = embed_cache::summarize(&dir)?;
+                if summaries.is_empty() {
+                    println!("No embedding cache found at {}", dir.display());
+                }
+                for summary in summaries {
+                    println!(
+                        "{:<48} entries: {:<8} size: {:>8} KiB  hits: {:<8} misses: {:<8} hit rate: {:.1}%",
+                        summary.key.to_string(),
+                        summary.entries,
+                        summary.bytes / 1024,
+                        summary.stats.hits,
+                        summary.stats.misses,
+                        summary.stats.hit_rate() * 100.0
+                    );
+                }
+            }
+            CacheCommands::Prune { database, model } => {
+                let dir = cache_dir(&database);
+                let removed = embed_cache::prune(&dir, &model)?;
+                if removed.is_empty() {
+                    println!("No cached embeddings found for {}", model.join(", "));
+                }
+                for summary in removed {
+                    println!(
+                        "Removed {} cached embeddings of {}",
+                        summary.entries, summary.key
+                    );
+                }
+            }
+        },
         Commands::Version { version } => {
             println!("Version: {}", version);
             std::process::exit(0);
@@ -259,20 +437,59 @@ pub fn cli(commands: Commands, rt: tokio::runtime::Runtime) -> Result<()> {
     Ok(())
 }
 
-async fn check_connection(client: &HttpsClient, url: &str) -> Result<()> {
-    // let uri = hyper::Uri::from_static(&url);
-    let uri = url.parse::<http::Uri>()?;
+/// API key of the `--api-key` spec or the configured key when it isn't given
+fn api_key_or_settings(api_key: Option<&str>) -> Result<Secret> {
+    match api_key {
+        Some(spec) => Secret::resolve(spec).context("Failed to load the API key"),
+        None => settings().resolve_api_key(),
+    }
+}
 
-    let res = client.get(uri).await?;
-    if res.status().is_success() {
-        if let Some(info) = res.extensions().get::<HttpInfo>() {
-            info!("remote addr = {}", info.remote_addr())
-        }
-    } else {
-        anyhow::bail!(anyhow::anyhow!("Failed to connect to the server {}", url));
+/// Check the profile endpoint answers an authenticated request
+async fn check_profile(client: &HttpsClient, profile: &Profile) -> Result<()> {
+    let api_key = profile.resolve_api_key()?.unwrap_or_default();
+    check_endpoint(client, &profile.llm_provider()?, &profile.api_url, &api_key).await
+}
+
+/// Check the provider endpoint answers an authenticated request, `api/version` for ollama and
+/// `v1/models` for openai and openai compatible servers
+async fn check_endpoint(
+    client: &HttpsClient,
+    provider: &LLMProvider,
+    api_url: &str,
+    api_key: &Secret,
+) -> Result<()> {
+    let url = match provider {
+        LLMProvider::Ollama => format!("{}/{}", api_url, OLLAMA_VERSION_API),
+        LLMProvider::OpenAI => format!("{}/{}", api_url, OPEN_AI_MODELS_API),
+    };
+
+    let request = http::Request::builder()
+        .method("GET")
+        .uri(&url)
+        .header("Authorization", format!("Bearer {}", api_key.expose()))
+        .body(Full::new(Bytes::new()))
+        .context("Failed to build request")?;
+
+    let res = client
+        .request(request)
+        .await
+        .with_context(|| format!("Failed to connect to {}", url))?;
+    if !res.status().is_success() {
+        anyhow::bail!("{} returned status {}", url, res.status());
+    }
+    if let Some(info) = res.extensions().get::<HttpInfo>() {
+        info!("remote addr = {}", info.remote_addr())
     }
+    debug!("Endpoint check {} returned {}", url, res.status());
 
     Ok(())
 }
 
-type HttpsClient = LegacyClient<HttpsConnector<HttpConnector>, Full<Bytes>>;
+/// Symbol the query chunks are matched on, None without a symbol
+fn symbol_query(symbol: Option<String>, symbol_match: &str) -> Result<Option<SymbolQuery>> {
+    let mode = SymbolMatch::parse_mode(symbol_match).context("Invalid symbol match")?;
+    Ok(symbol
+        .filter(|name| !name.is_empty())
+        .map(|name| SymbolQuery::new(&name, mode)))
+}
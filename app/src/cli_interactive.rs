@@ -1,9 +1,7 @@
 use crate::commands::Commands;
 use anyhow::{anyhow, Context, Result};
+use configs::settings::settings;
 use configs::LLMProvider;
-use configs::constants::{
-    AI_MODEL, CHAT_API_KEY, CHAT_API_URL, EMBEDDING_MODEL, OPEN_AI_URL, SYSTEM_PROMPT_PATH,
-};
 use dialoguer::{console::Term, theme::ColorfulTheme, Confirm, Input, Select};
 
 pub fn interactive_cli() -> Result<Commands> {
@@ -33,12 +31,29 @@ pub fn interactive_cli() -> Result<Commands> {
                     .interact_text()?,
                 chunk_size: Input::with_theme(&theme)
                     .with_prompt("Enter chunk size")
-                    .default("1024".to_string())
+                    .default(settings().chunk_size.value.to_string())
                     .interact_text()?,
+                chunk_sizer: Input::with_theme(&theme)
+                    .with_prompt(
+                        "Chunk size unit (characters, tiktoken:<encoding>, hf:<tokenizer.json>)",
+                    )
+                    .default(settings().chunk_sizer.value.clone())
+                    .interact_text()?,
+                chunk_overlap: settings().chunk_overlap.value.map(|o| o.to_string()),
+                embed_mode: Input::with_theme(&theme)
+                    .with_prompt("Embed mode (chunk or line)")
+                    .default(settings().embed_mode.value.clone())
+                    .interact_text()?,
+                batch_size: settings().embed_batch_size.value.to_string(),
+                max_in_flight: settings().embed_max_in_flight.value.to_string(),
+                include: Vec::new(),
+                exclude: Vec::new(),
+                max_file_size: settings().max_file_size.value.to_string(),
+                parallelism: settings().load_parallelism.value.to_string(),
                 llm_provider,
                 embed_model: Input::with_theme(&theme)
                     .with_prompt("Embedding model")
-                    .default(EMBEDDING_MODEL.to_string())
+                    .default(settings().embed_model.value.clone())
                     .interact_text()?,
                 api_url,
                 api_key,
@@ -59,7 +74,7 @@ pub fn interactive_cli() -> Result<Commands> {
                 api_key,
                 model: Input::with_theme(&theme)
                     .with_prompt("Embedding model")
-                    .default(EMBEDDING_MODEL.to_string())
+                    .default(settings().embed_model.value.clone())
                     .interact_text()?,
                 table: Input::with_theme(&theme)
                     .with_prompt("Table name")
@@ -79,6 +94,10 @@ pub fn interactive_cli() -> Result<Commands> {
                     .default(false)
                     .interact()?
                     .to_string(),
+                symbol: fetch_symbol(&theme)?,
+                symbol_match: "boost".to_string(),
+                since: None,
+                until: None,
             })
         }
 
@@ -95,13 +114,13 @@ pub fn interactive_cli() -> Result<Commands> {
                 llm_provider,
                 embed_model: Input::with_theme(&theme)
                     .with_prompt("Embedding model")
-                    .default(EMBEDDING_MODEL.to_string())
+                    .default(settings().embed_model.value.clone())
                     .interact_text()?,
                 api_url,
                 api_key,
                 ai_model: Input::with_theme(&theme)
                     .with_prompt("AI Model")
-                    .default(AI_MODEL.to_string())
+                    .default(settings().ai_model.value.clone())
                     .interact_text()?,
                 table: Input::with_theme(&theme)
                     .with_prompt("VectorDB Table")
@@ -121,9 +140,13 @@ pub fn interactive_cli() -> Result<Commands> {
                     .default(false)
                     .interact()?
                     .to_string(),
+                symbol: fetch_symbol(&theme)?,
+                symbol_match: "boost".to_string(),
+                since: None,
+                until: None,
                 system_prompt: Input::with_theme(&theme)
                     .with_prompt("System prompt file path")
-                    .default(SYSTEM_PROMPT_PATH.into())
+                    .default(settings().system_prompt.value.clone())
                     .interact_text()?,
                 continue_chat: Confirm::with_theme(&theme)
                     .with_prompt("Continue chat?")
@@ -147,7 +170,7 @@ pub fn interactive_cli() -> Result<Commands> {
                 api_key,
                 ai_model: Input::with_theme(&theme)
                     .with_prompt("AI Model")
-                    .default(AI_MODEL.to_string())
+                    .default(settings().ai_model.value.clone())
                     .interact_text()?,
             })
         }
@@ -164,7 +187,7 @@ pub fn interactive_cli() -> Result<Commands> {
     }
 }
 
-fn fetch_llm_config(theme: &ColorfulTheme) -> Result<(String, String, String)> {
+fn fetch_llm_config(theme: &ColorfulTheme) -> Result<(String, String, Option<String>)> {
     let llm_provider = Input::with_theme(theme)
         .with_prompt("LLM provider")
         .validate_with(|input: &String| -> core::result::Result<(), &str> {
@@ -173,30 +196,32 @@ fn fetch_llm_config(theme: &ColorfulTheme) -> Result<(String, String, String)> {
                 _ => Err("Please input one of the supported providers: ollama, openai"),
             }
         })
-        .default("ollama".to_string())
+        .default(settings().llm_provider.value.clone())
         .interact_text()?;
 
     let chat_api_url =
-        get_chat_api_url(&llm_provider).context("Provided LLm Provider not supported")?;
+        LLMProvider::get_api_url(&llm_provider).context("Provided LLm Provider not supported")?;
 
     let api_url = Input::with_theme(theme)
         .with_prompt("Chat API Url")
         .default(chat_api_url)
         .interact_text()?;
 
-    let api_key = Input::with_theme(theme)
-        .with_prompt("API Key")
-        .default(CHAT_API_KEY.to_string())
+    // the key is not echoed as a default, empty input keeps the configured key
+    let api_key: String = Input::with_theme(theme)
+        .with_prompt("API Key (empty for configured key, env:VAR, file:PATH or cmd:COMMAND)")
+        .allow_empty(true)
         .interact_text()?;
+    let api_key = Some(api_key).filter(|k| !k.is_empty());
 
     Ok((llm_provider, api_url, api_key))
 }
 
-fn get_chat_api_url(provider: &str) -> Result<String> {
-    let provider =
-        LLMProvider::get_provider(provider).map_err(|_| anyhow!("Unsupported provider"))?;
-    match provider {
-        LLMProvider::OpenAI => Ok(OPEN_AI_URL.to_string()),
-        LLMProvider::Ollama => Ok(CHAT_API_URL.to_string()),
-    }
+/// Exact symbol name to boost the chunks on, empty input matches no symbol
+fn fetch_symbol(theme: &ColorfulTheme) -> Result<Option<String>> {
+    let symbol: String = Input::with_theme(theme)
+        .with_prompt("Symbol to boost (empty for none)")
+        .allow_empty(true)
+        .interact_text()?;
+    Ok(Some(symbol.trim().to_string()).filter(|s| !s.is_empty()))
 }
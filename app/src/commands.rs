@@ -1,8 +1,8 @@
 use ::std::io::{self, Write};
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand, ValueEnum};
-use configs::constants::{AI_MODEL, EMBEDDING_MODEL, SYSTEM_PROMPT_PATH, VERSION};
-use configs::constants::{CHAT_API_KEY, CHAT_API_URL};
+use configs::constants::VERSION;
+use configs::settings::settings;
 use log::info;
 use tokio::runtime::Runtime;
 
@@ -24,6 +24,9 @@ pub struct Args {
     /// Select to run in agent mode
     #[clap(short = 'g', long, global = true)]
     pub agent_mode: Option<bool>,
+    /// Select a named provider profile from the config
+    #[clap(long, global = true)]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -44,24 +47,57 @@ pub enum Commands {
         path: String,
         // chunk size
         #[clap(short, long)]
-        #[clap(default_value = "2048")]
+        #[clap(default_value_t = settings().chunk_size.value.to_string())]
         chunk_size: String,
+        /// Unit of the chunk size `characters`, `tiktoken:<encoding or model>` or `hf:<path to tokenizer.json>`
+        #[clap(long)]
+        #[clap(default_value_t = settings().chunk_sizer.value.clone())]
+        chunk_sizer: String,
+        /// Characters or tokens shared by neighbouring chunks, a quarter of the chunk size up to 256 when not set
+        #[clap(long)]
+        chunk_overlap: Option<String>,
+        /// Embed each chunk as one vector `chunk` or every line of a chunk `line`
+        #[clap(long)]
+        #[clap(default_value_t = settings().embed_mode.value.clone())]
+        embed_mode: String,
+        /// Chunks sent in a single embedding request
+        #[clap(long)]
+        #[clap(default_value_t = settings().embed_batch_size.value.to_string())]
+        batch_size: String,
+        /// Embedding requests in flight at the same time
+        #[clap(long)]
+        #[clap(default_value_t = settings().embed_max_in_flight.value.to_string())]
+        max_in_flight: String,
+        /// Only load files matching these globs, relative to the loaded directory
+        #[clap(long)]
+        include: Vec<String>,
+        /// Skip files and directories matching these globs, relative to the loaded directory
+        #[clap(long)]
+        exclude: Vec<String>,
+        /// Skip files above this size in bytes, 0 is unlimited
+        #[clap(long)]
+        #[clap(default_value_t = settings().max_file_size.value.to_string())]
+        max_file_size: String,
+        /// Files read and split at the same time, 0 uses the number of CPUs
+        #[clap(long)]
+        #[clap(default_value_t = settings().load_parallelism.value.to_string())]
+        parallelism: String,
         /// Provide the model to use for query embedding
         #[clap(short = 'm', long)]
-        #[clap(default_value = "ollama")]
+        #[clap(default_value_t = settings().llm_provider.value.clone())]
         llm_provider: String,
         /// Provide the model to use for query embedding
         #[clap(short, long)]
-        #[clap(default_value = EMBEDDING_MODEL)]
+        #[clap(default_value_t = settings().embed_model.value.clone())]
         embed_model: String,
         /// Provide the API endpoint to use
         #[clap(short = 'u', long)]
-        #[clap(default_value = CHAT_API_URL)]
+        #[clap(default_value_t = settings().api_url.value.clone())]
         api_url: String,
-        /// Provide the API key to use
+        /// Provide the API key to use, `env:VAR`, `file:PATH` and `cmd:COMMAND` load it from that source,
+        /// defaults to the configured key
         #[clap(short = 'k', long)]
-        #[clap(default_value = CHAT_API_KEY)]
-        api_key: String,
+        api_key: Option<String>,
     },
     /// Query the Lance Vector Database
     LanceQuery {
@@ -70,19 +106,19 @@ pub enum Commands {
         input: Vec<String>,
         /// Provide the provider to use for query embedding
         #[clap(short = 'p', long)]
-        #[clap(default_value = "ollama")]
+        #[clap(default_value_t = settings().llm_provider.value.clone())]
         llm_provider: String,
         /// Provide the API endpoint to use
         #[clap(short = 'u', long)]
-        #[clap(default_value = CHAT_API_URL)]
+        #[clap(default_value_t = settings().api_url.value.clone())]
         api_url: String,
-        /// Provide the API key to use
+        /// Provide the API key to use, `env:VAR`, `file:PATH` and `cmd:COMMAND` load it from that source,
+        /// defaults to the configured key
         #[clap(short = 'k', long)]
-        #[clap(default_value = CHAT_API_KEY)]
-        api_key: String,
+        api_key: Option<String>,
         /// Provide the model to use for query embedding
         #[clap(short, long)]
-        #[clap(default_value = EMBEDDING_MODEL)]
+        #[clap(default_value_t = settings().embed_model.value.clone())]
         model: String,
         /// Provide the table to use to query
         #[clap(short, long)]
@@ -98,6 +134,19 @@ pub enum Commands {
         #[clap(short, long)]
         #[clap(default_value = "false")]
         file_context: String,
+        /// Exact name of a definition e.g. `run_embedding_pipeline` to match the chunks on
+        #[clap(long)]
+        symbol: Option<String>,
+        /// `boost` puts the chunks with the symbol first, `filter` keeps only them
+        #[clap(long)]
+        #[clap(default_value = "boost")]
+        symbol_match: String,
+        /// Only log chunks at or after this time e.g. `2024-05-01` or `2024-05-01T12:00:00Z`
+        #[clap(long)]
+        since: Option<String>,
+        /// Only log chunks before this time
+        #[clap(long)]
+        until: Option<String>,
     },
     /// Query the Lance Vector Database and chat with the AI
     RagQuery {
@@ -106,23 +155,23 @@ pub enum Commands {
         input: Vec<String>,
         /// Provide the model to use for query embedding
         #[clap(short = 'p', long)]
-        #[clap(default_value = "ollama")]
+        #[clap(default_value_t = settings().llm_provider.value.clone())]
         llm_provider: String,
         /// Provide the model to use for query embedding
         #[clap(short, long)]
-        #[clap(default_value = EMBEDDING_MODEL)]
+        #[clap(default_value_t = settings().embed_model.value.clone())]
         embed_model: String,
         /// Provide the API endpoint to use
         #[clap(short = 'u', long)]
-        #[clap(default_value = CHAT_API_URL)]
+        #[clap(default_value_t = settings().api_url.value.clone())]
         api_url: String,
-        /// Provide the API key to use
+        /// Provide the API key to use, `env:VAR`, `file:PATH` and `cmd:COMMAND` load it from that source,
+        /// defaults to the configured key
         #[clap(short = 'k', long)]
-        #[clap(default_value = CHAT_API_KEY)]
-        api_key: String,
+        api_key: Option<String>,
         /// Provide the AI model to use for generation
         #[clap(short, long)]
-        #[clap(default_value = AI_MODEL)]
+        #[clap(default_value_t = settings().ai_model.value.clone())]
         ai_model: String,
         /// Provide the table to use to query
         #[clap(short, long)]
@@ -138,9 +187,22 @@ pub enum Commands {
         #[clap(short, long)]
         #[clap(default_value = "false")]
         file_context: String,
+        /// Exact name of a definition e.g. `run_embedding_pipeline` to match the chunks on
+        #[clap(long)]
+        symbol: Option<String>,
+        /// `boost` puts the chunks with the symbol first, `filter` keeps only them
+        #[clap(long)]
+        #[clap(default_value = "boost")]
+        symbol_match: String,
+        /// Only log chunks at or after this time e.g. `2024-05-01` or `2024-05-01T12:00:00Z`
+        #[clap(long)]
+        since: Option<String>,
+        /// Only log chunks before this time
+        #[clap(long)]
+        until: Option<String>,
         /// specify if the system prompt is to be used default is false
         #[clap(short, long)]
-        #[clap(default_value = SYSTEM_PROMPT_PATH)]
+        #[clap(default_value_t = settings().system_prompt.value.clone())]
         system_prompt: String,
         /// continue flag to continue the conversation
         #[clap(short, long)]
@@ -154,27 +216,103 @@ pub enum Commands {
         prompt: String,
         /// Provide the model to use for query embedding
         #[clap(short = 'p', long)]
-        #[clap(default_value = "ollama")]
+        #[clap(default_value_t = settings().llm_provider.value.clone())]
         llm_provider: String,
         /// Provide the API endpoint to use
         #[clap(short, long)]
-        #[clap(default_value = CHAT_API_URL)]
+        #[clap(default_value_t = settings().api_url.value.clone())]
         api_url: String,
-        /// Provide the API key to use
+        /// Provide the API key to use, `env:VAR`, `file:PATH` and `cmd:COMMAND` load it from that source,
+        /// defaults to the configured key
         #[clap(short, long)]
-        #[clap(default_value = CHAT_API_KEY)]
-        api_key: String,
+        api_key: Option<String>,
         /// Provide the AI model to use for generation
         #[clap(short, long)]
-        #[clap(default_value = AI_MODEL)]
+        #[clap(default_value_t = settings().ai_model.value.clone())]
         ai_model: String,
     },
 
+    /// Inspect the effective configuration
+    Config {
+        #[clap(subcommand)]
+        command: ConfigCommands,
+    },
+
+    /// List and test the provider profiles
+    Profiles {
+        #[clap(subcommand)]
+        command: ProfileCommands,
+    },
+
+    /// Show and prune the embedding cache of a database
+    Cache {
+        #[clap(subcommand)]
+        command: CacheCommands,
+    },
+
     /// Exit the application
     Exit,
     Man,
 }
 
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Print the effective config values and where each came from
+    Show {
+        /// Override the LLM provider
+        #[clap(long)]
+        llm_provider: Option<String>,
+        /// Override the API endpoint
+        #[clap(long)]
+        api_url: Option<String>,
+        /// Override the API key, `env:VAR`, `file:PATH` and `cmd:COMMAND` load it from that source
+        #[clap(long)]
+        api_key: Option<String>,
+        /// Override the embedding model
+        #[clap(long)]
+        embed_model: Option<String>,
+        /// Override the AI model
+        #[clap(long)]
+        ai_model: Option<String>,
+        /// Override the system prompt path
+        #[clap(long)]
+        system_prompt: Option<String>,
+        /// Override the chunk size
+        #[clap(long)]
+        chunk_size: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCommands {
+    /// List the available profiles
+    List,
+    /// Check the profile endpoint is reachable with its API key
+    Test {
+        /// The name of the profile to test
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Show the cached models with their size and hit rate
+    Stats {
+        /// The database the cache belongs to
+        #[clap(short, long)]
+        database: String,
+    },
+    /// Remove the cached embeddings of models no longer in use
+    Prune {
+        /// The database the cache belongs to
+        #[clap(short, long)]
+        database: String,
+        /// The embedding model to remove, can be repeated
+        #[clap(short, long, required = true)]
+        model: Vec<String>,
+    },
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, ValueEnum)]
 pub enum LogLevel {
     Debug,
@@ -236,6 +374,8 @@ pub fn run_app(args: Args, rt: Runtime) -> Result<()> {
         let commands =
             cli_interactive::interactive_cli().context("Failed to run interactive CLI")?;
         cli::cli(commands, rt).context("Failed to run interactive Command")?;
+    } else if let Some(commands) = args.cmd {
+        cli::cli(commands, rt).context("Failed to run Command")?;
     } else {
         info!("Running in agent mode");
         crate::agent_interactive::interactive_cli(&rt)
@@ -260,15 +400,27 @@ pub fn build_args() -> Commands {
         }
     }
 
-    args.cmd.map_or_else(
-        || {
-            info!("No subcommand provided. Use --help for more information.");
-            Commands::Version {
-                version: configs::constants::VERSION.to_string(),
-            }
-        },
-        |cmd: Commands| cmd,
-    )
+    args.cmd.unwrap_or_else(|| {
+        info!("No subcommand provided. Use --help for more information.");
+        Commands::Version {
+            version: configs::constants::VERSION.to_string(),
+        }
+    })
+}
+
+/// Find the `--profile` flag before clap parses the arguments,
+/// the profile has to be loaded first as it provides the command defaults.
+pub fn profile_from_args<I: IntoIterator<Item = String>>(args: I) -> Option<String> {
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            return args.next();
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+    }
+    None
 }
 
 /// Generic function to fetch a value from the command line if not provided as an argument.
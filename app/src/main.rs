@@ -5,6 +5,10 @@ use clap::Parser;
 fn main() -> Result<()> {
     println!("Starting Application");
 
+    // Load the layered config before parsing so it can supply the cli defaults
+    let profile = commands::profile_from_args(std::env::args());
+    configs::settings::init(profile).context("Failed to load config")?;
+
     let args = Args::parse();
 
     let rt = tokio::runtime::Builder::new_multi_thread()
@@ -24,6 +24,15 @@ mod tests {
             Commands::Load {
                 path,
                 chunk_size,
+                chunk_sizer,
+                chunk_overlap,
+                embed_mode,
+                batch_size,
+                max_in_flight,
+                include,
+                exclude,
+                max_file_size,
+                parallelism,
                 llm_provider,
                 embed_model,
                 api_url,
@@ -32,6 +41,15 @@ mod tests {
                 println!("Load command");
                 println!("Path: {:?}", path);
                 println!("Chunk size: {:?}", chunk_size);
+                println!("Chunk sizer: {:?}", chunk_sizer);
+                println!("Chunk overlap: {:?}", chunk_overlap);
+                println!("Embed mode: {:?}", embed_mode);
+                println!("Batch size: {:?}", batch_size);
+                println!("Max in flight: {:?}", max_in_flight);
+                println!("Include: {:?}", include);
+                println!("Exclude: {:?}", exclude);
+                println!("Max file size: {:?}", max_file_size);
+                println!("Parallelism: {:?}", parallelism);
                 println!("LLM Provider: {:?}", llm_provider);
                 println!("Embed Model: {:?}", embed_model);
                 println!("API URL: {:?}", api_url);
@@ -47,6 +65,10 @@ mod tests {
                 database,
                 whole_query,
                 file_context,
+                symbol,
+                symbol_match,
+                since,
+                until,
             } => {
                 println!("Lance Query command");
                 println!("Query: {:?}", input);
@@ -58,6 +80,8 @@ mod tests {
                 println!("Database: {:?}", database);
                 println!("Whole Query: {:?}", whole_query);
                 println!("File Context: {:?}", file_context);
+                println!("Symbol: {:?} ({})", symbol, symbol_match);
+                println!("Time Window: {:?} - {:?}", since, until);
             }
             Commands::RagQuery {
                 input,
@@ -70,6 +94,10 @@ mod tests {
                 database,
                 whole_query,
                 file_context: file_query,
+                symbol,
+                symbol_match,
+                since,
+                until,
                 system_prompt,
                 continue_chat,
             } => {
@@ -85,6 +113,8 @@ mod tests {
                 println!("Database: {:?}", database);
                 println!("Whole Query: {:?}", whole_query);
                 println!("File Query: {:?}", file_query);
+                println!("Symbol: {:?} ({})", symbol, symbol_match);
+                println!("Time Window: {:?} - {:?}", since, until);
                 println!("System Prompt: {:?}", system_prompt);
                 println!("Continue Chat: {:?}", continue_chat);
             }
@@ -102,6 +132,18 @@ mod tests {
                 println!("API Key: {:?}", api_key);
                 println!("AI Model: {:?}", ai_model);
             }
+            Commands::Config { command } => {
+                println!("Config command");
+                println!("Command: {:?}", command);
+            }
+            Commands::Profiles { command } => {
+                println!("Profiles command");
+                println!("Command: {:?}", command);
+            }
+            Commands::Cache { command } => {
+                println!("Cache command");
+                println!("Command: {:?}", command);
+            }
             Commands::Exit => {
                 println!("Exit command");
             }
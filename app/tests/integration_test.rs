@@ -14,10 +14,19 @@ mod integration_tests {
         let commands = Commands::Load {
             path: "tests/resources/sample/".to_string(),
             chunk_size: "512".to_string(), // provide realistic test value
+            chunk_sizer: "characters".to_string(),
+            chunk_overlap: None,
+            embed_mode: "chunk".to_string(),
+            batch_size: "16".to_string(),
+            max_in_flight: "4".to_string(),
+            include: vec![],
+            exclude: vec![],
+            max_file_size: "0".to_string(),
+            parallelism: "0".to_string(),
             llm_provider: "ollama".to_string(),
             embed_model: EMBEDDING_MODEL.to_string(),
             api_url: CHAT_API_URL.to_string(),
-            api_key: CHAT_API_KEY.to_string(),
+            api_key: Some(CHAT_API_KEY.to_string()),
         };
 
         // Execute the load command
@@ -35,12 +44,16 @@ mod integration_tests {
             llm_provider: "ollama".to_string(),
             embed_model: EMBEDDING_MODEL.to_string(),
             api_url: CHAT_API_URL.to_string(),
-            api_key: CHAT_API_KEY.to_string(),
+            api_key: Some(CHAT_API_KEY.to_string()),
             ai_model: AI_MODEL.to_string(), // adjust based on running model
             table: "sample_table".to_string(),
             database: "sample_db".to_string(),
             whole_query: "false".to_string(),
             file_context: "false".to_string(),
+            symbol: None,
+            symbol_match: "boost".to_string(),
+            since: None,
+            until: None,
             system_prompt: "tests/resources/rag_prompt.txt".to_string(), // your actual prompt
             continue_chat: "false".to_string(),
         };
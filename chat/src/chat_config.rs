@@ -3,6 +3,7 @@ use crate::prompt_template::Prompt;
 use anyhow::Context;
 use anyhow::Result;
 use configs::constants::{self, OPEN_AI_CHAT_API, OPEN_AI_URL};
+use configs::secret::Secret;
 pub use configs::LLMProvider;
 use log::debug;
 use serde::{Deserialize, Serialize};
@@ -60,13 +61,13 @@ impl ChatMessage {
     }
 }
 
-/// ChatRequest
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// ChatRequest settings of a chat, only the `ChatBody` is sent to the provider
+#[derive(Clone, Debug)]
 pub struct ChatRequest {
     pub provider: LLMProvider,
     pub model: String,
     pub api_url: String,
-    pub api_key: String,
+    pub api_key: Secret,
     pub messages: Vec<ChatMessage>,
     pub stream: bool,
     pub format: String,
@@ -91,7 +92,7 @@ impl ChatRequest {
         provider: &str,
         model: &str,
         api_url: String,
-        api_key: String,
+        api_key: Secret,
         stream: bool,
         format: String,
         options: Option<Options>,
@@ -10,10 +10,12 @@ use chat_config::ChatResponse;
 use chat_config::ChatResponseTrait;
 use chat_config::OpenAiResponse;
 use configs::constants::CHAT_RESPONSE_FORMAT;
+use configs::secret::{redact_header, Secret};
 use configs::HttpsClient;
 use configs::LLMProvider;
 use http_body_util::Full;
 use log::{debug, info};
+use model_options::Options;
 use std::io::Write;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -35,6 +37,7 @@ pub async fn run_chat(
     api_url: &str,
     api_key: &str,
     ai_model: &str,
+    options: Option<Options>,
 ) -> anyhow::Result<()> {
     info!("Starting LLM chat...");
 
@@ -60,10 +63,10 @@ pub async fn run_chat(
         provider,
         ai_model,
         api_url.to_string(),
-        api_key.to_string(),
+        Secret::new(api_key),
         false,
         CHAT_RESPONSE_FORMAT.to_string(),
-        None,
+        options,
         prompt,
     );
 
@@ -103,6 +106,7 @@ pub async fn run_chat_with_history(
     api_url: &str,
     api_key: &str,
     ai_model: &str,
+    options: Option<Options>,
     input_fn: fn() -> String,
     continue_chat: bool,
 ) -> anyhow::Result<()> {
@@ -121,18 +125,14 @@ pub async fn run_chat_with_history(
             .await
             .context("Failed to create prompt")?;
 
-        // @TODO : Add this as command line argument
-        // let options = model_options::OptionsBuilder::new().num_ctx(128000).build();
-        // let options = None;
-
         let chat_request = chat_config::ChatRequest::new(
             provider,
             ai_model,
             api_url.to_string(),
-            api_key.to_string(),
+            Secret::new(api_key),
             false,
             CHAT_RESPONSE_FORMAT.to_string(),
-            None,
+            options.clone(),
             prompt,
         );
 
@@ -216,10 +216,21 @@ pub async fn ai_chat(
         .method("POST")
         .uri(&chat_url)
         .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", chat_request.api_key))
+        .header(
+            "Authorization",
+            format!("Bearer {}", chat_request.api_key.expose()),
+        )
         .body(request_body)
         .context("Failed to build request")?;
 
+    for (name, value) in request.headers() {
+        debug!(
+            "Chat Request Header {}: {}",
+            name,
+            redact_header(name.as_str(), value.to_str().unwrap_or_default())
+        );
+    }
+
     // Send the request and await the response.
     let response = http_client.request(request).await?;
     if response.status() != 200 {
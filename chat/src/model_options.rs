@@ -58,6 +58,23 @@ pub struct Options {
     num_thread: Option<i32>,
 }
 
+impl Options {
+    /// Build the options from a JSON value e.g. the `options` table of a profile
+    pub fn from_value(value: serde_json::Value) -> anyhow::Result<Options> {
+        serde_json::from_value(value).map_err(|e| anyhow::anyhow!("Invalid model options: {}", e))
+    }
+
+    /// Get the model options from the effective settings if any are configured
+    pub fn from_settings() -> anyhow::Result<Option<Options>> {
+        configs::settings::settings()
+            .options
+            .value
+            .clone()
+            .map(Options::from_value)
+            .transpose()
+    }
+}
+
 pub struct OptionsBuilder {
     options: Options,
 }
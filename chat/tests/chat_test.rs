@@ -45,6 +45,7 @@ mod tests {
             api_url,
             api_key,
             ai_model,
+            None,
         )
         .await
         .unwrap();
@@ -1,6 +1,6 @@
 // constants
 #[allow(dead_code)]
-pub const EMBEDDING_URL: &str = "http://10.0.0.213:11434/api/embed"; // @TODO: Change this to the url plus the endpoint
+pub const EMBEDDING_URL: &str = "http://localhost:11434/api/embed"; // @TODO: Change this to the url plus the endpoint
 pub const EMBEDDING_MODEL: &str = "nomic-embed-text";
 
 // pub const VECTOR_DB_DIM_STR: &str = "768";
@@ -8,7 +8,7 @@ pub const VECTOR_DB_DIM_SIZE: i32 = 768;
 pub const VERSION: &str = "1.0.0";
 // pub const QUERY_LIMIT: i64 = 1;
 pub const LANCEDB_DISTANCE_FN: lancedb::DistanceType = lancedb::DistanceType::L2;
-pub const CHAT_API_URL: &str = "http://10.0.0.213:11434";
+pub const CHAT_API_URL: &str = "http://localhost:11434";
 pub const CHAT_API_KEY: &str = "api_key";
 pub const CHAT_RESPONSE_FORMAT: &str = "json";
 pub const SYSTEM_PROMPT_PATH: &str = "app/src/resources/rag_prompt.txt";
@@ -20,4 +20,35 @@ pub const OPEN_AI_URL: &str = "https://api.openai.com";
 pub const OPEN_AI_CHAT_API: &str = "v1/chat/completions";
 pub const OPEN_AI_EMBED_API: &str = "v1/embeddings";
 
-// pub const DEFAULT_CHUNK_SIZE: usize = 2048;
+pub const DEFAULT_CHUNK_SIZE: usize = 2048;
+pub const DEFAULT_CHUNK_SIZER: &str = "characters";
+// overlap between neighbouring chunks, capped at a quarter of the chunk size
+pub const DEFAULT_CHUNK_OVERLAP: usize = 256;
+pub const DEFAULT_EMBED_MODE: &str = "chunk";
+pub const DEFAULT_EMBED_BATCH_SIZE: usize = 16;
+pub const DEFAULT_EMBED_MAX_IN_FLIGHT: usize = 4;
+// files above this size in bytes are skipped while loading, 0 is unlimited
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+// files read and split at the same time while loading, 0 uses the number of CPUs
+pub const DEFAULT_LOAD_PARALLELISM: usize = 0;
+// bytes an archive may expand to while loading, loading stops at the limit, 0 is unlimited
+pub const DEFAULT_MAX_ARCHIVE_SIZE: u64 = 100 * 1024 * 1024;
+// entries read from an archive while loading, loading stops at the limit, 0 is unlimited
+pub const DEFAULT_MAX_ARCHIVE_ENTRIES: usize = 10_000;
+// timestamp starting a log entry e.g. `2024-05-01 12:00:00,123`, `2024-05-01T12:00:00Z` or spark `24/05/01 12:00:00`
+pub const DEFAULT_LOG_TIMESTAMP_PATTERN: &str = r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(?:[.,]\d{1,9})?(?:Z|[+-]\d{2}:?\d{2})?|\d{2}/\d{2}/\d{2} \d{2}:\d{2}:\d{2}";
+// severity of a log entry, the first match after the timestamp wins
+pub const DEFAULT_LOG_LEVEL_PATTERN: &str =
+    r"(?i)\b(?:TRACE|DEBUG|INFO|NOTICE|WARN(?:ING)?|ERROR|FATAL|CRITICAL|SEVERE|PANIC)\b";
+// rows buffered before a write to the lance table, larger writes mean fewer fragments
+pub const EMBED_WRITE_BUFFER_ROWS: usize = 2048;
+pub const DEFAULT_LLM_PROVIDER: &str = "ollama";
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+// chat generation on a local model can take minutes
+pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 300;
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+pub const OLLAMA_VERSION_API: &str = "api/version";
+pub const OPEN_AI_MODELS_API: &str = "v1/models";
+pub const OPEN_AI_EMBED_MODEL: &str = "text-embedding-3-small";
+pub const OPEN_AI_CHAT_MODEL: &str = "gpt-4o-mini";
@@ -1,42 +1,27 @@
 pub mod constants;
+pub mod profile;
+pub mod retry;
+pub mod secret;
+pub mod settings;
+pub mod transport;
 use crate::constants::{CHAT_API_URL, OPEN_AI_URL};
 use anyhow::anyhow;
-use http_body_util::Full;
-use hyper::body::Bytes;
-use hyper_rustls::HttpsConnector;
-use hyper_util::client::legacy::connect::HttpConnector;
-use hyper_util::client::legacy::Client as LegacyClient;
-use hyper_util::rt::TokioExecutor;
-use log::debug;
-use rustls::crypto::ring::default_provider;
 use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
 
-pub type HttpsClient = LegacyClient<HttpsConnector<HttpConnector>, Full<Bytes>>;
+pub use transport::{HttpsClient, HttpsClientBuilder, ProxyConfig};
 
-/// creates an HTTPS client with native roots
+static HTTPS_CLIENT: OnceLock<HttpsClient> = OnceLock::new();
+
+/// Get the shared HTTPS client configured from the settings, safe to call more than once,
+/// every call returns a clone sharing the same connection pool.
 /// Returns: HttpsClient
 pub fn get_https_client() -> anyhow::Result<HttpsClient> {
-    // Install the crypto provider required by rustls
-    match default_provider().install_default() {
-        anyhow::Result::Ok(_) => debug!("Crypto provider installed successfully"),
-        Err(e) => {
-            return Err(anyhow::anyhow!(
-                "Failed to install crypto provider: {:?}",
-                e
-            ));
-        }
+    if let Some(client) = HTTPS_CLIENT.get() {
+        return anyhow::Ok(client.clone());
     }
-
-    // HTTPS connector with native roots
-    let https = hyper_rustls::HttpsConnectorBuilder::new()
-        .with_native_roots()?
-        .https_or_http()
-        .enable_http1()
-        .build();
-
-    // Build the hyper client from the HTTPS connector
-    let client: HttpsClient = LegacyClient::builder(TokioExecutor::new()).build(https);
-    anyhow::Ok(client)
+    let client = HttpsClientBuilder::from_settings(settings::settings())?.build()?;
+    anyhow::Ok(HTTPS_CLIENT.get_or_init(|| client).clone())
 }
 
 /// LLMProvider supported enum of LLM providers. @TODO Embedding provider only ollama
@@ -56,9 +41,14 @@ impl LLMProvider {
         }
     }
 
+    /// Get the api url for the provider, the configured api url is used when it belongs to the provider
     pub fn get_api_url(provider: &str) -> anyhow::Result<String> {
         let provider =
             LLMProvider::get_provider(provider).map_err(|_| anyhow!("Unsupported provider"))?;
+        let settings = settings::settings();
+        if LLMProvider::get_provider(&settings.llm_provider.value).ok() == Some(provider.clone()) {
+            return anyhow::Ok(settings.api_url.value.clone());
+        }
         match provider {
             LLMProvider::OpenAI => anyhow::Ok(OPEN_AI_URL.to_string()),
             LLMProvider::Ollama => anyhow::Ok(CHAT_API_URL.to_string()),
@@ -0,0 +1,115 @@
+use crate::constants::{
+    AI_MODEL, CHAT_API_KEY, CHAT_API_URL, EMBEDDING_MODEL, OPEN_AI_CHAT_MODEL, OPEN_AI_EMBED_MODEL,
+    OPEN_AI_URL,
+};
+use crate::secret::{Secret, SecretSource};
+use crate::LLMProvider;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Profile a named bundle of provider, endpoint, key source, default models and model options.
+/// Profiles are defined in the config file under `[profiles.<name>]`.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub provider: String,
+    pub api_url: String,
+    /// Literal API key, prefer one of the other key sources to keep keys out of config files
+    pub api_key: Option<Secret>,
+    /// Name of the environment variable holding the API key
+    pub api_key_env: Option<String>,
+    /// Path of a file holding the API key
+    pub api_key_file: Option<String>,
+    /// Command printing the API key e.g. `pass show openai`
+    pub api_key_command: Option<String>,
+    pub embed_model: Option<String>,
+    pub chat_model: Option<String>,
+    /// Model options passed to the chat request e.g. `{ temperature = 0.2, num_ctx = 8192 }`
+    pub options: Option<serde_json::Value>,
+}
+
+impl Profile {
+    /// Parse the provider name of the profile
+    pub fn llm_provider(&self) -> Result<LLMProvider> {
+        LLMProvider::get_provider(&self.provider)
+    }
+
+    /// Source of the API key in order env variable, file, command and literal key
+    pub fn api_key_source(&self) -> Option<SecretSource> {
+        if let Some(var) = &self.api_key_env {
+            Some(SecretSource::Env(var.clone()))
+        } else if let Some(path) = &self.api_key_file {
+            Some(SecretSource::File(path.clone()))
+        } else if let Some(command) = &self.api_key_command {
+            Some(SecretSource::Command(command.clone()))
+        } else {
+            self.api_key.clone().map(SecretSource::Literal)
+        }
+    }
+
+    /// Resolve the API key from the key source in order env variable, file, command and literal key
+    pub fn resolve_api_key(&self) -> Result<Option<Secret>> {
+        self.api_key_source()
+            .map(|source| source.resolve().context("Failed to read the API key"))
+            .transpose()
+    }
+
+    /// Describe where the API key comes from without revealing it
+    pub fn key_source(&self) -> String {
+        if let Some(var) = &self.api_key_env {
+            format!("env {}", var)
+        } else if let Some(path) = &self.api_key_file {
+            format!("file {}", path)
+        } else if self.api_key_command.is_some() {
+            "command".to_string()
+        } else if self.api_key.is_some() {
+            "config".to_string()
+        } else {
+            "none".to_string()
+        }
+    }
+
+    /// Check the profile can be used e.g. the provider is supported
+    pub fn validate(&self, name: &str) -> Result<()> {
+        self.llm_provider()
+            .with_context(|| format!("Invalid provider in profile {}", name))?;
+        if self.api_url.is_empty() {
+            return Err(anyhow!("Profile {} has an empty api_url", name));
+        }
+        Ok(())
+    }
+}
+
+/// Built-in profiles available without a config file, config file profiles with the same name replace them.
+pub fn builtin_profiles() -> BTreeMap<String, Profile> {
+    let mut profiles = BTreeMap::new();
+    profiles.insert(
+        "local-ollama".to_string(),
+        Profile {
+            provider: "ollama".to_string(),
+            api_url: CHAT_API_URL.to_string(),
+            api_key: Some(Secret::new(CHAT_API_KEY)),
+            api_key_env: None,
+            api_key_file: None,
+            api_key_command: None,
+            embed_model: Some(EMBEDDING_MODEL.to_string()),
+            chat_model: Some(AI_MODEL.to_string()),
+            options: None,
+        },
+    );
+    profiles.insert(
+        "openai".to_string(),
+        Profile {
+            provider: "openai".to_string(),
+            api_url: OPEN_AI_URL.to_string(),
+            api_key: None,
+            api_key_env: Some("OPENAI_API_KEY".to_string()),
+            api_key_file: None,
+            api_key_command: None,
+            embed_model: Some(OPEN_AI_EMBED_MODEL.to_string()),
+            chat_model: Some(OPEN_AI_CHAT_MODEL.to_string()),
+            options: None,
+        },
+    );
+    profiles
+}
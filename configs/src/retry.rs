@@ -0,0 +1,229 @@
+use http::{HeaderMap, StatusCode};
+use rand::Rng;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+/// RetryPolicy exponential backoff with jitter for failed requests
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, 0 disables retries
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 0,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Status codes worth another attempt, the server is overloaded or temporarily unavailable
+    pub fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// Delay before the retry `attempt` (starting at 1), `Retry-After` from the server wins
+    /// over the backoff but is capped by `max_delay`
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        // equal jitter keeps at least half of the backoff and spreads the rest
+        let half = exp / 2;
+        half + rand::thread_rng().gen_range(Duration::ZERO..=half)
+    }
+}
+
+/// Parse the `Retry-After` header as delay seconds or an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// RateLimit client side limits applied to each provider host
+#[derive(Debug, Clone, Default)]
+pub struct RateLimit {
+    /// Requests started per second, 0 is unlimited
+    pub requests_per_second: f64,
+    /// Requests in flight at the same time, 0 is unlimited
+    pub max_concurrency: usize,
+}
+
+/// HostLimiter spaces the requests to a host and bounds how many run at once
+#[derive(Debug)]
+struct HostLimiter {
+    interval: Option<Duration>,
+    next_slot: Mutex<Instant>,
+    semaphore: Option<Arc<Semaphore>>,
+}
+
+/// RateLimiter keeps one limiter per host so providers do not slow each other down
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    hosts: Mutex<HashMap<String, Arc<HostLimiter>>>,
+}
+
+/// Permit held while the request is in flight
+pub struct RatePermit {
+    _permit: Option<OwnedSemaphorePermit>,
+    /// The request had to wait for the limiter
+    pub waited: bool,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            limit,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn host(&self, host: &str) -> Arc<HostLimiter> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts
+            .entry(host.to_string())
+            .or_insert_with(|| {
+                Arc::new(HostLimiter {
+                    interval: (self.limit.requests_per_second > 0.0)
+                        .then(|| Duration::from_secs_f64(1.0 / self.limit.requests_per_second)),
+                    next_slot: Mutex::new(Instant::now()),
+                    semaphore: (self.limit.max_concurrency > 0)
+                        .then(|| Arc::new(Semaphore::new(self.limit.max_concurrency))),
+                })
+            })
+            .clone()
+    }
+
+    /// Wait for a concurrency permit and the next request slot of the host
+    pub async fn acquire(&self, host: &str) -> RatePermit {
+        let limiter = self.host(host);
+        let mut waited = false;
+
+        let permit = match &limiter.semaphore {
+            Some(semaphore) => match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    waited = true;
+                    semaphore.clone().acquire_owned().await.ok()
+                }
+            },
+            None => None,
+        };
+
+        if let Some(interval) = limiter.interval {
+            let slot = {
+                let mut next = limiter.next_slot.lock().unwrap_or_else(|e| e.into_inner());
+                let slot = (*next).max(Instant::now());
+                *next = slot + interval;
+                slot
+            };
+            if slot > Instant::now() {
+                waited = true;
+                tokio::time::sleep_until(slot).await;
+            }
+        }
+
+        RatePermit {
+            _permit: permit,
+            waited,
+        }
+    }
+}
+
+/// TransportStats counts requests, retries and rate limit waits, shared by the client clones
+#[derive(Debug, Default)]
+pub struct TransportStats {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    throttled: AtomicU64,
+    rate_limited: AtomicU64,
+    failures: AtomicU64,
+}
+
+impl TransportStats {
+    pub(crate) fn request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The server answered 429 Too Many Requests
+    pub(crate) fn throttled(&self) {
+        self.throttled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The client side limiter delayed a request
+    pub(crate) fn rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// StatsSnapshot point in time copy of the transport counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsSnapshot {
+    pub requests: u64,
+    pub retries: u64,
+    pub throttled: u64,
+    pub rate_limited: u64,
+    pub failures: u64,
+}
+
+impl fmt::Display for StatsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "HTTP requests: {}, retries: {}, throttled (429): {}, rate limited: {}, failed: {}",
+            self.requests, self.retries, self.throttled, self.rate_limited, self.failures
+        )
+    }
+}
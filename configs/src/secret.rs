@@ -0,0 +1,173 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+
+const REDACTED: &str = "********";
+
+/// Secret a value such as an API key that is never printed by `Debug` or `Display`. It doesn't
+/// implement `Serialize` so a struct holding it can't be sent or saved with the key masked.
+/// Use `expose` only where the value has to be sent e.g. the `Authorization` header.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    /// Access the secret value
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Read the secret from an environment variable
+    pub fn from_env(var: &str) -> Result<Self> {
+        let value = std::env::var(var)
+            .with_context(|| format!("Environment variable {} is not set", var))?;
+        Ok(Secret(value))
+    }
+
+    /// Read the secret from a file, surrounding whitespace is trimmed
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let value = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read secret file {}", path.display()))?;
+        Ok(Secret(value.trim().to_string()))
+    }
+
+    /// Read the secret from the first line printed by a shell command e.g. `pass show openai`
+    pub fn from_command(command: &str) -> Result<Self> {
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .output()
+            .with_context(|| format!("Failed to run secret command {}", command))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "Secret command {} exited with {}",
+                command,
+                output.status
+            ));
+        }
+        let stdout =
+            String::from_utf8(output.stdout).context("Secret command output is not UTF-8")?;
+        let value = stdout.lines().next().unwrap_or_default().trim();
+        Ok(Secret(value.to_string()))
+    }
+
+    /// Resolve a key source spec, `env:VAR`, `file:PATH` and `cmd:COMMAND` are loaded
+    /// from their source, any other value is used as the literal secret.
+    pub fn resolve(spec: &str) -> Result<Self> {
+        SecretSource::parse(spec).resolve()
+    }
+}
+
+/// SecretSource where a secret is loaded from, it is only resolved where the secret is used
+/// so a `cmd:` source doesn't run for commands that never send the key
+#[derive(Clone, PartialEq, Eq)]
+pub enum SecretSource {
+    Literal(Secret),
+    /// Name of the environment variable
+    Env(String),
+    /// Path of the file
+    File(String),
+    /// Shell command printing the secret
+    Command(String),
+}
+
+impl SecretSource {
+    /// Parse a key source spec `env:VAR`, `file:PATH`, `cmd:COMMAND` or a literal secret
+    pub fn parse(spec: &str) -> Self {
+        if let Some(var) = spec.strip_prefix("env:") {
+            SecretSource::Env(var.to_string())
+        } else if let Some(path) = spec.strip_prefix("file:") {
+            SecretSource::File(path.to_string())
+        } else if let Some(command) = spec.strip_prefix("cmd:") {
+            SecretSource::Command(command.to_string())
+        } else {
+            SecretSource::Literal(Secret::new(spec))
+        }
+    }
+
+    /// Load the secret, a command runs again on every call
+    pub fn resolve(&self) -> Result<Secret> {
+        match self {
+            SecretSource::Literal(secret) => Ok(secret.clone()),
+            SecretSource::Env(var) => Secret::from_env(var),
+            SecretSource::File(path) => Secret::from_file(path),
+            SecretSource::Command(command) => Secret::from_command(command),
+        }
+    }
+}
+
+impl Default for SecretSource {
+    fn default() -> Self {
+        SecretSource::Literal(Secret::default())
+    }
+}
+
+impl fmt::Debug for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretSource({})", self)
+    }
+}
+
+/// The source without the secret, commands are masked as they may hold a key e.g. `echo sk-...`
+impl fmt::Display for SecretSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SecretSource::Literal(secret) => write!(f, "{}", secret),
+            SecretSource::Env(var) => write!(f, "env:{}", var),
+            SecretSource::File(path) => write!(f, "file:{}", path),
+            SecretSource::Command(_) => write!(f, "cmd:{}", REDACTED),
+        }
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Secret({})", REDACTED)
+    }
+}
+
+impl fmt::Display for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Secret)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Secret(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Secret(value.to_string())
+    }
+}
+
+/// Redact the value of sensitive headers such as `Authorization` before logging them
+pub fn redact_header<'a>(name: &str, value: &'a str) -> &'a str {
+    match name.to_lowercase().as_str() {
+        "authorization" | "proxy-authorization" | "api-key" | "x-api-key" => REDACTED,
+        _ => value,
+    }
+}
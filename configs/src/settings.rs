@@ -0,0 +1,764 @@
+use crate::constants::{
+    AI_MODEL, CHAT_API_KEY, CHAT_API_URL, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_SIZER,
+    DEFAULT_CONNECT_TIMEOUT_SECS, DEFAULT_EMBED_BATCH_SIZE, DEFAULT_EMBED_MAX_IN_FLIGHT,
+    DEFAULT_EMBED_MODE, DEFAULT_LLM_PROVIDER, DEFAULT_LOAD_PARALLELISM, DEFAULT_LOG_LEVEL_PATTERN,
+    DEFAULT_LOG_TIMESTAMP_PATTERN, DEFAULT_MAX_ARCHIVE_ENTRIES, DEFAULT_MAX_ARCHIVE_SIZE,
+    DEFAULT_MAX_FILE_SIZE, DEFAULT_MAX_RETRIES, DEFAULT_REQUEST_TIMEOUT_SECS,
+    DEFAULT_RETRY_BACKOFF_MS, EMBEDDING_MODEL, SYSTEM_PROMPT_PATH, VECTOR_DB_DIM_SIZE,
+};
+use crate::profile::{builtin_profiles, Profile};
+use crate::secret::{Secret, SecretSource};
+use anyhow::{anyhow, Context, Result};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Name of the config file looked up in the user config dir and the project dir.
+pub const CONFIG_FILE_NAME: &str = "rag-agent.toml";
+/// Directory under `~/.config` that holds the user config file.
+pub const CONFIG_DIR_NAME: &str = "rag-agent-rust";
+/// Prefix for the environment variable overrides e.g. `RAG_API_URL`.
+pub const ENV_PREFIX: &str = "RAG_";
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// SettingSource records which layer provided the effective value of a setting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingSource {
+    Default,
+    UserFile(PathBuf),
+    ProjectFile(PathBuf),
+    Env(String),
+    Profile(String),
+    Cli,
+}
+
+impl fmt::Display for SettingSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingSource::Default => write!(f, "default"),
+            SettingSource::UserFile(path) => write!(f, "user file {}", path.display()),
+            SettingSource::ProjectFile(path) => write!(f, "project file {}", path.display()),
+            SettingSource::Env(var) => write!(f, "env {}", var),
+            SettingSource::Profile(name) => write!(f, "profile {}", name),
+            SettingSource::Cli => write!(f, "cli"),
+        }
+    }
+}
+
+/// Setting a single value along with the layer it came from
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: SettingSource,
+}
+
+impl<T> Setting<T> {
+    fn default(value: T) -> Self {
+        Setting {
+            value,
+            source: SettingSource::Default,
+        }
+    }
+
+    fn set(&mut self, value: T, source: SettingSource) {
+        self.value = value;
+        self.source = source;
+    }
+}
+
+/// SettingsFile is the shape of the `rag-agent.toml` file, every key is optional.
+#[derive(Deserialize, Debug, Default)]
+pub struct SettingsFile {
+    pub llm_provider: Option<String>,
+    pub api_url: Option<String>,
+    pub api_key: Option<String>,
+    pub embed_model: Option<String>,
+    /// Output size requested from OpenAI compatible embedding models that support shortening
+    pub embed_dimensions: Option<u32>,
+    pub ai_model: Option<String>,
+    /// Vector size of a table when there is nothing to probe the embedding model with
+    pub vector_dim: Option<i32>,
+    pub system_prompt: Option<String>,
+    pub chunk_size: Option<usize>,
+    /// Unit of `chunk_size`: `characters`, `tiktoken:<encoding or model>` or `hf:<path to tokenizer.json>`
+    pub chunk_sizer: Option<String>,
+    /// Characters or tokens shared by neighbouring chunks
+    pub chunk_overlap: Option<usize>,
+    /// Chunking rules per language or glob, the first matching rule wins
+    #[serde(default)]
+    pub chunking: Vec<ChunkRule>,
+    /// `chunk` embeds each chunk as one vector, `line` embeds every line of a chunk
+    pub embed_mode: Option<String>,
+    /// Chunks sent in a single embedding request
+    pub embed_batch_size: Option<usize>,
+    /// Embedding requests in flight at the same time while loading
+    pub embed_max_in_flight: Option<usize>,
+    /// Files above this size in bytes are skipped while loading, 0 is unlimited
+    pub max_file_size: Option<u64>,
+    /// Files read and split at the same time while loading, 0 uses the number of CPUs
+    pub load_parallelism: Option<usize>,
+    /// Bytes an archive may expand to while loading, 0 is unlimited
+    pub max_archive_size: Option<u64>,
+    /// Entries read from an archive while loading, 0 is unlimited
+    pub max_archive_entries: Option<usize>,
+    /// Regex of the timestamp starting a log entry, a `timestamp` group narrows the match
+    pub log_timestamp_pattern: Option<String>,
+    /// Regex of the severity of a log entry, a `level` group narrows the match
+    pub log_level_pattern: Option<String>,
+    /// Seconds to wait for a connection, 0 disables the timeout
+    pub connect_timeout: Option<u64>,
+    /// Seconds to wait for a response, 0 disables the timeout
+    pub request_timeout: Option<u64>,
+    /// Extra PEM CA bundle trusted for TLS connections
+    pub ca_bundle: Option<String>,
+    /// Offer HTTP/2 for TLS connections
+    pub http2: Option<bool>,
+    /// Retries for 429, 5xx, timeouts and connection errors, 0 disables retries
+    pub max_retries: Option<u32>,
+    /// Base delay of the exponential backoff in milliseconds
+    pub retry_backoff_ms: Option<u64>,
+    /// Requests per second sent to each provider host, 0 is unlimited
+    pub requests_per_second: Option<f64>,
+    /// Requests in flight to each provider host, 0 is unlimited
+    pub max_concurrency: Option<usize>,
+    /// Name of the profile to use when `--profile` is not given
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// ChunkRule chunking of the files matched by `language` or `glob`, keys that are not set
+/// fall back to `chunk_size`, `chunk_overlap` and `chunk_sizer`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ChunkRule {
+    /// Language name e.g. `rust` or a file extension e.g. `rs`
+    pub language: Option<String>,
+    /// Glob matched against the path relative to the loaded directory e.g. `docs/**/*.txt`
+    pub glob: Option<String>,
+    pub size: Option<usize>,
+    pub overlap: Option<usize>,
+    /// `characters`, `tiktoken:<encoding or model>` or `hf:<path to tokenizer.json>`
+    pub sizer: Option<String>,
+    /// `code`, `text`, `markdown` or `keys`, the language decides when not set
+    pub splitter: Option<String>,
+    /// Trim the whitespace around chunks, on by default
+    pub trim: Option<bool>,
+}
+
+impl SettingsFile {
+    pub fn from_toml(content: &str) -> Result<SettingsFile> {
+        toml::from_str(content).context("Failed to parse config file")
+    }
+
+    /// Read a config file, a missing file is not an error and returns None
+    pub fn read(path: &Path) -> Result<Option<SettingsFile>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let file = SettingsFile::from_toml(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        Ok(Some(file))
+    }
+}
+
+/// Settings effective configuration merged from defaults, config files, env and cli flags
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub llm_provider: Setting<String>,
+    pub api_url: Setting<String>,
+    /// Source of the API key, resolved by `resolve_api_key` where the key is sent
+    pub api_key: Setting<SecretSource>,
+    pub embed_model: Setting<String>,
+    pub embed_dimensions: Setting<Option<u32>>,
+    pub ai_model: Setting<String>,
+    pub vector_dim: Setting<i32>,
+    pub system_prompt: Setting<String>,
+    pub chunk_size: Setting<usize>,
+    pub chunk_sizer: Setting<String>,
+    pub chunk_overlap: Setting<Option<usize>>,
+    pub chunking: Setting<Vec<ChunkRule>>,
+    pub embed_mode: Setting<String>,
+    pub embed_batch_size: Setting<usize>,
+    pub embed_max_in_flight: Setting<usize>,
+    pub max_file_size: Setting<u64>,
+    pub load_parallelism: Setting<usize>,
+    pub max_archive_size: Setting<u64>,
+    pub max_archive_entries: Setting<usize>,
+    pub log_timestamp_pattern: Setting<String>,
+    pub log_level_pattern: Setting<String>,
+    pub connect_timeout: Setting<u64>,
+    pub request_timeout: Setting<u64>,
+    pub ca_bundle: Setting<Option<String>>,
+    pub http2: Setting<bool>,
+    pub max_retries: Setting<u32>,
+    pub retry_backoff_ms: Setting<u64>,
+    pub requests_per_second: Setting<f64>,
+    pub max_concurrency: Setting<usize>,
+    pub profile: Setting<Option<String>>,
+    pub options: Setting<Option<serde_json::Value>>,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            llm_provider: Setting::default(DEFAULT_LLM_PROVIDER.to_string()),
+            api_url: Setting::default(CHAT_API_URL.to_string()),
+            api_key: Setting::default(SecretSource::Literal(Secret::new(CHAT_API_KEY))),
+            embed_model: Setting::default(EMBEDDING_MODEL.to_string()),
+            embed_dimensions: Setting::default(None),
+            ai_model: Setting::default(AI_MODEL.to_string()),
+            vector_dim: Setting::default(VECTOR_DB_DIM_SIZE),
+            system_prompt: Setting::default(SYSTEM_PROMPT_PATH.to_string()),
+            chunk_size: Setting::default(DEFAULT_CHUNK_SIZE),
+            chunk_sizer: Setting::default(DEFAULT_CHUNK_SIZER.to_string()),
+            chunk_overlap: Setting::default(None),
+            chunking: Setting::default(Vec::new()),
+            embed_mode: Setting::default(DEFAULT_EMBED_MODE.to_string()),
+            embed_batch_size: Setting::default(DEFAULT_EMBED_BATCH_SIZE),
+            embed_max_in_flight: Setting::default(DEFAULT_EMBED_MAX_IN_FLIGHT),
+            max_file_size: Setting::default(DEFAULT_MAX_FILE_SIZE),
+            load_parallelism: Setting::default(DEFAULT_LOAD_PARALLELISM),
+            max_archive_size: Setting::default(DEFAULT_MAX_ARCHIVE_SIZE),
+            max_archive_entries: Setting::default(DEFAULT_MAX_ARCHIVE_ENTRIES),
+            log_timestamp_pattern: Setting::default(DEFAULT_LOG_TIMESTAMP_PATTERN.to_string()),
+            log_level_pattern: Setting::default(DEFAULT_LOG_LEVEL_PATTERN.to_string()),
+            connect_timeout: Setting::default(DEFAULT_CONNECT_TIMEOUT_SECS),
+            request_timeout: Setting::default(DEFAULT_REQUEST_TIMEOUT_SECS),
+            ca_bundle: Setting::default(None),
+            http2: Setting::default(false),
+            max_retries: Setting::default(DEFAULT_MAX_RETRIES),
+            retry_backoff_ms: Setting::default(DEFAULT_RETRY_BACKOFF_MS),
+            requests_per_second: Setting::default(0.0),
+            max_concurrency: Setting::default(0),
+            profile: Setting::default(None),
+            options: Setting::default(None),
+            profiles: builtin_profiles(),
+        }
+    }
+}
+
+impl Settings {
+    /// Load the settings in order: defaults, user config file, project config file, the selected
+    /// profile and `RAG_*` env vars. `profile` is the `--profile` flag, CLI flags are applied on top
+    /// by the caller.
+    pub fn load(profile: Option<String>) -> Result<Settings> {
+        let mut settings = Settings::default();
+
+        if let Some(path) = user_config_path() {
+            if let Some(file) = SettingsFile::read(&path)? {
+                debug!("Loaded user config file {}", path.display());
+                settings.merge_file(file, SettingSource::UserFile(path))?;
+            }
+        }
+
+        let path = project_config_path();
+        if let Some(file) = SettingsFile::read(&path)? {
+            debug!("Loaded project config file {}", path.display());
+            settings.merge_file(file, SettingSource::ProjectFile(path))?;
+        }
+
+        settings.merge_profile_and_env(profile, std::env::vars())?;
+
+        Ok(settings)
+    }
+
+    /// Apply the profile selected by `--profile`, `RAG_PROFILE` or the config files and then the
+    /// other `RAG_*` env vars so they override the profile
+    /// Arguments:
+    /// - profile: Option<String> the `--profile` flag, it wins over `RAG_PROFILE`
+    /// - vars: environment variables
+    ///
+    /// Returns:
+    /// - Result<()> an error for an unknown profile or an invalid env value
+    pub fn merge_profile_and_env<I>(&mut self, profile: Option<String>, vars: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let (profile_vars, vars): (Vec<_>, Vec<_>) = vars.into_iter().partition(|(name, _)| {
+            name.strip_prefix(ENV_PREFIX)
+                .is_some_and(|key| key.eq_ignore_ascii_case("profile"))
+        });
+        self.merge_env(profile_vars)?;
+        if let Some(name) = profile {
+            self.profile.set(Some(name), SettingSource::Cli);
+        }
+        self.apply_profile()?;
+        self.merge_env(vars)
+    }
+
+    /// Override the settings with the keys present in the config file
+    pub fn merge_file(&mut self, file: SettingsFile, source: SettingSource) -> Result<()> {
+        if let Some(v) = file.llm_provider {
+            self.llm_provider.set(v, source.clone());
+        }
+        if let Some(v) = file.api_url {
+            self.api_url.set(v, source.clone());
+        }
+        if let Some(v) = file.api_key {
+            self.set_api_key(&v, source.clone());
+        }
+        if let Some(v) = file.embed_model {
+            self.embed_model.set(v, source.clone());
+        }
+        if let Some(v) = file.embed_dimensions {
+            self.embed_dimensions.set(Some(v), source.clone());
+        }
+        if let Some(v) = file.ai_model {
+            self.ai_model.set(v, source.clone());
+        }
+        if let Some(v) = file.vector_dim {
+            self.vector_dim.set(v, source.clone());
+        }
+        if let Some(v) = file.system_prompt {
+            self.system_prompt.set(v, source.clone());
+        }
+        if let Some(v) = file.chunk_size {
+            self.chunk_size.set(v, source.clone());
+        }
+        if let Some(v) = file.chunk_sizer {
+            self.chunk_sizer.set(v, source.clone());
+        }
+        if let Some(v) = file.chunk_overlap {
+            self.chunk_overlap.set(Some(v), source.clone());
+        }
+        // rules of the project file replace the rules of the user file
+        if !file.chunking.is_empty() {
+            self.chunking.set(file.chunking, source.clone());
+        }
+        if let Some(v) = file.embed_mode {
+            self.embed_mode.set(v, source.clone());
+        }
+        if let Some(v) = file.embed_batch_size {
+            self.embed_batch_size.set(v, source.clone());
+        }
+        if let Some(v) = file.embed_max_in_flight {
+            self.embed_max_in_flight.set(v, source.clone());
+        }
+        if let Some(v) = file.max_file_size {
+            self.max_file_size.set(v, source.clone());
+        }
+        if let Some(v) = file.load_parallelism {
+            self.load_parallelism.set(v, source.clone());
+        }
+        if let Some(v) = file.max_archive_size {
+            self.max_archive_size.set(v, source.clone());
+        }
+        if let Some(v) = file.max_archive_entries {
+            self.max_archive_entries.set(v, source.clone());
+        }
+        if let Some(v) = file.log_timestamp_pattern {
+            self.log_timestamp_pattern.set(v, source.clone());
+        }
+        if let Some(v) = file.log_level_pattern {
+            self.log_level_pattern.set(v, source.clone());
+        }
+        if let Some(v) = file.connect_timeout {
+            self.connect_timeout.set(v, source.clone());
+        }
+        if let Some(v) = file.request_timeout {
+            self.request_timeout.set(v, source.clone());
+        }
+        if let Some(v) = file.ca_bundle {
+            self.ca_bundle.set(Some(v), source.clone());
+        }
+        if let Some(v) = file.http2 {
+            self.http2.set(v, source.clone());
+        }
+        if let Some(v) = file.max_retries {
+            self.max_retries.set(v, source.clone());
+        }
+        if let Some(v) = file.retry_backoff_ms {
+            self.retry_backoff_ms.set(v, source.clone());
+        }
+        if let Some(v) = file.requests_per_second {
+            self.requests_per_second.set(v, source.clone());
+        }
+        if let Some(v) = file.max_concurrency {
+            self.max_concurrency.set(v, source.clone());
+        }
+        if let Some(v) = file.profile {
+            self.profile.set(Some(v), source);
+        }
+        self.profiles.extend(file.profiles);
+        Ok(())
+    }
+
+    /// Set the API key from a key source spec `env:VAR`, `file:PATH`, `cmd:COMMAND` or a literal key,
+    /// the key is loaded by `resolve_api_key`
+    pub fn set_api_key(&mut self, spec: &str, source: SettingSource) {
+        self.api_key.set(SecretSource::parse(spec), source);
+    }
+
+    /// Load the API key from its source, a `cmd:` command runs on every call
+    pub fn resolve_api_key(&self) -> Result<Secret> {
+        self.api_key
+            .value
+            .resolve()
+            .with_context(|| format!("Failed to load the API key from {}", self.api_key.source))
+    }
+
+    /// Apply the selected profile over the file layers, the env vars and CLI flags take precedence
+    pub fn apply_profile(&mut self) -> Result<()> {
+        let Some(name) = self.profile.value.clone() else {
+            return Ok(());
+        };
+        let profile = self
+            .get_profile(&name)
+            .with_context(|| format!("Failed to apply profile {}", name))?
+            .clone();
+        profile.validate(&name)?;
+
+        let source = SettingSource::Profile(name);
+        self.llm_provider
+            .set(profile.provider.to_lowercase(), source.clone());
+        self.api_url.set(profile.api_url.clone(), source.clone());
+        if let Some(key) = profile.api_key_source() {
+            self.api_key.set(key, source.clone());
+        }
+        if let Some(model) = profile.embed_model {
+            self.embed_model.set(model, source.clone());
+        }
+        if let Some(model) = profile.chat_model {
+            self.ai_model.set(model, source.clone());
+        }
+        if profile.options.is_some() {
+            self.options.set(profile.options, source);
+        }
+        Ok(())
+    }
+
+    /// Look up a profile by name
+    pub fn get_profile(&self, name: &str) -> Result<&Profile> {
+        self.profiles.get(name).ok_or_else(|| {
+            anyhow!(
+                "Unknown profile: {} available profiles: {}",
+                name,
+                self.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+
+    /// Override the settings with `RAG_*` variables e.g. `RAG_API_URL`, `RAG_CHUNK_SIZE`
+    pub fn merge_env<I>(&mut self, vars: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (name, value) in vars {
+            let Some(key) = name.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let key = key.to_lowercase();
+            let source = SettingSource::Env(name.clone());
+            if key == "profile" {
+                self.profile.set(Some(value), source);
+            } else if !self.set_value(&key, &name, value, source)? {
+                debug!("Ignoring unknown config variable {}", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Override a single setting from a CLI flag, `None` keeps the current value
+    pub fn merge_cli(&mut self, key: &str, value: Option<String>) -> Result<()> {
+        let Some(value) = value else {
+            return Ok(());
+        };
+        if !self.set_value(key, key, value, SettingSource::Cli)? {
+            anyhow::bail!("Unknown setting: {}", key);
+        }
+        Ok(())
+    }
+
+    /// Set a setting from the text of an env var or a CLI flag
+    /// Arguments:
+    /// - key: &str setting key e.g. `chunk_size`
+    /// - name: &str env var or flag named in the parse error
+    /// - value: String text of the value
+    /// - source: SettingSource
+    ///
+    /// Returns:
+    /// - Result<bool> false for an unknown key, an error for a value that doesn't parse
+    fn set_value(
+        &mut self,
+        key: &str,
+        name: &str,
+        value: String,
+        source: SettingSource,
+    ) -> Result<bool> {
+        match key {
+            "llm_provider" => self.llm_provider.set(value, source),
+            "api_url" => self.api_url.set(value, source),
+            "api_key" => self.set_api_key(&value, source),
+            "embed_model" => self.embed_model.set(value, source),
+            "embed_dimensions" => self
+                .embed_dimensions
+                .set(Some(parse_value(name, &value)?), source),
+            "ai_model" => self.ai_model.set(value, source),
+            "vector_dim" => self.vector_dim.set(parse_value(name, &value)?, source),
+            "system_prompt" => self.system_prompt.set(value, source),
+            "chunk_size" => self.chunk_size.set(parse_value(name, &value)?, source),
+            "chunk_sizer" => self.chunk_sizer.set(value, source),
+            "chunk_overlap" => self
+                .chunk_overlap
+                .set(Some(parse_value(name, &value)?), source),
+            "embed_mode" => self.embed_mode.set(value, source),
+            "embed_batch_size" => self
+                .embed_batch_size
+                .set(parse_value(name, &value)?, source),
+            "embed_max_in_flight" => self
+                .embed_max_in_flight
+                .set(parse_value(name, &value)?, source),
+            "max_file_size" => self.max_file_size.set(parse_value(name, &value)?, source),
+            "load_parallelism" => self
+                .load_parallelism
+                .set(parse_value(name, &value)?, source),
+            "max_archive_size" => self
+                .max_archive_size
+                .set(parse_value(name, &value)?, source),
+            "max_archive_entries" => self
+                .max_archive_entries
+                .set(parse_value(name, &value)?, source),
+            "log_timestamp_pattern" => self.log_timestamp_pattern.set(value, source),
+            "log_level_pattern" => self.log_level_pattern.set(value, source),
+            "connect_timeout" => self.connect_timeout.set(parse_value(name, &value)?, source),
+            "request_timeout" => self.request_timeout.set(parse_value(name, &value)?, source),
+            "ca_bundle" => self.ca_bundle.set(Some(value), source),
+            "http2" => self.http2.set(parse_value(name, &value)?, source),
+            "max_retries" => self.max_retries.set(parse_value(name, &value)?, source),
+            "retry_backoff_ms" => self
+                .retry_backoff_ms
+                .set(parse_value(name, &value)?, source),
+            "requests_per_second" => self
+                .requests_per_second
+                .set(parse_value(name, &value)?, source),
+            "max_concurrency" => self.max_concurrency.set(parse_value(name, &value)?, source),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// List the settings as (key, value, source) for display, the api key is masked
+    pub fn entries(&self) -> Vec<(&'static str, String, &SettingSource)> {
+        vec![
+            (
+                "llm_provider",
+                self.llm_provider.value.clone(),
+                &self.llm_provider.source,
+            ),
+            ("api_url", self.api_url.value.clone(), &self.api_url.source),
+            (
+                "api_key",
+                self.api_key.value.to_string(),
+                &self.api_key.source,
+            ),
+            (
+                "embed_model",
+                self.embed_model.value.clone(),
+                &self.embed_model.source,
+            ),
+            (
+                "embed_dimensions",
+                self.embed_dimensions
+                    .value
+                    .map(|d| d.to_string())
+                    .unwrap_or_default(),
+                &self.embed_dimensions.source,
+            ),
+            (
+                "ai_model",
+                self.ai_model.value.clone(),
+                &self.ai_model.source,
+            ),
+            (
+                "vector_dim",
+                self.vector_dim.value.to_string(),
+                &self.vector_dim.source,
+            ),
+            (
+                "system_prompt",
+                self.system_prompt.value.clone(),
+                &self.system_prompt.source,
+            ),
+            (
+                "chunk_size",
+                self.chunk_size.value.to_string(),
+                &self.chunk_size.source,
+            ),
+            (
+                "chunk_sizer",
+                self.chunk_sizer.value.clone(),
+                &self.chunk_sizer.source,
+            ),
+            (
+                "chunk_overlap",
+                self.chunk_overlap
+                    .value
+                    .map(|o| o.to_string())
+                    .unwrap_or_default(),
+                &self.chunk_overlap.source,
+            ),
+            (
+                "chunking",
+                format!("{} rules", self.chunking.value.len()),
+                &self.chunking.source,
+            ),
+            (
+                "embed_mode",
+                self.embed_mode.value.clone(),
+                &self.embed_mode.source,
+            ),
+            (
+                "embed_batch_size",
+                self.embed_batch_size.value.to_string(),
+                &self.embed_batch_size.source,
+            ),
+            (
+                "embed_max_in_flight",
+                self.embed_max_in_flight.value.to_string(),
+                &self.embed_max_in_flight.source,
+            ),
+            (
+                "max_file_size",
+                self.max_file_size.value.to_string(),
+                &self.max_file_size.source,
+            ),
+            (
+                "load_parallelism",
+                self.load_parallelism.value.to_string(),
+                &self.load_parallelism.source,
+            ),
+            (
+                "max_archive_size",
+                self.max_archive_size.value.to_string(),
+                &self.max_archive_size.source,
+            ),
+            (
+                "max_archive_entries",
+                self.max_archive_entries.value.to_string(),
+                &self.max_archive_entries.source,
+            ),
+            (
+                "log_timestamp_pattern",
+                self.log_timestamp_pattern.value.clone(),
+                &self.log_timestamp_pattern.source,
+            ),
+            (
+                "log_level_pattern",
+                self.log_level_pattern.value.clone(),
+                &self.log_level_pattern.source,
+            ),
+            (
+                "connect_timeout",
+                self.connect_timeout.value.to_string(),
+                &self.connect_timeout.source,
+            ),
+            (
+                "request_timeout",
+                self.request_timeout.value.to_string(),
+                &self.request_timeout.source,
+            ),
+            (
+                "ca_bundle",
+                self.ca_bundle.value.clone().unwrap_or_default(),
+                &self.ca_bundle.source,
+            ),
+            ("http2", self.http2.value.to_string(), &self.http2.source),
+            (
+                "max_retries",
+                self.max_retries.value.to_string(),
+                &self.max_retries.source,
+            ),
+            (
+                "retry_backoff_ms",
+                self.retry_backoff_ms.value.to_string(),
+                &self.retry_backoff_ms.source,
+            ),
+            (
+                "requests_per_second",
+                self.requests_per_second.value.to_string(),
+                &self.requests_per_second.source,
+            ),
+            (
+                "max_concurrency",
+                self.max_concurrency.value.to_string(),
+                &self.max_concurrency.source,
+            ),
+            (
+                "profile",
+                self.profile.value.clone().unwrap_or_default(),
+                &self.profile.source,
+            ),
+            (
+                "options",
+                self.options
+                    .value
+                    .as_ref()
+                    .map(|o| o.to_string())
+                    .unwrap_or_default(),
+                &self.options.source,
+            ),
+        ]
+    }
+}
+
+/// Parse the text of an env var or a CLI flag into the type of its setting
+fn parse_value<T>(name: &str, value: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .parse()
+        .with_context(|| format!("Invalid value for {}: {}", name, value))
+}
+
+/// Path of the user config file `$XDG_CONFIG_HOME/rag-agent-rust/rag-agent.toml`
+/// falling back to `~/.config/rag-agent-rust/rag-agent.toml`
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// Path of the project config file `rag-agent.toml` in the current directory
+pub fn project_config_path() -> PathBuf {
+    PathBuf::from(CONFIG_FILE_NAME)
+}
+
+/// Load the settings once for the process with the profile selected by `--profile`,
+/// errors in the config files are returned to the caller, as is a different profile once
+/// the settings are loaded
+pub fn init(profile: Option<String>) -> Result<&'static Settings> {
+    if let Some(settings) = SETTINGS.get() {
+        if profile.is_some() && profile != settings.profile.value {
+            anyhow::bail!(
+                "Settings are already loaded with profile {}, can't switch to {}",
+                settings.profile.value.as_deref().unwrap_or("none"),
+                profile.unwrap_or_default()
+            );
+        }
+        return Ok(settings);
+    }
+    let settings = Settings::load(profile)?;
+    Ok(SETTINGS.get_or_init(|| settings))
+}
+
+/// Get the process wide settings, falls back to the defaults if the config could not be loaded
+pub fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| {
+        Settings::load(None).unwrap_or_else(|e| {
+            log::warn!("Failed to load config, using defaults: {:#}", e);
+            Settings::default()
+        })
+    })
+}
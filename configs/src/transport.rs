@@ -0,0 +1,577 @@
+use crate::retry::{
+    retry_after, RateLimit, RateLimiter, RetryPolicy, StatsSnapshot, TransportStats,
+};
+use crate::settings::Settings;
+use anyhow::{anyhow, Context, Result};
+use base64::Engine;
+use http::request::Parts;
+use http::uri::Scheme;
+use http::{HeaderValue, Request, Response, StatusCode, Uri};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::{Connected, Connection, HttpConnector};
+use hyper_util::client::legacy::Client as LegacyClient;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use log::{debug, warn};
+use rustls::crypto::ring::default_provider;
+use rustls::{ClientConfig, RootCertStore};
+use std::future::Future;
+use std::io::{BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context as TaskContext, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::TcpStream;
+use tower_service::Service;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Largest proxy CONNECT response header accepted
+const MAX_CONNECT_RESPONSE: usize = 8192;
+
+/// HttpsClient shared hyper client with connect and request timeouts, proxy and custom CA support,
+/// retries with backoff and client side rate limits. Cloning is cheap and the clones share
+/// the connection pool, the limiters and the stats.
+#[derive(Clone)]
+pub struct HttpsClient {
+    client: LegacyClient<HttpsConnector<ProxyConnector>, Full<Bytes>>,
+    proxy: ProxyConfig,
+    request_timeout: Option<Duration>,
+    retry: RetryPolicy,
+    limiter: Arc<RateLimiter>,
+    stats: Arc<TransportStats>,
+}
+
+impl HttpsClient {
+    /// Send the request, 429, 5xx, timeouts and connection errors are retried with backoff.
+    /// After the last retry the final response is returned so the caller can report the status.
+    pub async fn request(&self, request: Request<Full<Bytes>>) -> Result<Response<Incoming>> {
+        let (parts, body) = request.into_parts();
+        let host = parts
+            .uri
+            .authority()
+            .map(|a| a.to_string())
+            .unwrap_or_default();
+
+        let mut attempt = 0;
+        loop {
+            let permit = self.limiter.acquire(&host).await;
+            if permit.waited {
+                self.stats.rate_limited();
+            }
+            self.stats.request();
+            let result = self.send(copy_request(&parts, &body)).await;
+            drop(permit);
+
+            // Some(retry_after) when the attempt can be retried
+            let retry = match &result {
+                Ok(response) if RetryPolicy::is_retryable_status(response.status()) => {
+                    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                        self.stats.throttled();
+                    }
+                    Some((
+                        retry_after(response.headers()),
+                        response.status().to_string(),
+                    ))
+                }
+                Ok(_) => return result,
+                Err(e) if is_retryable_error(e) => Some((None, format!("{:#}", e))),
+                Err(_) => None,
+            };
+
+            match retry {
+                Some((retry_after, reason)) if attempt < self.retry.max_retries => {
+                    attempt += 1;
+                    let delay = self.retry.delay(attempt, retry_after);
+                    self.stats.retry();
+                    warn!(
+                        "Retrying {} in {:?} attempt {} of {}: {}",
+                        parts.uri, delay, attempt, self.retry.max_retries, reason
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                _ => {
+                    self.stats.failure();
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// Counters of the requests sent by this client and its clones
+    pub fn stats(&self) -> StatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    /// Send one attempt, the request timeout applies until the response headers arrive
+    async fn send(&self, mut request: Request<Full<Bytes>>) -> Result<Response<Incoming>> {
+        let uri = request.uri().clone();
+        // plain http requests through a proxy carry the proxy credentials themselves
+        if uri.scheme() == Some(&Scheme::HTTP) {
+            if let Some(auth) = self.proxy.proxy_for(&uri).and_then(proxy_authorization) {
+                request.headers_mut().insert("Proxy-Authorization", auth);
+            }
+        }
+
+        let response = self.client.request(request);
+        match self.request_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, response)
+                .await
+                .map_err(|_| {
+                    std::io::Error::new(
+                        ErrorKind::TimedOut,
+                        format!("Request to {} timed out after {:?}", uri, timeout),
+                    )
+                })?
+                .with_context(|| format!("Request to {} failed", uri)),
+            None => response
+                .await
+                .with_context(|| format!("Request to {} failed", uri)),
+        }
+    }
+
+    /// Send a GET request without a body
+    pub async fn get(&self, uri: Uri) -> Result<Response<Incoming>> {
+        let request = Request::builder()
+            .method("GET")
+            .uri(uri)
+            .body(Full::new(Bytes::new()))
+            .context("Failed to build request")?;
+        self.request(request).await
+    }
+}
+
+/// Rebuild the request for another attempt, the body is cheap to clone
+fn copy_request(parts: &Parts, body: &Full<Bytes>) -> Request<Full<Bytes>> {
+    let mut request = Request::new(body.clone());
+    *request.method_mut() = parts.method.clone();
+    *request.uri_mut() = parts.uri.clone();
+    *request.version_mut() = parts.version;
+    *request.headers_mut() = parts.headers.clone();
+    request
+}
+
+/// Timeouts, refused or reset connections and connections closed mid response are worth a retry
+fn is_retryable_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        if let Some(e) = cause.downcast_ref::<hyper_util::client::legacy::Error>() {
+            return e.is_connect();
+        }
+        if let Some(e) = cause.downcast_ref::<hyper::Error>() {
+            return e.is_incomplete_message() || e.is_canceled() || e.is_closed();
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            return matches!(
+                e.kind(),
+                ErrorKind::TimedOut
+                    | ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::ConnectionRefused
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+            );
+        }
+        false
+    })
+}
+
+/// HttpsClientBuilder configures the transport of the `HttpsClient`
+#[derive(Debug, Clone, Default)]
+pub struct HttpsClientBuilder {
+    connect_timeout: Option<Duration>,
+    request_timeout: Option<Duration>,
+    proxy: ProxyConfig,
+    ca_bundle: Option<PathBuf>,
+    http2: bool,
+    retry: RetryPolicy,
+    rate_limit: RateLimit,
+}
+
+impl HttpsClientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder from the config settings and the `HTTP(S)_PROXY`/`NO_PROXY` env variables
+    pub fn from_settings(settings: &Settings) -> Result<Self> {
+        let seconds = |s: u64| (s > 0).then(|| Duration::from_secs(s));
+        Ok(HttpsClientBuilder {
+            connect_timeout: seconds(settings.connect_timeout.value),
+            request_timeout: seconds(settings.request_timeout.value),
+            proxy: ProxyConfig::from_env()?,
+            ca_bundle: settings.ca_bundle.value.as_ref().map(PathBuf::from),
+            http2: settings.http2.value,
+            retry: RetryPolicy {
+                max_retries: settings.max_retries.value,
+                base_delay: Duration::from_millis(settings.retry_backoff_ms.value),
+                ..RetryPolicy::default()
+            },
+            rate_limit: RateLimit {
+                requests_per_second: settings.requests_per_second.value,
+                max_concurrency: settings.max_concurrency.value,
+            },
+        })
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    pub fn proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Extra PEM bundle trusted in addition to the native roots e.g. for an internal gateway
+    pub fn ca_bundle(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ca_bundle = Some(path.into());
+        self
+    }
+
+    /// Offer HTTP/2 through ALPN for TLS connections
+    pub fn http2(mut self, enabled: bool) -> Self {
+        self.http2 = enabled;
+        self
+    }
+
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Limits applied to each host the client talks to
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    pub fn build(self) -> Result<HttpsClient> {
+        install_crypto_provider();
+        let tls = tls_config(self.ca_bundle.as_deref())?;
+
+        let mut http = HttpConnector::new();
+        http.enforce_http(false);
+        http.set_connect_timeout(self.connect_timeout);
+        let connector = ProxyConnector {
+            http,
+            proxy: self.proxy.clone(),
+            connect_timeout: self.connect_timeout,
+        };
+
+        let builder = HttpsConnectorBuilder::new()
+            .with_tls_config(tls)
+            .https_or_http()
+            .enable_http1();
+        let https = if self.http2 {
+            builder.enable_http2().wrap_connector(connector)
+        } else {
+            builder.wrap_connector(connector)
+        };
+
+        let client = LegacyClient::builder(TokioExecutor::new()).build(https);
+        Ok(HttpsClient {
+            client,
+            proxy: self.proxy,
+            request_timeout: self.request_timeout,
+            retry: self.retry,
+            limiter: Arc::new(RateLimiter::new(self.rate_limit)),
+            stats: Arc::new(TransportStats::default()),
+        })
+    }
+}
+
+/// Install the ring crypto provider as the process default, later calls are a no-op
+fn install_crypto_provider() {
+    if default_provider().install_default().is_ok() {
+        debug!("Crypto provider installed successfully");
+    }
+}
+
+/// TLS config trusting the native roots and the certificates of the optional PEM bundle
+fn tls_config(ca_bundle: Option<&Path>) -> Result<ClientConfig> {
+    let mut roots = RootCertStore::empty();
+    let native = rustls_native_certs::load_native_certs();
+    for error in native.errors {
+        warn!("Failed to load a native certificate: {}", error);
+    }
+    let (added, ignored) = roots.add_parsable_certificates(native.certs);
+    debug!("Loaded {} native certificates, ignored {}", added, ignored);
+
+    if let Some(path) = ca_bundle {
+        let file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open CA bundle {}", path.display()))?;
+        let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Failed to parse CA bundle {}", path.display()))?;
+        if certs.is_empty() {
+            return Err(anyhow!(
+                "No certificates found in CA bundle {}",
+                path.display()
+            ));
+        }
+        for cert in certs {
+            roots
+                .add(cert)
+                .with_context(|| format!("Invalid certificate in CA bundle {}", path.display()))?;
+        }
+    }
+
+    let config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .context("Failed to set the TLS protocol versions")?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(config)
+}
+
+/// ProxyConfig proxies for http and https targets and the hosts that bypass them
+#[derive(Debug, Clone, Default)]
+pub struct ProxyConfig {
+    pub http: Option<Uri>,
+    pub https: Option<Uri>,
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Read `HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY`, the lowercase names take precedence
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Build the config from a variable lookup
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let lookup = |name: &str| {
+            var(&name.to_lowercase())
+                .or_else(|| var(name))
+                .filter(|v| !v.trim().is_empty())
+        };
+        let parse = |name: &str| -> Result<Option<Uri>> {
+            lookup(name)
+                .map(|v| {
+                    v.trim()
+                        .parse::<Uri>()
+                        .with_context(|| format!("Invalid proxy url in {}", name))
+                })
+                .transpose()
+        };
+
+        let all = parse("ALL_PROXY")?;
+        let http = parse("HTTP_PROXY")?.or_else(|| all.clone());
+        let https = parse("HTTPS_PROXY")?.or(all);
+        let no_proxy = lookup("NO_PROXY")
+            .map(|v| {
+                v.split(',')
+                    .map(|h| h.trim().to_lowercase())
+                    .filter(|h| !h.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(ProxyConfig {
+            http,
+            https,
+            no_proxy,
+        })
+    }
+
+    /// The proxy to use for the target, `None` connects directly
+    pub fn proxy_for(&self, uri: &Uri) -> Option<&Uri> {
+        let host = uri.host()?.trim_matches(['[', ']']).to_lowercase();
+        if self.bypass(&host) {
+            return None;
+        }
+        if uri.scheme() == Some(&Scheme::HTTPS) {
+            self.https.as_ref()
+        } else {
+            self.http.as_ref()
+        }
+    }
+
+    /// `NO_PROXY` entries match the host and its subdomains, `*` matches every host
+    fn bypass(&self, host: &str) -> bool {
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.rsplit_once(':').map_or(entry.as_str(), |(h, port)| {
+                if port.chars().all(|c| c.is_ascii_digit()) {
+                    h
+                } else {
+                    entry
+                }
+            });
+            let entry = entry.trim_start_matches("*.").trim_start_matches('.');
+            entry == "*"
+                || host == entry
+                || host
+                    .strip_suffix(entry)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+/// Basic `Proxy-Authorization` header from the user info of the proxy url
+fn proxy_authorization(proxy: &Uri) -> Option<HeaderValue> {
+    let user_info = proxy.authority()?.as_str().rsplit_once('@')?.0;
+    let credentials = base64::engine::general_purpose::STANDARD.encode(user_info);
+    let mut value = HeaderValue::from_str(&format!("Basic {}", credentials)).ok()?;
+    value.set_sensitive(true);
+    Some(value)
+}
+
+/// ProxyConnector opens the TCP connection directly or through the configured proxy,
+/// https targets are tunneled with `CONNECT` and TLS is layered on top by the `HttpsConnector`.
+#[derive(Clone)]
+pub struct ProxyConnector {
+    http: HttpConnector,
+    proxy: ProxyConfig,
+    connect_timeout: Option<Duration>,
+}
+
+impl Service<Uri> for ProxyConnector {
+    type Response = TokioIo<ProxyStream>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.http.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, dst: Uri) -> Self::Future {
+        let mut http = self.http.clone();
+        let proxy = self.proxy.proxy_for(&dst).cloned();
+        let connect_timeout = self.connect_timeout;
+        Box::pin(async move {
+            let Some(proxy) = proxy else {
+                let tcp = http.call(dst).await?.into_inner();
+                return Ok(TokioIo::new(ProxyStream {
+                    tcp,
+                    proxied: false,
+                }));
+            };
+
+            debug!("Connecting to {} through proxy {}", dst, proxy);
+            let mut tcp = http.call(proxy.clone()).await?.into_inner();
+            if dst.scheme() == Some(&Scheme::HTTPS) {
+                let tunnel = tunnel(&mut tcp, &dst, &proxy);
+                match connect_timeout {
+                    Some(timeout) => tokio::time::timeout(timeout, tunnel)
+                        .await
+                        .map_err(|_| anyhow!("Proxy CONNECT to {} timed out", dst))??,
+                    None => tunnel.await?,
+                }
+                Ok(TokioIo::new(ProxyStream {
+                    tcp,
+                    proxied: false,
+                }))
+            } else {
+                Ok(TokioIo::new(ProxyStream { tcp, proxied: true }))
+            }
+        })
+    }
+}
+
+/// Open a `CONNECT` tunnel to the target host through the proxy connection
+async fn tunnel(tcp: &mut TcpStream, dst: &Uri, proxy: &Uri) -> Result<()> {
+    let host = dst.host().context("Missing host in request url")?;
+    let port = dst.port_u16().unwrap_or(443);
+    let mut connect = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n",
+        host = host,
+        port = port
+    );
+    if let Some(auth) = proxy_authorization(proxy) {
+        let auth = auth.to_str().context("Invalid proxy credentials")?;
+        connect.push_str(&format!("Proxy-Authorization: {}\r\n", auth));
+    }
+    connect.push_str("\r\n");
+    tcp.write_all(connect.as_bytes())
+        .await
+        .context("Failed to send proxy CONNECT")?;
+
+    let mut response = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !response.ends_with(b"\r\n\r\n") {
+        let n = tcp
+            .read(&mut buf)
+            .await
+            .context("Failed to read proxy CONNECT response")?;
+        if n == 0 {
+            return Err(anyhow!("Proxy closed the connection during CONNECT"));
+        }
+        response.extend_from_slice(&buf[..n]);
+        if response.len() > MAX_CONNECT_RESPONSE {
+            return Err(anyhow!("Proxy CONNECT response is too large"));
+        }
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status = response.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(anyhow!(
+            "Proxy refused CONNECT to {}:{}: {}",
+            host,
+            port,
+            status
+        ));
+    }
+    Ok(())
+}
+
+/// ProxyStream TCP stream that tells hyper whether requests go to a proxy in absolute form
+pub struct ProxyStream {
+    tcp: TcpStream,
+    proxied: bool,
+}
+
+impl Connection for ProxyStream {
+    fn connected(&self) -> Connected {
+        self.tcp.connected().proxy(self.proxied)
+    }
+}
+
+impl AsyncRead for ProxyStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().tcp).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for ProxyStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().tcp).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().tcp).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.get_mut().tcp).poll_shutdown(cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.get_mut().tcp).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.tcp.is_write_vectored()
+    }
+}
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use configs::retry::{retry_after, RateLimit, RateLimiter, RetryPolicy};
+    use configs::transport::HttpsClientBuilder;
+    use http::{HeaderMap, HeaderValue};
+    use std::time::{Duration, Instant, SystemTime};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve the canned responses in order, one connection per response
+    async fn serve(responses: Vec<&'static str>) -> anyhow::Result<String> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/api/embed", listener.local_addr()?);
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut socket, _) = listener.accept().await?;
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await?;
+                socket.write_all(response.as_bytes()).await?;
+            }
+            anyhow::Ok(())
+        });
+        Ok(url)
+    }
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(100),
+        }
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(7)));
+
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        headers.insert("retry-after", HeaderValue::from_str(&date).unwrap());
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(50) && delay <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_backoff_is_bounded() {
+        let policy = policy(5);
+        for attempt in 1..=5 {
+            let exp = Duration::from_millis(10 * 2u64.pow(attempt - 1)).min(policy.max_delay);
+            let delay = policy.delay(attempt, None);
+            assert!(delay >= exp / 2 && delay <= exp, "attempt {}", attempt);
+        }
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(60))),
+            policy.max_delay
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retries_server_errors() -> anyhow::Result<()> {
+        let url = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nretry-after: 0\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            "HTTP/1.1 429 Too Many Requests\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 2\r\n\r\nok",
+        ])
+        .await?;
+
+        let client = HttpsClientBuilder::new().retry(policy(3)).build()?;
+        let response = client.get(url.parse()?).await?;
+        assert!(response.status().is_success());
+
+        let stats = client.stats();
+        assert_eq!(stats.requests, 3);
+        assert_eq!(stats.retries, 2);
+        assert_eq!(stats.throttled, 1);
+        assert_eq!(stats.failures, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_retries() -> anyhow::Result<()> {
+        let url = serve(vec![
+            "HTTP/1.1 500 Internal Server Error\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            "HTTP/1.1 500 Internal Server Error\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+        ])
+        .await?;
+
+        let client = HttpsClientBuilder::new().retry(policy(1)).build()?;
+        let response = client.get(url.parse()?).await?;
+        assert_eq!(response.status(), 500);
+        assert_eq!(client.stats().retries, 1);
+        assert_eq!(client.stats().failures, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() -> anyhow::Result<()> {
+        let url = serve(vec![
+            "HTTP/1.1 401 Unauthorized\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+        ])
+        .await?;
+
+        let client = HttpsClientBuilder::new().retry(policy(3)).build()?;
+        let response = client.get(url.parse()?).await?;
+        assert_eq!(response.status(), 401);
+        assert_eq!(client.stats().requests, 1);
+        assert_eq!(client.stats().retries, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(RateLimit {
+            requests_per_second: 20.0,
+            max_concurrency: 0,
+        });
+        let start = Instant::now();
+        let first = limiter.acquire("localhost:11434").await;
+        assert!(!first.waited);
+        let mut waited = false;
+        for _ in 0..3 {
+            waited |= limiter.acquire("localhost:11434").await.waited;
+        }
+        assert!(waited);
+        assert!(start.elapsed() >= Duration::from_millis(140));
+
+        // other hosts have their own budget
+        assert!(!limiter.acquire("api.openai.com:443").await.waited);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_bounds_concurrency() {
+        let limiter = std::sync::Arc::new(RateLimiter::new(RateLimit {
+            requests_per_second: 0.0,
+            max_concurrency: 1,
+        }));
+        let permit = limiter.acquire("localhost:11434").await;
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire("localhost:11434").await.waited })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiter.is_finished());
+        drop(permit);
+        assert!(waiter.await.unwrap());
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use configs::secret::{redact_header, Secret};
+    use configs::settings::Settings;
+
+    #[test]
+    fn test_secret_is_redacted() -> anyhow::Result<()> {
+        let secret = Secret::new("sk-secret");
+        assert!(!format!("{:?}", secret).contains("sk-secret"));
+        assert!(!format!("{}", secret).contains("sk-secret"));
+        assert_eq!(secret.expose(), "sk-secret");
+        assert_eq!(format!("{}", Secret::default()), "");
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_key_sources() -> anyhow::Result<()> {
+        assert_eq!(Secret::resolve("sk-literal")?.expose(), "sk-literal");
+
+        std::env::set_var("RAG_SECRET_TEST_KEY", "sk-env");
+        assert_eq!(
+            Secret::resolve("env:RAG_SECRET_TEST_KEY")?.expose(),
+            "sk-env"
+        );
+        assert!(Secret::resolve("env:RAG_SECRET_TEST_MISSING").is_err());
+
+        let path = std::env::temp_dir().join("rag_secret_test_key");
+        std::fs::write(&path, "sk-file\n")?;
+        let spec = format!("file:{}", path.display());
+        assert_eq!(Secret::resolve(&spec)?.expose(), "sk-file");
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(
+            Secret::resolve("cmd:echo sk-command")?.expose(),
+            "sk-command"
+        );
+        assert!(Secret::resolve("cmd:exit 1").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_settings_api_key_from_source() -> anyhow::Result<()> {
+        let mut settings = Settings::default();
+        settings.merge_cli("api_key", Some("cmd:echo sk-cli".to_string()))?;
+        assert_eq!(settings.resolve_api_key()?.expose(), "sk-cli");
+        assert!(!format!("{:?}", settings).contains("sk-cli"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_redact_header() {
+        assert!(!redact_header("Authorization", "Bearer sk-secret").contains("sk-secret"));
+        assert_eq!(
+            redact_header("Content-Type", "application/json"),
+            "application/json"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+#[cfg(test)]
+mod tests {
+    use configs::constants::{CHAT_API_URL, DEFAULT_CHUNK_SIZE, EMBEDDING_MODEL};
+    use configs::settings::{SettingSource, Settings, SettingsFile};
+    use std::path::PathBuf;
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_defaults() {
+        let settings = Settings::default();
+        assert_eq!(settings.api_url.value, CHAT_API_URL);
+        assert_eq!(settings.embed_model.value, EMBEDDING_MODEL);
+        assert_eq!(settings.chunk_size.value, DEFAULT_CHUNK_SIZE);
+        assert_eq!(settings.api_url.source, SettingSource::Default);
+    }
+
+    #[test]
+    fn test_layers_apply_in_order() -> anyhow::Result<()> {
+        let mut settings = Settings::default();
+
+        let user = SettingsFile::from_toml(
+            r#"
+            api_url = "http://gpu-box:11434"
+            ai_model = "llama3"
+            "#,
+        )?;
+        let user_path = PathBuf::from("/home/user/.config/rag-agent-rust/rag-agent.toml");
+        settings.merge_file(user, SettingSource::UserFile(user_path.clone()))?;
+
+        let project = SettingsFile::from_toml(r#"ai_model = "mistral""#)?;
+        let project_path = PathBuf::from("rag-agent.toml");
+        settings.merge_file(project, SettingSource::ProjectFile(project_path.clone()))?;
+
+        settings.merge_env(env(&[
+            ("RAG_CHUNK_SIZE", "512"),
+            ("RAG_UNKNOWN", "ignored"),
+            ("PATH", "/usr/bin"),
+        ]))?;
+        settings.merge_cli("embed_model", Some("mxbai-embed-large".to_string()))?;
+        settings.merge_cli("api_key", None)?;
+
+        assert_eq!(settings.api_url.value, "http://gpu-box:11434");
+        assert_eq!(settings.api_url.source, SettingSource::UserFile(user_path));
+        assert_eq!(settings.ai_model.value, "mistral");
+        assert_eq!(
+            settings.ai_model.source,
+            SettingSource::ProjectFile(project_path)
+        );
+        assert_eq!(settings.chunk_size.value, 512);
+        assert_eq!(
+            settings.chunk_size.source,
+            SettingSource::Env("RAG_CHUNK_SIZE".to_string())
+        );
+        assert_eq!(settings.embed_model.value, "mxbai-embed-large");
+        assert_eq!(settings.embed_model.source, SettingSource::Cli);
+        assert_eq!(settings.api_key.source, SettingSource::Default);
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunking_rules() -> anyhow::Result<()> {
+        let mut settings = Settings::default();
+        let file = SettingsFile::from_toml(
+            r#"
+            chunk_overlap = 64
+
+            [[chunking]]
+            language = "rust"
+            size = 512
+            sizer = "tiktoken:cl100k_base"
+
+            [[chunking]]
+            glob = "docs/**/*.md"
+            splitter = "markdown"
+            trim = false
+            "#,
+        )?;
+        let path = PathBuf::from("rag-agent.toml");
+        settings.merge_file(file, SettingSource::ProjectFile(path.clone()))?;
+
+        assert_eq!(settings.chunk_overlap.value, Some(64));
+        assert_eq!(settings.chunking.value.len(), 2);
+        assert_eq!(settings.chunking.value[0].language.as_deref(), Some("rust"));
+        assert_eq!(settings.chunking.value[0].size, Some(512));
+        assert_eq!(
+            settings.chunking.value[1].glob.as_deref(),
+            Some("docs/**/*.md")
+        );
+        assert_eq!(settings.chunking.value[1].trim, Some(false));
+        assert_eq!(settings.chunking.source, SettingSource::ProjectFile(path));
+
+        // a file without rules keeps the rules of the earlier layer
+        settings.merge_file(
+            SettingsFile::from_toml(r#"chunk_size = 1024"#)?,
+            SettingSource::Default,
+        )?;
+        assert_eq!(settings.chunking.value.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        let mut settings = Settings::default();
+        let err = settings
+            .merge_env(env(&[("RAG_VECTOR_DIM", "large")]))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid value for RAG_VECTOR_DIM: large");
+        let err = settings
+            .merge_cli("chunk_size", Some("big".to_string()))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Invalid value for chunk_size: big");
+        assert!(settings
+            .merge_cli("not_a_key", Some("x".to_string()))
+            .is_err());
+        assert!(SettingsFile::from_toml("chunk_size = \"big\"").is_err());
+    }
+
+    #[test]
+    fn test_entries_mask_api_key() {
+        let mut settings = Settings::default();
+        settings
+            .merge_cli("api_key", Some("sk-secret".to_string()))
+            .unwrap();
+        let entries = settings.entries();
+        let (_, value, source) = entries
+            .iter()
+            .find(|(key, _, _)| *key == "api_key")
+            .unwrap();
+        assert!(!value.contains("sk-secret"));
+        assert_eq!(**source, SettingSource::Cli);
+    }
+
+    #[test]
+    fn test_profile_applies_over_files() -> anyhow::Result<()> {
+        let mut settings = Settings::default();
+        let file = SettingsFile::from_toml(
+            r#"
+            ai_model = "mistral"
+            profile = "remote"
+
+            [profiles.remote]
+            provider = "OpenAI"
+            api_url = "https://llm.example.com"
+            api_key = "sk-profile"
+            chat_model = "gpt-4o"
+            options = { temperature = 0.2 }
+            "#,
+        )?;
+        settings.merge_file(
+            file,
+            SettingSource::ProjectFile(PathBuf::from("rag-agent.toml")),
+        )?;
+        settings.apply_profile()?;
+        settings.merge_cli("api_url", Some("http://localhost:8080".to_string()))?;
+
+        let source = SettingSource::Profile("remote".to_string());
+        assert_eq!(settings.llm_provider.value, "openai");
+        assert_eq!(settings.llm_provider.source, source);
+        assert_eq!(settings.ai_model.value, "gpt-4o");
+        assert_eq!(settings.ai_model.source, source);
+        assert_eq!(settings.resolve_api_key()?.expose(), "sk-profile");
+        assert_eq!(settings.embed_model.value, EMBEDDING_MODEL);
+        assert!(settings.options.value.is_some());
+        assert_eq!(settings.api_url.value, "http://localhost:8080");
+        assert_eq!(settings.api_url.source, SettingSource::Cli);
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_overrides_profile() -> anyhow::Result<()> {
+        let mut settings = Settings::default();
+        let file = SettingsFile::from_toml(
+            r#"
+            profile = "remote"
+
+            [profiles.remote]
+            provider = "openai"
+            api_url = "https://llm.example.com"
+            api_key_command = "exit 1"
+            chat_model = "gpt-4o"
+            "#,
+        )?;
+        settings.merge_file(
+            file,
+            SettingSource::ProjectFile(PathBuf::from("rag-agent.toml")),
+        )?;
+        settings.merge_profile_and_env(
+            None,
+            env(&[
+                ("RAG_API_URL", "http://env-box:11434"),
+                ("RAG_LLM_PROVIDER", "ollama"),
+            ]),
+        )?;
+
+        // the key command of the profile only runs when the key is used
+        assert_eq!(settings.api_url.value, "http://env-box:11434");
+        assert_eq!(
+            settings.api_url.source,
+            SettingSource::Env("RAG_API_URL".to_string())
+        );
+        assert_eq!(settings.llm_provider.value, "ollama");
+        assert_eq!(settings.ai_model.value, "gpt-4o");
+        assert!(settings.resolve_api_key().is_err());
+
+        // --profile wins over RAG_PROFILE
+        let mut settings = Settings::default();
+        settings.merge_file(
+            SettingsFile::from_toml(
+                r#"
+                [profiles.other]
+                provider = "ollama"
+                api_url = "http://other:11434"
+                "#,
+            )?,
+            SettingSource::ProjectFile(PathBuf::from("rag-agent.toml")),
+        )?;
+        settings
+            .merge_profile_and_env(Some("other".to_string()), env(&[("RAG_PROFILE", "openai")]))?;
+        assert_eq!(settings.profile.value.as_deref(), Some("other"));
+        assert_eq!(settings.api_url.value, "http://other:11434");
+        Ok(())
+    }
+
+    #[test]
+    fn test_init_rejects_another_profile() -> anyhow::Result<()> {
+        let loaded = configs::settings::init(None)?.profile.value.clone();
+        assert!(configs::settings::init(loaded).is_ok());
+        let err = configs::settings::init(Some("not-the-loaded-profile".to_string())).unwrap_err();
+        assert!(err.to_string().contains("already loaded"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_profile_is_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.get_profile("local-ollama").is_ok());
+        settings.profile.value = Some("missing".to_string());
+        assert!(settings.apply_profile().is_err());
+    }
+}
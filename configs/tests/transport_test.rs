@@ -0,0 +1,151 @@
+#[cfg(test)]
+mod tests {
+    use configs::transport::{HttpsClientBuilder, ProxyConfig};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn vars(pairs: &'static [(&'static str, &'static str)]) -> impl Fn(&str) -> Option<String> {
+        move |name| {
+            pairs
+                .iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_proxy_from_vars() -> anyhow::Result<()> {
+        let proxy = ProxyConfig::from_vars(vars(&[
+            ("HTTPS_PROXY", "http://proxy.corp:3128"),
+            ("http_proxy", "http://lower.corp:8080"),
+            ("HTTP_PROXY", "http://upper.corp:8080"),
+            ("NO_PROXY", "localhost, .internal.corp,10.0.0.1:11434"),
+        ]))?;
+
+        let https = "https://api.openai.com/v1/models".parse()?;
+        let http = "http://gpu-box:11434/api/version".parse()?;
+        assert_eq!(
+            proxy.proxy_for(&https).map(|u| u.to_string()),
+            Some("http://proxy.corp:3128/".to_string())
+        );
+        assert_eq!(
+            proxy.proxy_for(&http).map(|u| u.to_string()),
+            Some("http://lower.corp:8080/".to_string())
+        );
+        assert!(proxy
+            .proxy_for(&"http://localhost:11434".parse()?)
+            .is_none());
+        assert!(proxy
+            .proxy_for(&"https://llm.internal.corp".parse()?)
+            .is_none());
+        assert!(proxy.proxy_for(&"http://10.0.0.1:11434".parse()?).is_none());
+        assert!(proxy
+            .proxy_for(&"https://notinternal.corp".parse()?)
+            .is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_is_repeatable() -> anyhow::Result<()> {
+        HttpsClientBuilder::new().build()?;
+        HttpsClientBuilder::new().http2(true).build()?;
+        configs::get_https_client()?;
+        configs::get_https_client()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_ca_bundle_is_rejected() -> anyhow::Result<()> {
+        assert!(HttpsClientBuilder::new()
+            .ca_bundle("/does/not/exist.pem")
+            .build()
+            .is_err());
+
+        let path = std::env::temp_dir().join("rag_transport_test_empty.pem");
+        std::fs::write(&path, "not a certificate")?;
+        assert!(HttpsClientBuilder::new().ca_bundle(&path).build().is_err());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_request_goes_through_proxy() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy_url = format!("http://user:pass@{}", listener.local_addr()?);
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await?;
+            socket
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok")
+                .await?;
+            anyhow::Ok(String::from_utf8_lossy(&buf[..n]).to_string())
+        });
+
+        let proxy = ProxyConfig {
+            http: Some(proxy_url.parse()?),
+            https: None,
+            no_proxy: vec![],
+        };
+        let client = HttpsClientBuilder::new().proxy(proxy).build()?;
+        let response = client
+            .get("http://ollama.example:11434/api/version".parse()?)
+            .await?;
+        assert!(response.status().is_success());
+
+        let request = server.await??;
+        assert!(request.starts_with("GET http://ollama.example:11434/api/version HTTP/1.1"));
+        assert!(request.contains("proxy-authorization: Basic dXNlcjpwYXNz"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_https_request_is_tunneled() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let proxy_url = format!("http://{}", listener.local_addr()?);
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await?;
+            socket.write_all(b"HTTP/1.1 403 Forbidden\r\n\r\n").await?;
+            anyhow::Ok(String::from_utf8_lossy(&buf[..n]).to_string())
+        });
+
+        let proxy = ProxyConfig {
+            http: None,
+            https: Some(proxy_url.parse()?),
+            no_proxy: vec![],
+        };
+        let client = HttpsClientBuilder::new().proxy(proxy).build()?;
+        let error = client
+            .get("https://api.openai.com/v1/models".parse()?)
+            .await
+            .unwrap_err();
+        assert!(format!("{:?}", error).contains("403"));
+
+        let request = server.await??;
+        assert!(request.starts_with("CONNECT api.openai.com:443 HTTP/1.1"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/api/version", listener.local_addr()?);
+        // accept the connection but never answer
+        let _server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await?;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            drop(socket);
+            anyhow::Ok(())
+        });
+
+        let client = HttpsClientBuilder::new()
+            .request_timeout(Duration::from_millis(200))
+            .build()?;
+        let error = client.get(url.parse()?).await.unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use crate::walker::{SkipReason, Walker};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// Separator between the path of an archive and the path of an entry inside it
+pub const ENTRY_SEPARATOR: &str = "!/";
+
+/// Zip entries may expand to at most this many times their compressed size
+const MAX_COMPRESSION_RATIO: u64 = 100;
+
+/// Zip entries up to this size in bytes are not checked against the compression ratio
+const RATIO_CHECK_SIZE: u64 = 1024 * 1024;
+
+/// ArchiveFormat of an archive loaded like a directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Archive format from the file name: `.zip`, `.tar`, `.tar.gz` or `.tgz`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// ArchiveLimits how far an archive is expanded before its remaining entries are skipped,
+/// 0 is unlimited
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveLimits {
+    /// Bytes the entries of an archive may expand to
+    pub max_size: u64,
+    /// Entries read from an archive
+    pub max_entries: usize,
+}
+
+/// Path of an entry inside an archive e.g. `bundle.zip!/src/main.rs`
+pub fn entry_path(archive: &Path, entry: &str) -> PathBuf {
+    PathBuf::from(format!("{}{}{}", archive.display(), ENTRY_SEPARATOR, entry))
+}
+
+/// Read the files of an archive one at a time, entries are checked against the globs and
+/// the size limit of the walker and reading stops at the archive limits
+/// Arguments:
+/// - path: &Path of the archive
+/// - format: ArchiveFormat
+/// - walker: &Walker the archive was found by
+/// - visit: called with the path inside the archive and the content of every file entry
+///   or the reason it was skipped
+///
+/// Returns:
+/// - Result<Option<SkipReason>> the limit the archive stopped at, an error when it can't be
+///   opened or is corrupt
+pub fn read_archive(
+    path: &Path,
+    format: ArchiveFormat,
+    walker: &Walker,
+    mut visit: impl FnMut(&str, std::result::Result<Vec<u8>, SkipReason>),
+) -> Result<Option<SkipReason>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut reader = EntryReader {
+        archive: walker.relative_path(path).to_path_buf(),
+        walker,
+        entries: 0,
+        size: 0,
+    };
+    match format {
+        ArchiveFormat::Zip => reader.read_zip(file, &mut visit),
+        ArchiveFormat::Tar => reader.read_tar(BufReader::new(file), &mut visit),
+        ArchiveFormat::TarGz => reader.read_tar(GzDecoder::new(BufReader::new(file)), &mut visit),
+    }
+    .with_context(|| format!("Failed to read archive {}", path.display()))
+}
+
+/// Entries and bytes read so far from an archive
+struct EntryReader<'a> {
+    /// Path of the archive relative to the walked directory
+    archive: PathBuf,
+    walker: &'a Walker,
+    entries: usize,
+    size: u64,
+}
+
+impl EntryReader<'_> {
+    fn read_zip(
+        &mut self,
+        file: File,
+        visit: &mut impl FnMut(&str, std::result::Result<Vec<u8>, SkipReason>),
+    ) -> Result<Option<SkipReason>> {
+        let mut archive = zip::ZipArchive::new(BufReader::new(file))?;
+        for i in 0..archive.len() {
+            let name = archive.name_for_index(i).unwrap_or_default().to_string();
+            let mut entry = match archive.by_index(i) {
+                Ok(entry) => entry,
+                // encrypted entries and unsupported compression methods
+                Err(e) => {
+                    visit(
+                        entry_name(&name),
+                        Err(SkipReason::Unreadable(e.to_string())),
+                    );
+                    continue;
+                }
+            };
+            if !entry.is_file() {
+                continue;
+            }
+            if let Some(limit) = self.count_entry() {
+                return Ok(Some(limit));
+            }
+            if let Some(reason) = self.check_entry(&name, entry.size()) {
+                visit(entry_name(&name), Err(reason));
+                continue;
+            }
+            if entry.size() > RATIO_CHECK_SIZE
+                && entry.size() / entry.compressed_size().max(1) > MAX_COMPRESSION_RATIO
+            {
+                let reason = format!("compression ratio above {}", MAX_COMPRESSION_RATIO);
+                visit(entry_name(&name), Err(SkipReason::ArchiveLimit(reason)));
+                continue;
+            }
+
+            // the sizes in the headers can't be trusted, reading stops past the remaining budget
+            let mut bytes = Vec::new();
+            entry
+                .by_ref()
+                .take(self.remaining_size().saturating_add(1))
+                .read_to_end(&mut bytes)
+                .with_context(|| format!("Failed to read {}", name))?;
+            if let Some(limit) = self.count_size(bytes.len() as u64) {
+                return Ok(Some(limit));
+            }
+            match self.check_entry(&name, bytes.len() as u64) {
+                Some(reason) => visit(entry_name(&name), Err(reason)),
+                None => visit(entry_name(&name), Ok(bytes)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_tar(
+        &mut self,
+        reader: impl Read,
+        visit: &mut impl FnMut(&str, std::result::Result<Vec<u8>, SkipReason>),
+    ) -> Result<Option<SkipReason>> {
+        let mut archive = tar::Archive::new(reader);
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            if let Some(limit) = self.count_entry() {
+                return Ok(Some(limit));
+            }
+            // entries are decompressed even when skipped, their size always counts
+            let size = entry.size();
+            if let Some(limit) = self.count_size(size) {
+                return Ok(Some(limit));
+            }
+            let name = entry.path()?.to_string_lossy().into_owned();
+            if let Some(reason) = self.check_entry(&name, size) {
+                visit(entry_name(&name), Err(reason));
+                continue;
+            }
+
+            let mut bytes = Vec::new();
+            entry
+                .read_to_end(&mut bytes)
+                .with_context(|| format!("Failed to read {}", name))?;
+            visit(entry_name(&name), Ok(bytes));
+        }
+        Ok(None)
+    }
+
+    /// Count a file entry, the limit it is above
+    fn count_entry(&mut self) -> Option<SkipReason> {
+        self.entries += 1;
+        let max_entries = self.walker.archive_limits().max_entries;
+        (max_entries > 0 && self.entries > max_entries)
+            .then(|| SkipReason::ArchiveLimit(format!("more than {} entries", max_entries)))
+    }
+
+    /// Count the expanded bytes of an entry, the limit they are above
+    fn count_size(&mut self, size: u64) -> Option<SkipReason> {
+        self.size = self.size.saturating_add(size);
+        let max_size = self.walker.archive_limits().max_size;
+        (max_size > 0 && self.size > max_size)
+            .then(|| SkipReason::ArchiveLimit(format!("expands beyond {} bytes", max_size)))
+    }
+
+    fn remaining_size(&self) -> u64 {
+        match self.walker.archive_limits().max_size {
+            0 => u64::MAX,
+            max_size => max_size.saturating_sub(self.size),
+        }
+    }
+
+    fn check_entry(&self, name: &str, size: u64) -> Option<SkipReason> {
+        self.walker
+            .check_entry(&entry_path(&self.archive, entry_name(name)), size)
+    }
+}
+
+/// Path of an entry without a leading `./` or `/`
+fn entry_name(name: &str) -> &str {
+    name.trim_start_matches("./").trim_start_matches('/')
+}
@@ -0,0 +1,242 @@
+use crate::file_loader::{Language, Sizer};
+use crate::logs::LogFormat;
+use anyhow::{anyhow, Context, Result};
+use configs::constants::DEFAULT_CHUNK_OVERLAP;
+use configs::settings::{settings, ChunkRule};
+use glob::Pattern;
+use std::path::Path;
+use text_splitter::ChunkConfig;
+
+/// Splitter how the text of a file is cut into chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Splitter {
+    /// Syntax aware splitting with the tree-sitter grammar of the language
+    Code,
+    /// Sentences and paragraphs of plain text
+    Text,
+    /// Headings, blocks and paragraphs of markdown
+    Markdown,
+    /// Entries of toml, yaml and json under their key path
+    KeyPath,
+    /// Entries of a log with their stack traces
+    Log,
+    /// Failed stages and jobs of a spark event log
+    SparkEvents,
+}
+
+impl Splitter {
+    pub fn parse_splitter(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "code" => Ok(Splitter::Code),
+            "text" => Ok(Splitter::Text),
+            "markdown" | "md" => Ok(Splitter::Markdown),
+            "keys" | "key_path" => Ok(Splitter::KeyPath),
+            "log" | "logs" => Ok(Splitter::Log),
+            "spark" | "spark_events" => Ok(Splitter::SparkEvents),
+            _ => Err(anyhow!(
+                "Unsupported splitter: {} expected code, text, markdown, keys, log or spark",
+                s
+            )),
+        }
+    }
+}
+
+/// ChunkSpec chunking of a single file
+#[derive(Debug, Clone)]
+pub struct ChunkSpec {
+    pub size: usize,
+    /// Overlap between neighbouring chunks, None caps the default overlap at a quarter of the size
+    pub overlap: Option<usize>,
+    pub sizer: Sizer,
+    /// None picks the splitter from the language of the file
+    pub splitter: Option<Splitter>,
+    pub trim: bool,
+}
+
+impl ChunkSpec {
+    pub fn new(size: usize, sizer: Sizer) -> Self {
+        ChunkSpec {
+            size,
+            overlap: None,
+            sizer,
+            splitter: None,
+            trim: true,
+        }
+    }
+
+    /// Read the chunk size, overlap and sizer from the settings
+    pub fn from_settings() -> Result<Self> {
+        let settings = settings();
+        Ok(ChunkSpec {
+            overlap: settings.chunk_overlap.value,
+            ..ChunkSpec::new(
+                settings.chunk_size.value,
+                Sizer::parse_sizer(&settings.chunk_sizer.value)?,
+            )
+        })
+    }
+
+    /// Overlap used for the chunks of this spec
+    pub fn overlap(&self) -> usize {
+        self.overlap
+            .unwrap_or_else(|| DEFAULT_CHUNK_OVERLAP.min(self.size / 4))
+    }
+
+    /// Build the text-splitter config of this spec
+    pub fn chunk_config(&self) -> Result<ChunkConfig<Sizer>> {
+        let overlap = self.overlap();
+        let chunk_config = ChunkConfig::new(self.size)
+            .with_sizer(self.sizer.clone())
+            .with_overlap(overlap)
+            .with_context(|| {
+                format!(
+                    "Chunk overlap {} must be smaller than the chunk size {}",
+                    overlap, self.size
+                )
+            })?
+            .with_trim(self.trim);
+        Ok(chunk_config)
+    }
+}
+
+/// A config rule with its language, glob and sizer parsed
+#[derive(Debug, Clone)]
+struct PolicyRule {
+    language: Option<Language>,
+    glob: Option<Pattern>,
+    size: Option<usize>,
+    overlap: Option<usize>,
+    sizer: Option<Sizer>,
+    splitter: Option<Splitter>,
+    trim: Option<bool>,
+}
+
+impl PolicyRule {
+    fn parse_rule(rule: &ChunkRule) -> Result<Self> {
+        if rule.language.is_none() && rule.glob.is_none() {
+            return Err(anyhow!("Chunking rule needs a language or a glob"));
+        }
+        let language = match &rule.language {
+            Some(name) => match Language::parse_name(name) {
+                Language::UNKNOWN => return Err(anyhow!("Unknown language: {}", name)),
+                language => Some(language),
+            },
+            None => None,
+        };
+        let glob = match &rule.glob {
+            Some(glob) => {
+                Some(Pattern::new(glob).with_context(|| format!("Invalid glob: {}", glob))?)
+            }
+            None => None,
+        };
+
+        Ok(PolicyRule {
+            language,
+            glob,
+            size: rule.size,
+            overlap: rule.overlap,
+            sizer: rule.sizer.as_deref().map(Sizer::parse_sizer).transpose()?,
+            splitter: rule
+                .splitter
+                .as_deref()
+                .map(Splitter::parse_splitter)
+                .transpose()?,
+            trim: rule.trim,
+        })
+    }
+
+    /// A rule with both a language and a glob only matches files that satisfy both
+    fn matches(&self, relative_path: &Path, language: Language) -> bool {
+        self.language.is_none_or(|l| l == language)
+            && self
+                .glob
+                .as_ref()
+                .is_none_or(|glob| glob.matches_path(relative_path))
+    }
+
+    fn apply(&self, spec: &ChunkSpec) -> ChunkSpec {
+        ChunkSpec {
+            size: self.size.unwrap_or(spec.size),
+            overlap: self.overlap.or(spec.overlap),
+            sizer: self.sizer.clone().unwrap_or_else(|| spec.sizer.clone()),
+            splitter: self.splitter.or(spec.splitter),
+            trim: self.trim.unwrap_or(spec.trim),
+        }
+    }
+}
+
+/// ChunkPolicy chunk size, overlap, sizer and splitter per language and glob,
+/// files without a matching rule use the default spec
+#[derive(Debug, Clone)]
+pub struct ChunkPolicy {
+    pub default: ChunkSpec,
+    rules: Vec<PolicyRule>,
+    /// Timestamp and level patterns of the files split as logs
+    pub log_format: LogFormat,
+}
+
+impl ChunkPolicy {
+    /// Policy chunking every file with the same spec
+    pub fn new(default: ChunkSpec) -> Self {
+        ChunkPolicy {
+            default,
+            rules: Vec::new(),
+            log_format: LogFormat::default(),
+        }
+    }
+
+    /// Policy with the rules of the config file on top of the default spec
+    /// Arguments:
+    /// - default: ChunkSpec for files without a matching rule
+    /// - rules: &[ChunkRule] from the `[[chunking]]` tables of the config file
+    ///
+    /// Returns:
+    /// - Result<ChunkPolicy>
+    pub fn with_rules(default: ChunkSpec, rules: &[ChunkRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                PolicyRule::parse_rule(rule)
+                    .with_context(|| format!("Invalid chunking rule {}", i + 1))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ChunkPolicy {
+            default,
+            rules,
+            log_format: LogFormat::default(),
+        })
+    }
+
+    /// Policy splitting logs with the timestamp and level patterns of `log_format`
+    pub fn with_log_format(mut self, log_format: LogFormat) -> Self {
+        self.log_format = log_format;
+        self
+    }
+
+    /// Policy from the chunk settings, the chunking rules and the log patterns of the config file
+    pub fn from_settings() -> Result<Self> {
+        Ok(
+            ChunkPolicy::with_rules(ChunkSpec::from_settings()?, &settings().chunking.value)?
+                .with_log_format(LogFormat::from_settings()?),
+        )
+    }
+
+    /// Spec of a file, the first rule matching the language or the path wins
+    /// Arguments:
+    /// - relative_path: &Path path of the file relative to the loaded directory
+    /// - language: Language of the file
+    ///
+    /// Returns:
+    /// - ChunkSpec
+    pub fn spec_for(&self, relative_path: &Path, language: Language) -> ChunkSpec {
+        match self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(relative_path, language))
+        {
+            Some(rule) => rule.apply(&self.default),
+            None => self.default.clone(),
+        }
+    }
+}
@@ -0,0 +1,539 @@
+use crate::file_loader::Language;
+use anyhow::{Context, Result};
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Leading bytes of a file read to detect its language from its content
+pub const DETECT_BYTES: u64 = 8192;
+
+/// Trailing bytes of a file longer than `DETECT_BYTES` read to find a modeline at its end
+const TAIL_BYTES: u64 = 1024;
+
+/// Lowest confidence the file loader accepts a detection with
+pub const MIN_CONFIDENCE: f32 = 0.5;
+
+/// Files known by their name
+const FILE_NAMES: [(&str, Language); 17] = [
+    ("Dockerfile", Language::Dockerfile),
+    ("Containerfile", Language::Dockerfile),
+    ("Makefile", Language::Makefile),
+    ("makefile", Language::Makefile),
+    ("GNUmakefile", Language::Makefile),
+    ("Gemfile", Language::Ruby),
+    ("Rakefile", Language::Ruby),
+    ("Vagrantfile", Language::Ruby),
+    ("Podfile", Language::Ruby),
+    ("Jenkinsfile", Language::Groovy),
+    (".bashrc", Language::Bash),
+    (".bash_profile", Language::Bash),
+    (".profile", Language::Bash),
+    (".zshrc", Language::Bash),
+    ("Cargo.lock", Language::Toml),
+    ("Pipfile", Language::Toml),
+    ("poetry.lock", Language::Toml),
+];
+
+/// Suffixes of templates and copies, `build.rs.in` is detected from `build.rs`
+const TEMPLATE_SUFFIXES: [&str; 11] = [
+    "in", "tmpl", "template", "tpl", "j2", "dist", "example", "sample", "orig", "bak", "old",
+];
+
+/// Interpreters of a shebang line, version suffixes e.g. `python3.12` are dropped
+const INTERPRETERS: [(&str, Language); 18] = [
+    ("python", Language::Python),
+    ("pypy", Language::Python),
+    ("sh", Language::Bash),
+    ("bash", Language::Bash),
+    ("zsh", Language::Bash),
+    ("dash", Language::Bash),
+    ("ksh", Language::Bash),
+    ("node", Language::JavaScript),
+    ("nodejs", Language::JavaScript),
+    ("deno", Language::TypeScript),
+    ("ts-node", Language::TypeScript),
+    ("ruby", Language::Ruby),
+    ("scala", Language::Scala),
+    ("kotlin", Language::Kotlin),
+    ("groovy", Language::Groovy),
+    ("make", Language::Makefile),
+    ("rust-script", Language::Rust),
+    ("cargo", Language::Rust),
+];
+
+/// Signature line prefixes of a language, the language with the most matching lines wins
+struct Signature {
+    language: Language,
+    prefixes: &'static [&'static str],
+    ignore_case: bool,
+}
+
+const SIGNATURES: [Signature; 14] = [
+    Signature {
+        language: Language::Rust,
+        prefixes: &[
+            "fn ",
+            "pub fn ",
+            "use std::",
+            "impl ",
+            "#[derive(",
+            "let mut ",
+            "pub struct ",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Python,
+        prefixes: &[
+            "def ",
+            "import ",
+            "from ",
+            "if __name__ ==",
+            "elif ",
+            "print(",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Go,
+        prefixes: &["package ", "func ", "import (", "type ", "go "],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Java,
+        prefixes: &[
+            "import java.",
+            "public class ",
+            "package ",
+            "@Override",
+            "private final ",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::C,
+        prefixes: &[
+            "#include <",
+            "#include \"",
+            "#define ",
+            "int main(",
+            "typedef struct",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Cpp,
+        prefixes: &[
+            "#include <iostream>",
+            "using namespace ",
+            "template <",
+            "template<",
+            "std::",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::CSharp,
+        prefixes: &[
+            "using System",
+            "namespace ",
+            "[assembly:",
+            "public sealed class ",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::JavaScript,
+        prefixes: &[
+            "const ",
+            "function ",
+            "module.exports",
+            "export default ",
+            "console.log(",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Ruby,
+        prefixes: &["require '", "require \"", "def ", "end", "module ", "puts "],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Bash,
+        prefixes: &[
+            "echo ", "export ", "if [", "fi", "then", "set -e", "done", "source ",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Sql,
+        prefixes: &[
+            "SELECT ",
+            "CREATE TABLE",
+            "INSERT INTO",
+            "DELETE FROM",
+            "ALTER TABLE",
+            "FROM ",
+        ],
+        ignore_case: true,
+    },
+    Signature {
+        language: Language::Dockerfile,
+        prefixes: &[
+            "FROM ",
+            "RUN ",
+            "COPY ",
+            "WORKDIR ",
+            "ENTRYPOINT ",
+            "CMD ",
+            "EXPOSE ",
+        ],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Markdown,
+        prefixes: &["## ", "### ", "```", "- [", "> "],
+        ignore_case: false,
+    },
+    Signature {
+        language: Language::Html,
+        prefixes: &["<!DOCTYPE HTML", "<HTML", "<HEAD>", "<BODY", "<DIV"],
+        ignore_case: true,
+    },
+];
+
+/// DetectionMethod what a language was detected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionMethod {
+    /// Well-known file name e.g. `Dockerfile`
+    FileName,
+    /// File extension, also under a template suffix e.g. `build.rs.in`
+    Extension,
+    /// Interpreter of a `#!` line
+    Shebang,
+    /// Vim or Emacs modeline e.g. `# vim: set ft=python:`
+    Modeline,
+    /// Signature lines of the content
+    Content,
+    /// Nothing matched
+    Undetected,
+}
+
+impl fmt::Display for DetectionMethod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            DetectionMethod::FileName => "file name",
+            DetectionMethod::Extension => "extension",
+            DetectionMethod::Shebang => "shebang",
+            DetectionMethod::Modeline => "modeline",
+            DetectionMethod::Content => "content",
+            DetectionMethod::Undetected => "undetected",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Detection language of a file with the confidence of the detection from 0 to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Detection {
+    pub language: Language,
+    pub confidence: f32,
+    pub method: DetectionMethod,
+}
+
+impl Detection {
+    fn new(language: Language, confidence: f32, method: DetectionMethod) -> Self {
+        Detection {
+            language,
+            confidence,
+            method,
+        }
+    }
+
+    /// Detection of a file nothing was recognized in, UNKNOWN with no confidence
+    pub fn undetected() -> Self {
+        Detection::new(Language::UNKNOWN, 0.0, DetectionMethod::Undetected)
+    }
+
+    pub fn is_detected(&self) -> bool {
+        self.language != Language::UNKNOWN
+    }
+}
+
+/// Detect the language of a file from its path only, well-known names before extensions
+/// Arguments:
+/// - path: &Path of the file, it isn't read
+///
+/// Returns:
+/// - Detection undetected when neither the name nor the extension is known
+pub fn detect_path(path: &Path) -> Detection {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    if let Some((_, language)) = FILE_NAMES.iter().find(|(n, _)| *n == name) {
+        return Detection::new(*language, 1.0, DetectionMethod::FileName);
+    }
+    if name.starts_with("Dockerfile.") {
+        return Detection::new(Language::Dockerfile, 1.0, DetectionMethod::FileName);
+    }
+
+    let extension = path.extension().and_then(|e| e.to_str());
+    match extension.map(Language::parse_language) {
+        Some(Language::UNKNOWN) | None => {}
+        Some(language) => return Detection::new(language, 0.9, DetectionMethod::Extension),
+    }
+    // `build.rs.in` is a template of a rust file
+    match (extension, path.file_stem()) {
+        (Some(extension), Some(stem)) if TEMPLATE_SUFFIXES.contains(&extension) => {
+            let detection = detect_path(Path::new(stem));
+            Detection {
+                confidence: detection.confidence * 0.8,
+                ..detection
+            }
+        }
+        // rotated logs e.g. `app.log.1`
+        (Some(extension), Some(stem))
+            if extension.chars().all(|c| c.is_ascii_digit())
+                && Path::new(stem).extension() == Some(OsStr::new("log")) =>
+        {
+            Detection::new(Language::Log, 0.9, DetectionMethod::Extension)
+        }
+        _ => Detection::undetected(),
+    }
+}
+
+/// Detect the language of a file from its path and the start of its content, the content
+/// is only looked at when the path isn't enough: shebang, then modeline, then signature lines
+/// Arguments:
+/// - path: &Path of the file
+/// - content: &str the whole file or its first bytes
+///
+/// Returns:
+/// - Detection undetected when nothing matched
+pub fn detect_language(path: &Path, content: &str) -> Detection {
+    let detection = detect_path(path);
+    if detection.is_detected() {
+        return detection;
+    }
+    detect_text(content, None)
+}
+
+/// Shebang, then modeline of the head or of the tail, then signature lines of the head
+fn detect_text(head: &str, tail: Option<&str>) -> Detection {
+    detect_shebang(head)
+        .or_else(|| detect_modeline(head))
+        .or_else(|| tail.and_then(detect_trailing_modeline))
+        .or_else(|| detect_content(head))
+        .unwrap_or_else(Detection::undetected)
+}
+
+/// Detect the language of a file on disk, its first `DETECT_BYTES` and its last `TAIL_BYTES`
+/// are read when the path isn't enough, binary content is never detected
+/// Arguments:
+/// - path: &Path of the file
+///
+/// Returns:
+/// - Result<Detection> an error when the file can't be read
+pub fn detect_file(path: &Path) -> Result<Detection> {
+    let detection = detect_path(path);
+    if detection.is_detected() {
+        return Ok(detection);
+    }
+    let (mut head, mut tail) = (Vec::new(), Vec::new());
+    std::fs::File::open(path)
+        .and_then(|mut file| {
+            file.by_ref().take(DETECT_BYTES).read_to_end(&mut head)?;
+            let len = file.metadata()?.len();
+            if len > DETECT_BYTES {
+                let start = len.saturating_sub(TAIL_BYTES).max(DETECT_BYTES);
+                file.seek(SeekFrom::Start(start))?;
+                file.read_to_end(&mut tail)?;
+            }
+            Ok(())
+        })
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(detect_head_and_tail(&head, &tail))
+}
+
+/// Detect the language of a file read into memory e.g. an archive entry, only its first
+/// `DETECT_BYTES` and last `TAIL_BYTES` are looked at and binary content is never detected
+/// Arguments:
+/// - path: &Path of the file
+/// - bytes: &[u8] the whole file or its first bytes
+///
+/// Returns:
+/// - Detection undetected when nothing matched
+pub fn detect_bytes(path: &Path, bytes: &[u8]) -> Detection {
+    let detection = detect_path(path);
+    if detection.is_detected() {
+        return detection;
+    }
+    let split = bytes.len().min(DETECT_BYTES as usize);
+    let tail_start = bytes.len().saturating_sub(TAIL_BYTES as usize).max(split);
+    detect_head_and_tail(&bytes[..split], &bytes[tail_start..])
+}
+
+/// Detect the language of the head of a file and, when the file is longer, of its tail
+fn detect_head_and_tail(head: &[u8], tail: &[u8]) -> Detection {
+    if head.contains(&0) {
+        return Detection::undetected();
+    }
+    let tail = (!tail.is_empty() && !tail.contains(&0)).then(|| String::from_utf8_lossy(tail));
+    detect_text(&String::from_utf8_lossy(head), tail.as_deref())
+}
+
+/// Language of the interpreter of a `#!` line e.g. `#!/usr/bin/env -S python3 -u`
+fn detect_shebang(content: &str) -> Option<Detection> {
+    let line = content.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        // options and variables of env come before the program
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+    let program = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let (_, language) = INTERPRETERS.iter().find(|(p, _)| *p == program)?;
+    Some(Detection::new(*language, 0.9, DetectionMethod::Shebang))
+}
+
+/// Language of a vim or emacs modeline in the last lines of the tail of a long file
+fn detect_trailing_modeline(tail: &str) -> Option<Detection> {
+    let lines: Vec<&str> = tail.lines().collect();
+    lines[lines.len().saturating_sub(5)..]
+        .iter()
+        .find_map(|line| modeline_language(line))
+        .map(|language| Detection::new(language, 0.95, DetectionMethod::Modeline))
+}
+
+/// Language of a vim or emacs modeline in the first or last lines of the content
+/// e.g. `# vim: set ft=python:`, `// vi: syntax=rust` or `# -*- mode: ruby -*-`
+fn detect_modeline(content: &str) -> Option<Detection> {
+    let lines: Vec<&str> = content.lines().collect();
+    let tail = lines.len().saturating_sub(5).max(5.min(lines.len()));
+    lines[..5.min(lines.len())]
+        .iter()
+        .chain(&lines[tail..])
+        .find_map(|line| modeline_language(line))
+        .map(|language| Detection::new(language, 0.95, DetectionMethod::Modeline))
+}
+
+fn modeline_language(line: &str) -> Option<Language> {
+    let name = if let Some((_, emacs)) = line.split_once("-*-") {
+        let emacs = emacs.split("-*-").next()?.trim();
+        // `-*- mode: python; coding: utf-8 -*-` or just `-*- python -*-`
+        match emacs
+            .split(';')
+            .find_map(|v| v.trim().strip_prefix("mode:"))
+        {
+            Some(mode) => mode.trim(),
+            None if !emacs.contains(':') => emacs,
+            None => return None,
+        }
+    } else {
+        let (_, vim) = ["vim:", "vi:", "ex:"]
+            .iter()
+            .find_map(|marker| line.split_once(marker))?;
+        vim.split([' ', ':'])
+            .find_map(|option| {
+                ["ft=", "filetype=", "syntax=", "syn="]
+                    .iter()
+                    .find_map(|key| option.strip_prefix(key))
+            })?
+            .trim()
+    };
+    let language = match name.to_lowercase().as_str() {
+        "sh" | "shell-script" => Language::Bash,
+        "c++" => Language::Cpp,
+        "js" => Language::JavaScript,
+        "cs" => Language::CSharp,
+        name => Language::parse_name(name),
+    };
+    (language != Language::UNKNOWN).then_some(language)
+}
+
+/// A single json value, or a head cut at `DETECT_BYTES` that is valid json up to the cut
+fn is_json(content: &str) -> bool {
+    let mut values =
+        serde_json::Deserializer::from_str(content).into_iter::<serde::de::IgnoredAny>();
+    match values.next() {
+        Some(Ok(_)) => values.next().is_none(),
+        // a shorter content is the whole file, which must be complete
+        Some(Err(e)) => e.is_eof() && content.len() >= DETECT_BYTES as usize,
+        None => false,
+    }
+}
+
+/// Language with the most signature lines, at least two lines and more than any other language
+fn detect_content(content: &str) -> Option<Detection> {
+    let trimmed = content.trim_start();
+    // spark event logs are json lines starting with the listener events
+    if trimmed.starts_with("{\"Event\":\"SparkListener") {
+        return Some(Detection::new(
+            Language::SPARKLOG,
+            0.95,
+            DetectionMethod::Content,
+        ));
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('[')) && is_json(content) {
+        return Some(Detection::new(
+            Language::Json,
+            0.8,
+            DetectionMethod::Content,
+        ));
+    }
+    if trimmed.starts_with("---\n") {
+        return Some(Detection::new(
+            Language::Yaml,
+            0.5,
+            DetectionMethod::Content,
+        ));
+    }
+
+    let lines: Vec<&str> = content.lines().map(str::trim_start).collect();
+    let mut scores: Vec<(Language, usize)> = SIGNATURES
+        .iter()
+        .map(|signature| {
+            let hits = lines
+                .iter()
+                .filter(|line| {
+                    signature
+                        .prefixes
+                        .iter()
+                        .any(|prefix| starts_with(line, prefix, signature.ignore_case))
+                })
+                .count();
+            (signature.language, hits)
+        })
+        .collect();
+    scores.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+    let (language, hits) = scores[0];
+    if hits < 2 || scores[1].1 == hits {
+        return None;
+    }
+    // more signature lines ahead of the runner up give more confidence, guesses stay below 0.8
+    let lead = (hits - scores[1].1) as f32 / hits as f32;
+    let confidence = (0.3 + 0.05 * hits as f32 + 0.2 * lead).min(0.75);
+    Some(Detection::new(
+        language,
+        confidence,
+        DetectionMethod::Content,
+    ))
+}
+
+/// A line starts with a signature, a signature ending in a word e.g. `fi` only matches the
+/// whole word
+fn starts_with(line: &str, prefix: &str, ignore_case: bool) -> bool {
+    let Some(head) = line.get(..prefix.len()) else {
+        return false;
+    };
+    let matches = if ignore_case {
+        head.eq_ignore_ascii_case(prefix)
+    } else {
+        head == prefix
+    };
+    let word_end = !prefix.ends_with(|c: char| c.is_alphanumeric())
+        || !line[prefix.len()..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+    matches && word_end
+}
@@ -0,0 +1,399 @@
+use anyhow::{anyhow, Context, Result};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const CACHE_MAGIC: &[u8; 8] = b"RAGEMBC1";
+const CACHE_EXT: &str = "embcache";
+const STATS_FILE: &str = "cache_stats.json";
+
+/// Sha256 of the text sent to the embedding model
+pub type ContentHash = [u8; 32];
+
+pub fn content_hash(text: &str) -> ContentHash {
+    Sha256::digest(text.as_bytes()).into()
+}
+
+/// Directory of the embedding cache kept next to a lance database
+pub fn cache_dir(db_uri: &str) -> PathBuf {
+    PathBuf::from(format!("{}_embed_cache", db_uri.trim_end_matches('/')))
+}
+
+/// CacheKey embeddings are only reused for the same provider, model and output size
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub provider: String,
+    pub model: String,
+    pub dimensions: Option<u32>,
+}
+
+impl CacheKey {
+    pub fn new(provider: &str, model: &str, dimensions: Option<u32>) -> Self {
+        CacheKey {
+            provider: provider.to_lowercase(),
+            model: model.to_string(),
+            dimensions,
+        }
+    }
+
+    /// File name safe for model names like `qwen2:7b` or `BAAI/bge-m3`, the hash of the full
+    /// key keeps `qwen2:7b` and `qwen2_7b` in separate files
+    fn file_name(&self) -> String {
+        let name: String = self
+            .to_string()
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+                _ => '_',
+            })
+            .collect();
+        let hash: String = Sha256::digest(self.to_string().as_bytes())[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        format!("{}-{}.{}", name, hash, CACHE_EXT)
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.dimensions {
+            Some(dimensions) => write!(f, "{}/{}@{}", self.provider, self.model, dimensions),
+            None => write!(f, "{}/{}", self.provider, self.model),
+        }
+    }
+}
+
+/// CacheStats hit and miss counters of a cache key
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+}
+
+impl fmt::Display for CacheStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Embedding cache hits: {}, misses: {}, hit rate: {:.1}%",
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.0
+        )
+    }
+}
+
+/// CacheSummary one cached provider and model on disk
+#[derive(Debug, Clone)]
+pub struct CacheSummary {
+    pub key: CacheKey,
+    pub entries: usize,
+    pub bytes: u64,
+    /// Hits and misses of every load that used this cache
+    pub stats: CacheStats,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct StatsEntry {
+    key: CacheKey,
+    stats: CacheStats,
+}
+
+#[derive(Debug, Default)]
+struct CacheFile {
+    entries: CacheEntries,
+    stats: CacheStats,
+}
+
+/// EmbedCache persistent embeddings keyed by (provider, model, hash of the text),
+/// each key is an append only file so a load can be interrupted without losing the cache
+#[derive(Debug)]
+pub struct EmbedCache {
+    dir: PathBuf,
+    files: Mutex<HashMap<CacheKey, CacheFile>>,
+}
+
+impl EmbedCache {
+    /// Open the cache directory, it is created when missing
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create cache directory {}", dir.display()))?;
+        Ok(EmbedCache {
+            dir,
+            files: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Look up the cached embeddings, None for every hash that is not cached
+    pub fn get_many(
+        &self,
+        key: &CacheKey,
+        hashes: &[ContentHash],
+    ) -> Result<Vec<Option<Vec<f32>>>> {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let file = self.load(&mut files, key)?;
+        let found: Vec<_> = hashes
+            .iter()
+            .map(|hash| file.entries.get(hash).cloned())
+            .collect();
+        let hits = found.iter().filter(|e| e.is_some()).count() as u64;
+        file.stats.hits += hits;
+        file.stats.misses += hashes.len() as u64 - hits;
+        Ok(found)
+    }
+
+    /// Store the embeddings, they are appended to the cache file right away
+    pub fn put_many(&self, key: &CacheKey, entries: Vec<(ContentHash, Vec<f32>)>) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let file = self.load(&mut files, key)?;
+
+        let path = self.dir.join(key.file_name());
+        let is_new = !path.exists();
+        let mut buf = Vec::new();
+        if is_new {
+            let header = serde_json::to_vec(key)?;
+            buf.extend_from_slice(CACHE_MAGIC);
+            buf.extend_from_slice(&(header.len() as u32).to_le_bytes());
+            buf.extend_from_slice(&header);
+        }
+        for (hash, embedding) in &entries {
+            buf.extend_from_slice(hash);
+            buf.extend_from_slice(&(embedding.len() as u32).to_le_bytes());
+            for value in embedding {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| f.write_all(&buf))
+            .with_context(|| format!("Failed to write cache file {}", path.display()))?;
+
+        file.entries.extend(entries);
+        Ok(())
+    }
+
+    /// Hits and misses of this run over every key
+    pub fn stats(&self) -> CacheStats {
+        let files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        files
+            .values()
+            .fold(CacheStats::default(), |acc, file| CacheStats {
+                hits: acc.hits + file.stats.hits,
+                misses: acc.misses + file.stats.misses,
+            })
+    }
+
+    /// Add the counters of this run to the totals shown by `cache stats`
+    pub fn save_stats(&self) -> Result<()> {
+        let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+        let mut totals = read_stats(&self.dir)?;
+        for (key, file) in files.iter_mut() {
+            let total = totals.entry(key.clone()).or_default();
+            total.hits += file.stats.hits;
+            total.misses += file.stats.misses;
+            file.stats = CacheStats::default();
+        }
+        write_stats(&self.dir, &totals)
+    }
+
+    fn load<'a>(
+        &self,
+        files: &'a mut HashMap<CacheKey, CacheFile>,
+        key: &CacheKey,
+    ) -> Result<&'a mut CacheFile> {
+        if !files.contains_key(key) {
+            let path = self.dir.join(key.file_name());
+            let entries = if path.exists() {
+                let (file_key, entries, valid_len) = read_cache_file(&path)?;
+                if &file_key != key {
+                    return Err(anyhow!(
+                        "Cache file {} holds the embeddings of {} not {}",
+                        path.display(),
+                        file_key,
+                        key
+                    ));
+                }
+                // drop the truncated record so the next append starts on a record boundary
+                if valid_len < fs::metadata(&path)?.len() {
+                    OpenOptions::new()
+                        .write(true)
+                        .open(&path)
+                        .and_then(|f| f.set_len(valid_len))
+                        .with_context(|| {
+                            format!("Failed to repair cache file {}", path.display())
+                        })?;
+                }
+                entries
+            } else {
+                HashMap::new()
+            };
+            debug!("Loaded {} cached embeddings for {}", entries.len(), key);
+            files.insert(
+                key.clone(),
+                CacheFile {
+                    entries,
+                    stats: CacheStats::default(),
+                },
+            );
+        }
+        Ok(files.get_mut(key).expect("cache file was just loaded"))
+    }
+}
+
+/// List the cached providers and models with their size and hit rate
+pub fn summarize(dir: &Path) -> Result<Vec<CacheSummary>> {
+    Ok(summarize_files(dir)?
+        .into_iter()
+        .map(|(_, summary)| summary)
+        .collect())
+}
+
+/// Summaries with the path of their cache file, the key is read from the file header
+fn summarize_files(dir: &Path) -> Result<Vec<(PathBuf, CacheSummary)>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let totals = read_stats(dir)?;
+    let mut summaries = Vec::new();
+    for path in cache_files(dir)? {
+        let (key, entries, _) = read_cache_file(&path)?;
+        let summary = CacheSummary {
+            stats: totals.get(&key).copied().unwrap_or_default(),
+            key,
+            entries: entries.len(),
+            bytes: fs::metadata(&path)?.len(),
+        };
+        summaries.push((path, summary));
+    }
+    summaries.sort_by_key(|(_, s)| s.key.to_string());
+    Ok(summaries)
+}
+
+/// Remove the cached embeddings of the given models, returns what was removed
+pub fn prune(dir: &Path, models: &[String]) -> Result<Vec<CacheSummary>> {
+    let removed: Vec<_> = summarize_files(dir)?
+        .into_iter()
+        .filter(|(_, s)| models.contains(&s.key.model))
+        .collect();
+    if removed.is_empty() {
+        return Ok(vec![]);
+    }
+
+    for (path, _) in &removed {
+        fs::remove_file(path)
+            .with_context(|| format!("Failed to remove cache file {}", path.display()))?;
+    }
+    let mut totals = read_stats(dir)?;
+    totals.retain(|key, _| !models.contains(&key.model));
+    write_stats(dir, &totals)?;
+    Ok(removed.into_iter().map(|(_, summary)| summary).collect())
+}
+
+fn cache_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)
+        .with_context(|| format!("Failed to read cache directory {}", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) == Some(CACHE_EXT) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// CacheEntries embeddings of a cache file by content hash
+type CacheEntries = HashMap<ContentHash, Vec<f32>>;
+
+/// Read a cache file, a truncated last record from an interrupted write is skipped,
+/// returns the key, the entries and the length of the valid records
+fn read_cache_file(path: &Path) -> Result<(CacheKey, CacheEntries, u64)> {
+    let data =
+        fs::read(path).with_context(|| format!("Failed to read cache file {}", path.display()))?;
+    if data.len() < 12 || &data[..8] != CACHE_MAGIC {
+        return Err(anyhow!("Invalid cache file {}", path.display()));
+    }
+    let header_len = u32::from_le_bytes(data[8..12].try_into()?) as usize;
+    let header = data
+        .get(12..12 + header_len)
+        .ok_or_else(|| anyhow!("Invalid cache file header {}", path.display()))?;
+    let key: CacheKey = serde_json::from_slice(header)
+        .with_context(|| format!("Invalid cache file header {}", path.display()))?;
+
+    let mut entries = HashMap::new();
+    let mut pos = 12 + header_len;
+    while pos < data.len() {
+        let Some(record) = data.get(pos..pos + 36) else {
+            break;
+        };
+        let hash: ContentHash = record[..32].try_into()?;
+        let dim = u32::from_le_bytes(record[32..36].try_into()?) as usize;
+        let Some(values) = data.get(pos + 36..pos + 36 + dim * 4) else {
+            break;
+        };
+        let embedding = values
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        entries.insert(hash, embedding);
+        pos += 36 + dim * 4;
+    }
+    if pos < data.len() {
+        warn!(
+            "Ignoring truncated record at the end of cache file {}",
+            path.display()
+        );
+    }
+    Ok((key, entries, pos as u64))
+}
+
+fn read_stats(dir: &Path) -> Result<HashMap<CacheKey, CacheStats>> {
+    let path = dir.join(STATS_FILE);
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read cache stats {}", path.display()))?;
+    let entries: Vec<StatsEntry> = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid cache stats {}", path.display()))?;
+    Ok(entries.into_iter().map(|e| (e.key, e.stats)).collect())
+}
+
+fn write_stats(dir: &Path, totals: &HashMap<CacheKey, CacheStats>) -> Result<()> {
+    let entries: Vec<_> = totals
+        .iter()
+        .map(|(key, stats)| StatsEntry {
+            key: key.clone(),
+            stats: *stats,
+        })
+        .collect();
+    let path = dir.join(STATS_FILE);
+    fs::write(&path, serde_json::to_string_pretty(&entries)?)
+        .with_context(|| format!("Failed to write cache stats {}", path.display()))
+}
@@ -1,5 +1,9 @@
 // add configs here
+use crate::file_loader::{ChunkMetadata, EmbedMode};
+use anyhow::{anyhow, Context, Result};
 use configs::constants;
+use configs::secret::Secret;
+use configs::settings::settings;
 use tokio::sync::RwLock;
 
 #[derive(serde::Serialize, Debug, Clone)]
@@ -8,11 +12,27 @@ pub struct EmbedRequest {
     // @TODO - add provider and api_url
     pub provider: String,
     pub api_url: String,
-    pub api_key: String,
+    /// Sent in the `Authorization` header never in the body
+    #[serde(skip_serializing)]
+    pub api_key: Secret,
     pub model: String,
     pub input: Vec<String>,
     pub metadata: Option<String>, // TODO - add metadata hashmap column JSON
     pub chunk_number: Option<i32>,
+    /// Output size for models that can shorten their embeddings e.g. `text-embedding-3-*`
+    pub dimensions: Option<u32>,
+    /// Row metadata of the chunk, written to the table with the embeddings
+    pub chunk_metadata: ChunkMetadata,
+}
+
+/// EmbedBody is the request body sent to the provider, Ollama `api/embed` and the
+/// OpenAI `v1/embeddings` endpoints both take `model` and a list of `input`
+#[derive(serde::Serialize, Debug)]
+struct EmbedBody<'a> {
+    model: &'a str,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<u32>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -21,9 +41,42 @@ pub struct EmbedResponse {
     pub embeddings: Vec<Vec<f32>>,
 }
 
+/// OpenAIEmbedResponse `v1/embeddings` response, one `data` item per input
+#[derive(serde::Deserialize, Debug)]
+struct OpenAIEmbedResponse {
+    #[serde(default)]
+    model: String,
+    data: Vec<OpenAIEmbedding>,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct OpenAIEmbedding {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Error body returned by OpenAI compatible servers and Ollama
+#[derive(serde::Deserialize, Debug)]
+#[serde(untagged)]
+enum EmbedError {
+    OpenAI { error: OpenAIError },
+    Ollama { error: String },
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct OpenAIError {
+    message: String,
+}
+
 impl EmbedRequest {
+    /// Serialize the provider request body, the api key and the row metadata are not sent
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
-        serde_json::to_string(self)
+        serde_json::to_string(&EmbedBody {
+            model: &self.model,
+            input: &self.input,
+            dimensions: self.dimensions,
+        })
     }
 
     pub fn add_input(&mut self, input: &str) {
@@ -58,11 +111,13 @@ impl EmbedRequest {
         let data = EmbedRequest {
             provider: provider.to_string(),
             api_url: api_url.to_string(),
-            api_key: api_key.to_string(),
+            api_key: Secret::new(api_key),
             model: model.to_string(),
             input,
             metadata: Some(metadata.to_string()),
             chunk_number,
+            dimensions: settings().embed_dimensions.value,
+            chunk_metadata: ChunkMetadata::default(),
         };
 
         std::sync::Arc::new(RwLock::new(data))
@@ -83,11 +138,13 @@ impl EmbedRequest {
         EmbedRequest {
             provider: provider.to_string(),
             api_url: api_url.to_string(),
-            api_key: api_key.to_string(),
+            api_key: Secret::new(api_key),
             model,
             input,
             metadata: None,
             chunk_number,
+            dimensions: settings().embed_dimensions.value,
+            chunk_metadata: ChunkMetadata::default(),
         }
     }
 
@@ -96,30 +153,42 @@ impl EmbedRequest {
         EmbedRequest {
             provider: "".to_string(),
             api_url: "".to_string(),
-            api_key: "".to_string(),
+            api_key: Secret::default(),
             model: "".to_string(),
             input: vec![],
             metadata: None,
             chunk_number: None,
+            dimensions: None,
+            chunk_metadata: ChunkMetadata::default(),
         }
     }
 
+    /// Embedding endpoint of the provider, for `openai` the api url is the base url of any
+    /// OpenAI compatible server (vLLM, llama.cpp server, LM Studio) with or without `/v1`
     pub fn get_embed_url(&self) -> String {
+        let api_url = self.api_url.trim_end_matches('/');
         match self.provider.to_lowercase().as_str() {
-            "openai" => format!(
-                "{}/{}",
-                // @TODO - this would need Embed Response rework
-                // constants::OPEN_AI_URL,
-                // constants::OPEN_AI_EMBED_API
-                constants::CHAT_API_URL,
-                constants::OLLAMA_EMBED_API
-            ),
-            "ollama" => format!("{}/{}", self.api_url, constants::OLLAMA_EMBED_API),
+            "openai" => {
+                let base_url = if api_url.is_empty() {
+                    constants::OPEN_AI_URL
+                } else {
+                    api_url
+                };
+                match base_url.strip_suffix("/v1") {
+                    Some(base_url) => format!("{}/{}", base_url, constants::OPEN_AI_EMBED_API),
+                    None => format!("{}/{}", base_url, constants::OPEN_AI_EMBED_API),
+                }
+            }
+            "ollama" => format!("{}/{}", api_url, constants::OLLAMA_EMBED_API),
             _ => panic!("Unsupported provider"),
         }
     }
 
-    pub fn get_api_key(&self) -> String {
+    pub fn set_dimensions(&mut self, dimensions: Option<u32>) {
+        self.dimensions = dimensions;
+    }
+
+    pub fn get_api_key(&self) -> Secret {
         self.api_key.clone()
     }
 }
@@ -133,6 +202,37 @@ impl EmbedResponse {
         serde_json::from_str(json)
     }
 
+    /// Parse the response of the provider, Ollama `{model, embeddings}` or OpenAI `{model, data[].embedding}`
+    /// Arguments:
+    /// - provider: &str
+    /// - json: &str
+    ///
+    /// Returns:
+    /// - Result<EmbedResponse>
+    pub fn from_provider_json(provider: &str, json: &str) -> Result<EmbedResponse> {
+        if let Ok(error) = serde_json::from_str::<EmbedError>(json) {
+            let message = match error {
+                EmbedError::OpenAI { error } => error.message,
+                EmbedError::Ollama { error } => error,
+            };
+            return Err(anyhow!("Embedding API error: {}", message));
+        }
+
+        match provider.to_lowercase().as_str() {
+            "openai" => {
+                let mut response: OpenAIEmbedResponse = serde_json::from_str(json)
+                    .context("Failed to parse OpenAI embedding response")?;
+                // the embeddings are returned in input order but the index is authoritative
+                response.data.sort_by_key(|d| d.index);
+                Ok(EmbedResponse {
+                    model: response.model,
+                    embeddings: response.data.into_iter().map(|d| d.embedding).collect(),
+                })
+            }
+            _ => serde_json::from_str(json).context("Failed to parse Ollama embedding response"),
+        }
+    }
+
     pub fn add_embedding(&mut self, embedding: Vec<f32>) {
         self.embeddings.push(embedding);
     }
@@ -167,3 +267,35 @@ impl EmbedResponse {
         serde_json::from_str(json)
     }
 }
+
+/// EmbedOptions how the chunks of a load are grouped into embedding requests
+#[derive(Debug, Clone)]
+pub struct EmbedOptions {
+    pub mode: EmbedMode,
+    /// Chunks sent in a single embedding request
+    pub batch_size: usize,
+    /// Embedding requests in flight at the same time
+    pub max_in_flight: usize,
+}
+
+impl Default for EmbedOptions {
+    fn default() -> Self {
+        EmbedOptions {
+            mode: EmbedMode::default(),
+            batch_size: constants::DEFAULT_EMBED_BATCH_SIZE,
+            max_in_flight: constants::DEFAULT_EMBED_MAX_IN_FLIGHT,
+        }
+    }
+}
+
+impl EmbedOptions {
+    /// Read the embed mode, batch size and in flight limit from the settings
+    pub fn from_settings() -> Result<Self> {
+        let settings = settings();
+        Ok(EmbedOptions {
+            mode: EmbedMode::parse_mode(&settings.embed_mode.value)?,
+            batch_size: settings.embed_batch_size.value,
+            max_in_flight: settings.embed_max_in_flight.value,
+        })
+    }
+}
@@ -1,20 +1,36 @@
+use crate::archive::{entry_path, read_archive, ArchiveFormat};
+use crate::chunk_policy::{ChunkPolicy, ChunkSpec, Splitter};
+use crate::detect::{detect_bytes, detect_file, detect_path, Detection, MIN_CONFIDENCE};
 use crate::embed_config::EmbedRequest;
+use crate::html::html_to_markdown;
+use crate::key_path::split_key_paths;
+use crate::logs::split_log;
+use crate::markdown::split_markdown;
+use crate::notebook::parse_notebook;
+use crate::pdf::{extract_pdf_pages, PdfText};
+use crate::spark::split_spark_events;
+use crate::symbols::{extract_symbols, symbols_in_range, Symbol};
+use crate::walker::{SkipReason, SkippedPath, WalkOptions, WalkedFiles, Walker};
 use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
+use configs::secret::Secret;
+use configs::settings::settings;
+use futures::stream::{self, StreamExt};
 use log::debug;
 use std::cmp::PartialEq;
 use std::ffi::OsStr;
-use std::fs::File;
-use std::io::{BufReader, Read};
+use std::fmt;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
-use text_splitter::{Characters, ChunkConfig, CodeSplitter, CodeSplitterError};
+use text_splitter::{Characters, ChunkConfig, ChunkSizer, CodeSplitter, TextSplitter};
+use tiktoken_rs::CoreBPE;
+use tokenizers::Tokenizer;
 use tokio::sync::RwLock;
 use tree_sitter_language::LanguageFn;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Language {
     Rust,
     Python,
@@ -27,7 +43,23 @@ pub enum Language {
     Header,
     Go,
     Scala,
+    Ruby,
+    Bash,
+    Kotlin,
+    CSharp,
+    Sql,
+    Toml,
+    Yaml,
+    Json,
+    Groovy,
+    Dockerfile,
+    Makefile,
     Text,
+    Markdown,
+    Html,
+    Pdf,
+    Notebook,
+    Log,
     SPARKLOG,
     UNKNOWN,
 }
@@ -46,19 +78,268 @@ impl Language {
             "h" => Language::Header,
             "go" => Language::Go,
             "scala" => Language::Scala,
+            "rb" | "rake" | "gemspec" => Language::Ruby,
+            "sh" | "bash" | "zsh" => Language::Bash,
+            "kt" | "kts" => Language::Kotlin,
+            "cs" => Language::CSharp,
+            "sql" => Language::Sql,
+            "toml" => Language::Toml,
+            "yaml" | "yml" => Language::Yaml,
+            "json" => Language::Json,
+            "dockerfile" => Language::Dockerfile,
+            "groovy" | "gradle" => Language::Groovy,
+            "mk" => Language::Makefile,
             "txt" => Language::Text,
-            "log" => Language::SPARKLOG,
-            // "sh" => Language::Text,
+            "md" | "markdown" | "mdx" => Language::Markdown,
+            "html" | "htm" | "xhtml" => Language::Html,
+            "pdf" => Language::Pdf,
+            "ipynb" => Language::Notebook,
+            "log" => Language::Log,
             "unknown" => Language::UNKNOWN,
             _ => Language::UNKNOWN,
         }
     }
+
+    /// Parse a language name e.g. `rust` or `typescript`, file extensions are accepted too
+    pub fn parse_name(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "rust" => Language::Rust,
+            "python" => Language::Python,
+            "java" => Language::Java,
+            "javascript" => Language::JavaScript,
+            "typescript" => Language::TypeScript,
+            "header" => Language::Header,
+            "scala" => Language::Scala,
+            "ruby" => Language::Ruby,
+            "shell" => Language::Bash,
+            "kotlin" => Language::Kotlin,
+            "csharp" | "c#" => Language::CSharp,
+            "makefile" | "make" => Language::Makefile,
+            "text" => Language::Text,
+            "sparklog" => Language::SPARKLOG,
+            "notebook" | "jupyter" => Language::Notebook,
+            ext => Language::parse_language(ext),
+        }
+    }
+
+    /// Lower case name of the language, `parse_name` reads it back
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Python => "python",
+            Language::Cpp => "cpp",
+            Language::Java => "java",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Tsx => "tsx",
+            Language::C => "c",
+            Language::Header => "header",
+            Language::Go => "go",
+            Language::Scala => "scala",
+            Language::Ruby => "ruby",
+            Language::Bash => "bash",
+            Language::Kotlin => "kotlin",
+            Language::CSharp => "csharp",
+            Language::Sql => "sql",
+            Language::Toml => "toml",
+            Language::Yaml => "yaml",
+            Language::Json => "json",
+            Language::Groovy => "groovy",
+            Language::Dockerfile => "dockerfile",
+            Language::Makefile => "makefile",
+            Language::Text => "text",
+            Language::Markdown => "markdown",
+            Language::Html => "html",
+            Language::Pdf => "pdf",
+            Language::Notebook => "notebook",
+            Language::Log => "log",
+            Language::SPARKLOG => "sparklog",
+            Language::UNKNOWN => "unknown",
+        }
+    }
+
+    /// Language of a file from its name e.g. `Dockerfile` or `Makefile`, else from its extension
+    pub fn from_path(path: &Path) -> Self {
+        detect_path(path).language
+    }
+}
+
+/// EmbedMode granularity of the vectors written for a chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmbedMode {
+    /// One vector for the whole chunk, the chunk text is stored as the row content
+    #[default]
+    Chunk,
+    /// One vector for every non empty line of the chunk
+    Line,
+}
+
+impl EmbedMode {
+    pub fn parse_mode(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "chunk" => Ok(EmbedMode::Chunk),
+            "line" => Ok(EmbedMode::Line),
+            _ => Err(anyhow!(
+                "Unsupported embed mode: {} expected chunk or line",
+                s
+            )),
+        }
+    }
+}
+
+/// Sizer unit the chunk size is measured in, tokens keep chunks within the context
+/// window of the embedding model
+#[derive(Clone, Default)]
+pub enum Sizer {
+    #[default]
+    Characters,
+    /// Tokens of a tiktoken encoding e.g. `cl100k_base` for the OpenAI embedding models
+    Tiktoken(String, Arc<CoreBPE>),
+    /// Tokens of a HuggingFace `tokenizer.json`
+    HuggingFace(String, Arc<Tokenizer>),
+}
+
+impl Sizer {
+    /// Parse `characters`, `tiktoken:<encoding or model>` or `hf:<path to tokenizer.json>`
+    pub fn parse_sizer(s: &str) -> Result<Self> {
+        match s.split_once(':') {
+            None if s.eq_ignore_ascii_case("characters") => Ok(Sizer::Characters),
+            Some(("tiktoken", name)) => {
+                let bpe = match name {
+                    "cl100k_base" => tiktoken_rs::cl100k_base(),
+                    "o200k_base" => tiktoken_rs::o200k_base(),
+                    "p50k_base" => tiktoken_rs::p50k_base(),
+                    "r50k_base" => tiktoken_rs::r50k_base(),
+                    model => tiktoken_rs::get_bpe_from_model(model),
+                }
+                .with_context(|| format!("Unknown tiktoken encoding or model: {}", name))?;
+                Ok(Sizer::Tiktoken(name.to_string(), Arc::new(bpe)))
+            }
+            Some(("hf", path)) => {
+                let tokenizer = Tokenizer::from_file(path)
+                    .map_err(|e| anyhow!("Failed to load tokenizer {}: {}", path, e))?;
+                Ok(Sizer::HuggingFace(path.to_string(), Arc::new(tokenizer)))
+            }
+            _ => Err(anyhow!(
+                "Unsupported chunk sizer: {} expected characters, tiktoken:<encoding> or hf:<tokenizer.json>",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Debug for Sizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Sizer::Characters => write!(f, "characters"),
+            Sizer::Tiktoken(name, _) => write!(f, "tiktoken:{}", name),
+            Sizer::HuggingFace(path, _) => write!(f, "hf:{}", path),
+        }
+    }
+}
+
+impl ChunkSizer for Sizer {
+    fn size(&self, chunk: &str) -> usize {
+        match self {
+            Sizer::Characters => Characters.size(chunk),
+            Sizer::Tiktoken(_, bpe) => bpe.as_ref().size(chunk),
+            Sizer::HuggingFace(_, tokenizer) => tokenizer.as_ref().size(chunk),
+        }
+    }
+}
+
+/// ChunkMetadata structure of the document a chunk was cut from, stored in its own table columns
+#[derive(serde::Serialize, Debug, Clone, Default, PartialEq)]
+pub struct ChunkMetadata {
+    /// Markdown headings above the chunk e.g. `Usage > Commands`
+    pub heading: Option<String>,
+    /// Languages of the fenced code blocks in the chunk
+    pub code_languages: Vec<String>,
+    /// Page of a PDF document the chunk starts on, numbered from 1
+    pub page: Option<u32>,
+    /// `<title>` of an html page
+    pub title: Option<String>,
+    /// Canonical URL of an html page
+    pub url: Option<String>,
+    /// Index of the notebook cell the chunk was cut from
+    pub cell_index: Option<u32>,
+    /// `code`, `markdown` or `raw` type of the notebook cell
+    pub cell_type: Option<String>,
+    /// Path of the file relative to the loaded directory e.g. `src/lib.rs`
+    pub path: Option<String>,
+    /// Language detected for the file e.g. `rust`
+    pub language: Option<String>,
+    /// First line of the chunk in the file, numbered from 1
+    pub start_line: Option<u32>,
+    /// Last line of the chunk in the file
+    pub end_line: Option<u32>,
+    /// Byte offset of the chunk in the file
+    pub byte_start: Option<u64>,
+    /// Byte offset after the end of the chunk in the file
+    pub byte_end: Option<u64>,
+    /// Definitions enclosing or inside a code chunk, outer definitions first
+    pub symbols: Vec<Symbol>,
+    /// Keys leading to a toml, yaml or json chunk e.g. `workspace.dependencies.lancedb`
+    pub key_path: Option<String>,
+    /// Timestamp of the first entry of a log chunk in milliseconds since the epoch
+    pub log_timestamp: Option<i64>,
+    /// Most severe level of the entries of a log chunk e.g. `ERROR`
+    pub log_level: Option<String>,
+    /// Id of the spark application of an event log chunk e.g. `app-20240501120000-0001`
+    pub spark_app_id: Option<String>,
+    /// Spark job that failed or that the failed stage belongs to
+    pub spark_job_id: Option<u32>,
+    /// Spark stage that failed, None for the chunk of a failed job
+    pub spark_stage_id: Option<u32>,
+    /// Attempt of the failed spark stage, numbered from 0
+    pub spark_stage_attempt: Option<u32>,
+    /// First line of the failure reason of a spark stage or job
+    pub failure_reason: Option<String>,
+}
+
+impl ChunkMetadata {
+    /// Metadata of a chunk cut from a byte offset of the text of a file
+    pub(crate) fn at_offset(offset: usize, chunk: &str) -> Self {
+        ChunkMetadata {
+            byte_start: Some(offset as u64),
+            byte_end: Some((offset + chunk.len()) as u64),
+            ..Default::default()
+        }
+    }
+}
+
+/// LineIndex byte offsets the lines of a text start at
+struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    fn new(content: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        LineIndex { starts }
+    }
+
+    /// Line of a byte offset, numbered from 1
+    fn line(&self, offset: usize) -> u32 {
+        self.starts.partition_point(|start| *start <= offset) as u32
+    }
+
+    /// Add the first and last line of the byte range of a chunk
+    fn add_lines(&self, metadata: &mut ChunkMetadata) {
+        if let (Some(start), Some(end)) = (metadata.byte_start, metadata.byte_end) {
+            metadata.start_line = Some(self.line(start as usize));
+            metadata.end_line = Some(self.line((end as usize).max(start as usize + 1) - 1));
+        }
+    }
 }
 
 pub struct FileChunk {
     pub content: Vec<String>,
     pub file_path: PathBuf,
     pub chunk_number: i32,
+    pub metadata: ChunkMetadata,
 }
 
 /// A struct that represents a codebase.
@@ -69,9 +350,15 @@ impl FileChunk {
             content: content_lines,
             file_path,
             chunk_number,
+            metadata: ChunkMetadata::default(),
         }
     }
 
+    pub fn with_metadata(mut self, metadata: ChunkMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
     pub fn get_content(&self) -> String {
         self.content.join("\n")
     }
@@ -84,6 +371,19 @@ impl FileChunk {
         self.chunk_number
     }
 
+    /// Texts sent to the embedding model for this chunk
+    pub fn embed_input(&self, mode: EmbedMode) -> Vec<String> {
+        match mode {
+            EmbedMode::Chunk => vec![self.get_content()],
+            EmbedMode::Line => self
+                .content
+                .iter()
+                .filter(|line| !line.trim().is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+
     pub fn print_file_chunk(&self) {
         println!(
             "File: {}, Chunk {}: {}",
@@ -107,13 +407,14 @@ pub fn chunk_embed_request(
     api_url: &str,
     api_key: &str,
     model: &str,
+    mode: EmbedMode,
 ) -> EmbedRequest {
     EmbedRequest {
         provider: provider.to_string(),
         api_url: api_url.to_string(),
-        api_key: api_key.to_string(),
+        api_key: Secret::new(api_key),
         model: model.to_string(),
-        input: chunk.content.clone(),
+        input: chunk.embed_input(mode),
         metadata: Some(
             chunk
                 .file_path
@@ -124,6 +425,8 @@ pub fn chunk_embed_request(
                 .to_string(),
         ),
         chunk_number: Some(chunk.chunk_number),
+        dimensions: settings().embed_dimensions.value,
+        chunk_metadata: chunk.metadata.clone(),
     }
 }
 
@@ -133,30 +436,193 @@ pub fn chunk_embed_request_arc(
     api_url: &str,
     api_key: &str,
     model: &str,
+    mode: EmbedMode,
 ) -> Arc<RwLock<EmbedRequest>> {
     Arc::new(RwLock::new(chunk_embed_request(
-        chunk, provider, api_url, api_key, model,
+        chunk, provider, api_url, api_key, model, mode,
     )))
 }
 
-/// Load a codebase into chunks of text.
+/// Leading bytes of a file checked for NUL bytes
+const BINARY_CHECK_BYTES: usize = 8000;
+
+/// LoadedFiles chunks of a load and the paths left out with the reason
+#[derive(Default)]
+pub struct LoadedFiles {
+    pub chunks: Vec<FileChunk>,
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// FileLoad chunks of a single file or the reason it was skipped
+enum FileLoad {
+    Chunks(Vec<FileChunk>),
+    Skipped(SkipReason),
+}
+
+impl FileLoad {
+    /// Record the path relative to the loaded directory and the language of the file on its chunks
+    fn with_source(mut self, relative_path: &Path, language: Language) -> Self {
+        if let FileLoad::Chunks(chunks) = &mut self {
+            let path = relative_path
+                .iter()
+                .map(|part| part.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            let language = match language {
+                Language::UNKNOWN => None,
+                language => Some(language.name().to_string()),
+            };
+            for chunk in chunks {
+                chunk.metadata.path = Some(path.clone());
+                chunk.metadata.language = language.clone();
+            }
+        }
+        self
+    }
+}
+
+/// Load a codebase into chunks of text, the policy picks the chunking of every file.
 pub async fn load_codebase_into_chunks(
     root_dir: &str,
-    max_chunk_size: usize,
+    chunk_policy: &ChunkPolicy,
 ) -> Result<Vec<FileChunk>> {
+    let loaded = load_files(root_dir, chunk_policy, &WalkOptions::default()).await?;
+    for skipped in &loaded.skipped {
+        debug!("Skipped {}", skipped);
+    }
+    Ok(loaded.chunks)
+}
+
+/// Load a file or the files of a directory into chunks, ignored, excluded, oversized, binary
+/// and unreadable files are skipped and returned with the reason instead of failing the load.
+/// The entries of `.zip`, `.tar` and `.tar.gz` archives are loaded like files with paths
+/// such as `bundle.zip!/src/main.rs`
+/// Arguments:
+/// - root_dir: &str file, directory or archive to load
+/// - chunk_policy: &ChunkPolicy chunking of every file
+/// - walk_options: &WalkOptions which files of a directory are loaded
+///
+/// Returns:
+/// - Result<LoadedFiles>
+pub async fn load_files(
+    root_dir: &str,
+    chunk_policy: &ChunkPolicy,
+    walk_options: &WalkOptions,
+) -> Result<LoadedFiles> {
     let root_path = PathBuf::from(root_dir);
+    let options = walk_options.clone();
+    let (walker, walked) = tokio::task::spawn_blocking(move || walk_files(&root_path, &options))
+        .await
+        .context("Failed to join the file walker")??;
+
+    // files are read and split on the blocking pool, `buffered` keeps the walk order
+    let parallelism = match walk_options.parallelism {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let chunk_policy = Arc::new(chunk_policy.clone());
+    let walker = Arc::new(walker);
+    let mut file_loads = stream::iter(walked.files)
+        .map(|file_path| {
+            let chunk_policy = Arc::clone(&chunk_policy);
+            let walker = Arc::clone(&walker);
+            tokio::task::spawn_blocking(move || {
+                if let Some(format) = ArchiveFormat::from_path(&file_path) {
+                    return load_archive(&file_path, format, &walker, &chunk_policy);
+                }
+                let relative_path = walker.relative_path(&file_path);
+                let language = confident_language(&file_path, detect_file(&file_path));
+                let file_load =
+                    split_file_into_chunks(&file_path, relative_path, language, &chunk_policy)
+                        .with_context(|| {
+                            format!("Failed to split {} into chunks", file_path.display())
+                        })
+                        .map(|file_load| file_load.with_source(relative_path, language));
+                vec![(file_path, file_load)]
+            })
+        })
+        .buffered(parallelism);
+
+    let mut loaded = LoadedFiles {
+        chunks: Vec::new(),
+        skipped: walked.skipped,
+    };
+    while let Some(result) = file_loads.next().await {
+        for (file_path, file_load) in result.context("Failed to join the file loader")? {
+            match file_load? {
+                FileLoad::Chunks(chunks) => loaded.chunks.extend(chunks),
+                FileLoad::Skipped(reason) => loaded.skipped.push(SkippedPath {
+                    path: file_path,
+                    reason,
+                }),
+            }
+        }
+    }
+    Ok(loaded)
+}
+
+/// Load the files of an archive one entry at a time, every entry is detected and split like
+/// a file on disk. A corrupt archive keeps the entries read before the corruption and an
+/// archive above its limits the entries read before the limit, both are skipped with the reason
+fn load_archive(
+    archive_path: &Path,
+    format: ArchiveFormat,
+    walker: &Walker,
+    chunk_policy: &ChunkPolicy,
+) -> Vec<(PathBuf, Result<FileLoad>)> {
+    let relative_archive = walker.relative_path(archive_path).to_path_buf();
+    let mut file_loads = Vec::new();
+    let read = read_archive(archive_path, format, walker, |entry, bytes| {
+        let file_path = entry_path(archive_path, entry);
+        let file_load = match bytes {
+            Ok(bytes) => {
+                let relative_path = entry_path(&relative_archive, entry);
+                let language =
+                    confident_language(&file_path, Ok(detect_bytes(Path::new(entry), &bytes)));
+                split_entry_into_chunks(&file_path, &relative_path, bytes, language, chunk_policy)
+                    .with_context(|| format!("Failed to split {} into chunks", file_path.display()))
+                    .map(|file_load| file_load.with_source(&relative_path, language))
+            }
+            Err(reason) => Ok(FileLoad::Skipped(reason)),
+        };
+        file_loads.push((file_path, file_load));
+    });
+    let reason = match read {
+        Ok(limit) => limit,
+        Err(e) => Some(SkipReason::Unreadable(format!("{:#}", e))),
+    };
+    if let Some(reason) = reason {
+        file_loads.push((archive_path.to_path_buf(), Ok(FileLoad::Skipped(reason))));
+    }
+    file_loads
+}
 
+/// Files to load under a file or directory and the walker of the directory relative paths
+/// start from
+fn walk_files(root_path: &Path, walk_options: &WalkOptions) -> Result<(Walker, WalkedFiles)> {
     if root_path.is_file() {
-        let chunk = split_file_into_chunks(&root_path, max_chunk_size)
-            .await
-            .context("Failed to split file into chunks")?;
-        return Ok(chunk);
+        let parent = root_path.parent().unwrap_or(Path::new("")).to_path_buf();
+        let walker = Walker::new(&parent, walk_options)?;
+        let walked = match walker.check_file(root_path) {
+            Some(reason) => WalkedFiles {
+                files: vec![],
+                skipped: vec![SkippedPath {
+                    path: root_path.to_path_buf(),
+                    reason,
+                }],
+            },
+            None => WalkedFiles {
+                files: vec![root_path.to_path_buf()],
+                skipped: vec![],
+            },
+        };
+        return Ok((walker, walked));
     }
 
     if root_path.is_dir() {
-        return process_directory(&root_path, max_chunk_size)
-            .await
-            .context("Failed to process directory");
+        let walker = Walker::new(root_path, walk_options)?;
+        let walked = walker.walk().context("Failed to process directory")?;
+        return Ok((walker, walked));
     }
 
     Err(anyhow!(
@@ -164,115 +630,283 @@ pub async fn load_codebase_into_chunks(
     ))
 }
 
-/// process_directory recursively processes a directory and its subdirectories to extract code chunks.
-async fn process_directory(path: &PathBuf, max_chunk_size: usize) -> Result<Vec<FileChunk>> {
-    async fn inner_process_directory(
-        path: &PathBuf,
-        max_chunk_size: usize,
-        chunks: &mut Vec<FileChunk>,
-    ) -> Result<()> {
-        let mut entries = tokio::fs::read_dir(path)
-            .await
-            .context(format!("Failed to read directory {}", path.display()))?;
-
-        // let is_code = is_code_file(path);
-
-        while let Some(entry) = entries.next_entry().await? {
-            let file_path = entry.path();
-            debug!("File Path: {:?}", file_path);
-            let file_type = entry.file_type().await?;
-            if file_type.is_file() {
-                let chunk = split_file_into_chunks(&file_path, max_chunk_size).await?;
-                chunks.extend(chunk);
-            } else if file_type.is_dir() {
-                Box::pin(inner_process_directory(&file_path, max_chunk_size, chunks)).await?;
-            }
-        }
-        Ok(())
+/// Content of a file as text, files with NUL bytes or invalid UTF-8 are binary
+fn text_content(bytes: Vec<u8>) -> std::result::Result<String, SkipReason> {
+    if bytes.iter().take(BINARY_CHECK_BYTES).any(|b| *b == 0) {
+        return Err(SkipReason::Binary);
     }
+    String::from_utf8(bytes).map_err(|_| SkipReason::Binary)
+}
 
-    let mut chunks = Vec::new();
-    inner_process_directory(path, max_chunk_size, &mut chunks).await?;
-    Ok(chunks)
+/// Language of a file from its name or extension, else from the start of its content,
+/// detections below `MIN_CONFIDENCE` are UNKNOWN
+fn confident_language(file_path: &Path, detection: Result<Detection>) -> Language {
+    match detection {
+        Ok(detection) if detection.confidence >= MIN_CONFIDENCE => {
+            debug!(
+                "Detected {} as {:?} from its {} ({:.2})",
+                file_path.display(),
+                detection.language,
+                detection.method,
+                detection.confidence
+            );
+            detection.language
+        }
+        Ok(_) => Language::UNKNOWN,
+        Err(e) => {
+            debug!("No language for {}: {:#}", file_path.display(), e);
+            Language::UNKNOWN
+        }
+    }
 }
 
-/// Split a file into chunks of text based on language-specific rules.
-async fn split_file_into_chunks(
-    file_path: &PathBuf,
-    max_chunk_size: usize,
-) -> Result<Vec<FileChunk>> {
-    let file = File::open(file_path).context("Failed to open file")?;
-    let mut reader = BufReader::new(file);
-    let mut content = String::new();
-    reader.read_to_string(&mut content)?;
+/// Split a file into chunks of text with the splitter, size and overlap the policy picks
+/// for its language and path, reads the file with blocking IO.
+fn split_file_into_chunks(
+    file_path: &Path,
+    relative_path: &Path,
+    language: Language,
+    chunk_policy: &ChunkPolicy,
+) -> Result<FileLoad> {
+    let Some(spec) = supported_spec(relative_path, language, chunk_policy) else {
+        return Ok(FileLoad::Skipped(SkipReason::Unsupported));
+    };
+    match std::fs::read(file_path) {
+        Ok(bytes) => split_content_into_chunks(file_path, bytes, language, &spec, chunk_policy),
+        Err(e) => Ok(FileLoad::Skipped(SkipReason::Unreadable(e.to_string()))),
+    }
+}
 
-    // Create a chunk config with the specified max chunk size
-    let chunk_config = ChunkConfig::new(max_chunk_size)
-        .with_overlap(256)
-        .context("Failed to create chunk config")?
-        .with_trim(true);
+/// Split an archive entry read into memory like a file on disk
+fn split_entry_into_chunks(
+    file_path: &Path,
+    relative_path: &Path,
+    bytes: Vec<u8>,
+    language: Language,
+    chunk_policy: &ChunkPolicy,
+) -> Result<FileLoad> {
+    match supported_spec(relative_path, language, chunk_policy) {
+        Some(spec) => split_content_into_chunks(file_path, bytes, language, &spec, chunk_policy),
+        None => Ok(FileLoad::Skipped(SkipReason::Unsupported)),
+    }
+}
 
-    let (language, is_supported) = is_supported_file(file_path);
+/// Chunking the policy picks for a file, None for a file of an unknown language unless a
+/// rule with an explicit splitter matches it
+fn supported_spec(
+    relative_path: &Path,
+    language: Language,
+    chunk_policy: &ChunkPolicy,
+) -> Option<ChunkSpec> {
+    let is_supported = language != Language::UNKNOWN;
+    let spec = chunk_policy.spec_for(relative_path, language);
 
     debug!(
-        "File Extension: {:?} Is Supported File {:}",
-        language, is_supported
+        "File Extension: {:?} Is Supported File {:} Chunking {:?}",
+        language, is_supported, spec
     );
 
-    if !is_supported {
-        debug!("Unsupported file extension");
+    // a rule with an explicit splitter also loads files of unknown languages
+    if !is_supported && spec.splitter.is_none() {
+        debug!("Unsupported file language");
+        return None;
     }
+    Some(spec)
+}
 
-    // let mut chunks: Vec<FileChunk> = Vec::new();
-    if is_supported && language == Language::Text {
-        // user tree_sitter_markdown
-        let splitter = text_splitter::TextSplitter::new(chunk_config);
-        let chunks = splitter
-            .chunks(&content)
-            .enumerate()
-            .map(|(i, chunk)| {
-                Ok(FileChunk::new(
-                    chunk.to_string(),
-                    file_path.clone(),
-                    i as i32,
-                ))
-            })
-            .collect::<Result<Vec<FileChunk>, CodeSplitterError>>()?;
+/// Split the content of a file with the chunking picked for it
+fn split_content_into_chunks(
+    file_path: &Path,
+    bytes: Vec<u8>,
+    language: Language,
+    spec: &ChunkSpec,
+    chunk_policy: &ChunkPolicy,
+) -> Result<FileLoad> {
+    let chunk_config = spec
+        .chunk_config()
+        .with_context(|| format!("Failed to create chunk config for {}", file_path.display()))?;
+
+    if language == Language::Pdf {
+        return process_pdf_file(file_path, &bytes, chunk_config);
+    }
 
-        return Ok(chunks);
+    let mut content = match text_content(bytes) {
+        Ok(content) => content,
+        Err(reason) => return Ok(FileLoad::Skipped(reason)),
+    };
+
+    if language == Language::Notebook {
+        return match split_notebook(file_path, &content, spec) {
+            Ok(chunks) => Ok(FileLoad::Chunks(chunks)),
+            Err(e) => Ok(FileLoad::Skipped(SkipReason::Unreadable(format!(
+                "{:#}",
+                e
+            )))),
+        };
     }
 
-    if is_supported && language != Language::Text && language != Language::SPARKLOG {
-        let splitter = CodeSplitter::new(
-            get_language_from_file_extension(language).context("Unsupported file extension")?,
-            chunk_config,
-        )
-        .context("Failed to create code splitter")?;
+    // html pages are split as markdown without their navigation, scripts and styles
+    let mut file_metadata = ChunkMetadata::default();
+    if language == Language::Html {
+        let page = html_to_markdown(&content);
+        file_metadata.title = page.title;
+        file_metadata.url = page.canonical_url;
+        content = page.markdown;
+    }
 
-        let code_chunks = splitter.chunks(&content);
+    let splitter = match spec.splitter {
+        Some(splitter) => splitter,
+        None if language == Language::Text => Splitter::Text,
+        None if language == Language::Markdown || language == Language::Html => Splitter::Markdown,
+        None if matches!(language, Language::Toml | Language::Yaml | Language::Json) => {
+            Splitter::KeyPath
+        }
+        None if language == Language::Log => Splitter::Log,
+        None if language == Language::SPARKLOG => Splitter::SparkEvents,
+        None => Splitter::Code,
+    };
 
-        let chunks: Vec<FileChunk> = code_chunks
+    let chunks: Vec<(String, ChunkMetadata)> = match splitter {
+        Splitter::Code => match get_language_from_file_extension(language) {
+            Ok(grammar) => {
+                // code chunks are tagged with the definitions around and inside them
+                let symbols = extract_symbols(grammar, &content).unwrap_or_else(|e| {
+                    debug!("No symbols for {}: {:#}", file_path.display(), e);
+                    Vec::new()
+                });
+                CodeSplitter::new(grammar, chunk_config)
+                    .context("Failed to create code splitter")?
+                    .chunk_indices(&content)
+                    .map(|(offset, chunk)| {
+                        let metadata = ChunkMetadata {
+                            symbols: symbols_in_range(&symbols, offset, offset + chunk.len()),
+                            ..ChunkMetadata::at_offset(offset, chunk)
+                        };
+                        (chunk.to_string(), metadata)
+                    })
+                    .collect()
+            }
+            Err(_) => {
+                debug!("No grammar for {:?} splitting as text", language);
+                split_text(&content, chunk_config)
+            }
+        },
+        Splitter::Text => split_text(&content, chunk_config),
+        // toml, yaml and json chunks carry the key path they were cut from
+        Splitter::KeyPath => match key_path_grammar(language) {
+            Some(grammar) => match split_key_paths(grammar, &content, spec.chunk_config()?) {
+                Ok(chunks) => chunks,
+                Err(e) => {
+                    debug!("Splitting {} as text: {:#}", file_path.display(), e);
+                    split_text(&content, chunk_config)
+                }
+            },
+            None => split_text(&content, chunk_config),
+        },
+        // markdown chunks carry their heading path and code block languages
+        Splitter::Markdown => split_markdown(&content, chunk_config),
+        // log chunks keep stack traces with their entry and carry its timestamp and level
+        Splitter::Log => split_log(&content, &chunk_policy.log_format, chunk_config),
+        // spark event logs become a chunk per failed stage and job, other files are logs
+        Splitter::SparkEvents => match split_spark_events(&content, spec.chunk_config()?) {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                debug!("Splitting {} as a log: {:#}", file_path.display(), e);
+                split_log(&content, &chunk_policy.log_format, chunk_config)
+            }
+        },
+    };
+
+    // offsets into the markdown of an html page don't point into the file
+    let lines = (language != Language::Html).then(|| LineIndex::new(&content));
+    Ok(FileLoad::Chunks(
+        chunks
+            .into_iter()
             .enumerate()
-            .map(|(i, chunk)| {
-                Ok(FileChunk::new(
-                    chunk.to_string(),
-                    file_path.clone(),
-                    i as i32,
-                ))
+            .map(|(i, (chunk, span))| {
+                let mut metadata = ChunkMetadata {
+                    heading: span.heading,
+                    code_languages: span.code_languages,
+                    symbols: span.symbols,
+                    key_path: span.key_path,
+                    log_timestamp: span.log_timestamp,
+                    log_level: span.log_level,
+                    spark_app_id: span.spark_app_id,
+                    spark_job_id: span.spark_job_id,
+                    spark_stage_id: span.spark_stage_id,
+                    spark_stage_attempt: span.spark_stage_attempt,
+                    failure_reason: span.failure_reason,
+                    ..file_metadata.clone()
+                };
+                if let Some(lines) = &lines {
+                    metadata.byte_start = span.byte_start;
+                    metadata.byte_end = span.byte_end;
+                    lines.add_lines(&mut metadata);
+                }
+                FileChunk::new(chunk, file_path.to_path_buf(), i as i32).with_metadata(metadata)
             })
-            .collect::<Result<Vec<FileChunk>, CodeSplitterError>>()?;
+            .collect(),
+    ))
+}
 
-        return Ok(chunks);
-    }
+/// Split plain text, every chunk records its byte range
+fn split_text(content: &str, chunk_config: ChunkConfig<Sizer>) -> Vec<(String, ChunkMetadata)> {
+    TextSplitter::new(chunk_config)
+        .chunk_indices(content)
+        .map(|(offset, chunk)| (chunk.to_string(), ChunkMetadata::at_offset(offset, chunk)))
+        .collect()
+}
 
-    if is_supported && language == Language::SPARKLOG {
-        return process_spark_log_file(file_path, chunk_config).await;
-    }
+/// Split the cells of a jupyter notebook, code cells with the grammar of the notebook kernel and
+/// markdown cells as text, every chunk records the index and type of its cell and code chunks
+/// the definitions around and inside them.
+fn split_notebook(file_path: &Path, content: &str, spec: &ChunkSpec) -> Result<Vec<FileChunk>> {
+    let notebook = parse_notebook(content)
+        .with_context(|| format!("Failed to parse notebook {}", file_path.display()))?;
+    let code_splitter = match get_language_from_file_extension(notebook.language) {
+        Ok(grammar) => Some((
+            grammar,
+            CodeSplitter::new(grammar, spec.chunk_config()?)
+                .context("Failed to create code splitter")?,
+        )),
+        Err(_) => None,
+    };
+    let text_splitter = TextSplitter::new(spec.chunk_config()?);
 
-    Ok(vec![])
+    let mut chunks = Vec::new();
+    for cell in &notebook.cells {
+        let (cell_chunks, symbols): (Vec<(usize, &str)>, _) = match &code_splitter {
+            Some((grammar, splitter)) if cell.cell_type == "code" => (
+                splitter.chunk_indices(&cell.source).collect(),
+                extract_symbols(*grammar, &cell.source).unwrap_or_default(),
+            ),
+            _ => (
+                text_splitter.chunk_indices(&cell.source).collect(),
+                Vec::new(),
+            ),
+        };
+        for (offset, chunk) in cell_chunks {
+            let metadata = ChunkMetadata {
+                cell_index: Some(cell.index),
+                cell_type: Some(cell.cell_type.clone()),
+                symbols: symbols_in_range(&symbols, offset, offset + chunk.len()),
+                ..Default::default()
+            };
+            chunks.push(
+                FileChunk::new(
+                    chunk.to_string(),
+                    file_path.to_path_buf(),
+                    chunks.len() as i32,
+                )
+                .with_metadata(metadata),
+            );
+        }
+    }
+    Ok(chunks)
 }
 
-/// Checks if the given file is a supported code file based on its extension.
+/// Checks if the given file is a supported code file based on its name or extension,
+/// `detect::detect_file` also looks at the content.
 ///
 /// # Arguments
 ///
@@ -283,19 +917,12 @@ async fn split_file_into_chunks(
 /// A tuple where the first element is the detected `Language` enum type,
 /// and the second element is a boolean indicating if the file is supported.
 pub fn is_supported_file(file_path: &Path) -> (Language, bool) {
-    // Extracts the file extension and converts it to a string slice
-    let ext = file_path.extension().and_then(|e| e.to_str());
-
-    if let Some(ext_str) = ext {
-        debug!("File Extension: {}", ext_str);
-
-        match Language::parse_language(ext_str) {
-            Language::UNKNOWN => (Language::UNKNOWN, false),
-            lang => (lang, true),
+    match Language::from_path(file_path) {
+        Language::UNKNOWN => {
+            debug!("No supported name or extension: {}", file_path.display());
+            (Language::UNKNOWN, false)
         }
-    } else {
-        debug!("No valid extension found.");
-        (Language::UNKNOWN, false)
+        lang => (lang, true),
     }
 }
 
@@ -309,8 +936,16 @@ fn get_language_from_file_extension(language: Language) -> Result<LanguageFn> {
         Language::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT,
         Language::Tsx => tree_sitter_typescript::LANGUAGE_TSX,
         Language::C => tree_sitter_c::LANGUAGE,
+        // headers are parsed as c++, a superset of the c found in most headers
+        Language::Header => tree_sitter_cpp::LANGUAGE,
         Language::Go => tree_sitter_go::LANGUAGE,
         Language::Scala => tree_sitter_scala::LANGUAGE,
+        Language::Ruby => tree_sitter_ruby::LANGUAGE,
+        Language::Bash => tree_sitter_bash::LANGUAGE,
+        Language::CSharp => tree_sitter_c_sharp::LANGUAGE,
+        Language::Toml => tree_sitter_toml_ng::LANGUAGE,
+        Language::Yaml => tree_sitter_yaml::LANGUAGE,
+        Language::Json => tree_sitter_json::LANGUAGE,
         Language::UNKNOWN => return Err(anyhow!("Unsupported file extension")),
         _ => return Err(anyhow!("Unsupported file extension")),
     };
@@ -318,57 +953,51 @@ fn get_language_from_file_extension(language: Language) -> Result<LanguageFn> {
     Ok(language)
 }
 
-async fn process_spark_log_file(
-    file_path: &PathBuf,
-    chunk_config: ChunkConfig<Characters>,
-) -> Result<Vec<FileChunk>> {
-    let file = File::open(file_path).context("Failed to open file")?;
-    let mut reader = BufReader::new(file);
-    let mut content = String::new();
-    reader.read_to_string(&mut content)?;
+/// Grammar of the config formats split on their key paths
+fn key_path_grammar(language: Language) -> Option<LanguageFn> {
+    match language {
+        Language::Toml | Language::Yaml | Language::Json => {
+            get_language_from_file_extension(language).ok()
+        }
+        _ => None,
+    }
+}
 
-    // remove lines without error or exception
-    let error_lines = capture_context_lines(&content, 20);
+/// Split the text of a PDF page by page, every chunk records the page it was cut from.
+/// Encrypted, image-only and unreadable documents are skipped.
+fn process_pdf_file(
+    file_path: &Path,
+    bytes: &[u8],
+    chunk_config: ChunkConfig<Sizer>,
+) -> Result<FileLoad> {
+    let pages = match extract_pdf_pages(bytes) {
+        Ok(PdfText::Pages(pages)) => pages,
+        Ok(PdfText::Encrypted) => return Ok(FileLoad::Skipped(SkipReason::Encrypted)),
+        Ok(PdfText::ImageOnly) => return Ok(FileLoad::Skipped(SkipReason::NoText)),
+        Err(e) => {
+            return Ok(FileLoad::Skipped(SkipReason::Unreadable(format!(
+                "{:#}",
+                e
+            ))))
+        }
+    };
 
-    let splitter = text_splitter::TextSplitter::new(chunk_config);
-    let chunks = splitter
-        .chunks(&error_lines)
+    let splitter = TextSplitter::new(chunk_config);
+    let chunks = pages
+        .iter()
+        .flat_map(|(page, text)| {
+            splitter
+                .chunks(text)
+                .map(move |chunk| (*page, chunk.to_string()))
+        })
         .enumerate()
-        .map(|(i, chunk)| {
-            Ok(FileChunk::new(
-                chunk.to_string(),
-                file_path.clone(),
-                i as i32,
-            ))
+        .map(|(i, (page, chunk))| {
+            FileChunk::new(chunk, file_path.to_path_buf(), i as i32).with_metadata(ChunkMetadata {
+                page: Some(page),
+                ..Default::default()
+            })
         })
-        .collect::<Result<Vec<FileChunk>, CodeSplitterError>>()?;
-
-    Ok(chunks)
-}
-
-fn capture_context_lines(content: &str, num_lines: usize) -> String {
-    let filtered_lines: Vec<&str> = content
-        .lines()
-        .filter(|line| !line.to_lowercase().contains("lineage"))
         .collect();
 
-    let mut result = Vec::new();
-
-    for (i, line) in filtered_lines.iter().enumerate() {
-        // Check for "ERROR" or "EXCEPTION" after filtering out "LINEAGE"
-        if line.to_lowercase().contains("error") || line.to_lowercase().contains("exception") {
-            let start = i.saturating_sub(num_lines);
-            let end = usize::min(i + num_lines + 1, filtered_lines.len());
-
-            for context_line in &filtered_lines[start..end] {
-                if !context_line.trim().is_empty() {
-                    result.push(*context_line);
-                }
-            }
-        }
-    }
-
-    debug!("Error Lines: {}", result.join("\n"));
-
-    result.join("\n")
+    Ok(FileLoad::Chunks(chunks))
 }
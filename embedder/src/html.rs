@@ -0,0 +1,181 @@
+use scraper::{ElementRef, Html, Node, Selector};
+
+/// Elements dropped with everything inside them
+const SKIPPED_ELEMENTS: [&str; 8] = [
+    "nav", "script", "style", "noscript", "template", "head", "svg", "iframe",
+];
+
+/// Elements that start a new block of text
+const BLOCK_ELEMENTS: [&str; 20] = [
+    "p",
+    "div",
+    "section",
+    "article",
+    "main",
+    "aside",
+    "header",
+    "footer",
+    "table",
+    "tr",
+    "ul",
+    "ol",
+    "dl",
+    "dt",
+    "dd",
+    "blockquote",
+    "figure",
+    "figcaption",
+    "details",
+    "summary",
+];
+
+/// HtmlDocument text of an html page as markdown with the title and canonical URL of the page
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HtmlDocument {
+    pub title: Option<String>,
+    pub canonical_url: Option<String>,
+    pub markdown: String,
+}
+
+/// Convert an html page to markdown, navigation, scripts and styles are dropped,
+/// headings become `#` headings and `<pre>` blocks fenced code blocks
+/// Arguments:
+/// - html: &str content of the html file
+///
+/// Returns:
+/// - HtmlDocument
+pub fn html_to_markdown(html: &str) -> HtmlDocument {
+    let document = Html::parse_document(html);
+
+    let title = select_first(&document, "title")
+        .map(|title| collapse_whitespace(&title.text().collect::<String>()))
+        .filter(|title| !title.is_empty());
+    let canonical_url = select_first(&document, r#"link[rel~="canonical"]"#)
+        .and_then(|link| link.value().attr("href"))
+        .map(|href| href.trim().to_string())
+        .filter(|href| !href.is_empty());
+
+    let mut writer = MarkdownWriter::default();
+    writer.write_children(document.root_element());
+
+    HtmlDocument {
+        title,
+        canonical_url,
+        markdown: writer.finish(),
+    }
+}
+
+fn select_first<'a>(document: &'a Html, selector: &str) -> Option<ElementRef<'a>> {
+    let selector = Selector::parse(selector).ok()?;
+    document.select(&selector).next()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Language of a code block from a `language-rust` or `lang-rust` class
+fn code_language(element: &ElementRef) -> Option<String> {
+    element.value().classes().find_map(|class| {
+        class
+            .strip_prefix("language-")
+            .or_else(|| class.strip_prefix("lang-"))
+            .map(|language| language.to_lowercase())
+    })
+}
+
+#[derive(Default)]
+struct MarkdownWriter {
+    output: String,
+    /// Inline text of the current block, whitespace collapsed when flushed
+    line: String,
+}
+
+impl MarkdownWriter {
+    fn write_children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.line.push_str(text),
+                Node::Element(_) => {
+                    if let Some(child) = ElementRef::wrap(child) {
+                        self.write_element(child);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write_element(&mut self, element: ElementRef) {
+        let name = element.value().name();
+        match name {
+            name if SKIPPED_ELEMENTS.contains(&name) => {}
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                let text = collapse_whitespace(&element.text().collect::<String>());
+                self.flush_block();
+                if !text.is_empty() {
+                    self.push_block(&format!("{} {}", "#".repeat(level), text));
+                }
+            }
+            "pre" => {
+                let language = code_language(&element).or_else(|| {
+                    element
+                        .children()
+                        .filter_map(ElementRef::wrap)
+                        .find(|child| child.value().name() == "code")
+                        .and_then(|code| code_language(&code))
+                });
+                let code = element.text().collect::<String>();
+                self.flush_block();
+                self.push_block(&format!(
+                    "```{}\n{}\n```",
+                    language.unwrap_or_default(),
+                    code.trim_matches('\n')
+                ));
+            }
+            "code" => {
+                let code = element.text().collect::<String>();
+                self.line.push_str(&format!("`{}`", code.trim()));
+            }
+            "br" => self.flush_block(),
+            "li" => {
+                self.flush_block();
+                self.line.push_str("- ");
+                self.write_children(element);
+                self.flush_block();
+            }
+            "td" | "th" => {
+                self.write_children(element);
+                self.line.push_str(" | ");
+            }
+            name if BLOCK_ELEMENTS.contains(&name) => {
+                self.flush_block();
+                self.write_children(element);
+                self.flush_block();
+            }
+            _ => self.write_children(element),
+        }
+    }
+
+    fn flush_block(&mut self) {
+        let line = collapse_whitespace(&self.line);
+        self.line.clear();
+        let line = line.trim_end_matches(" |").trim();
+        if !line.is_empty() && line != "-" {
+            self.push_block(line);
+        }
+    }
+
+    fn push_block(&mut self, block: &str) {
+        if !self.output.is_empty() {
+            self.output.push_str("\n\n");
+        }
+        self.output.push_str(block);
+    }
+
+    fn finish(mut self) -> String {
+        self.flush_block();
+        self.output
+    }
+}
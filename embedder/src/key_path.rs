@@ -0,0 +1,246 @@
+use crate::file_loader::{ChunkMetadata, Sizer};
+use anyhow::{anyhow, Result};
+use text_splitter::{ChunkConfig, ChunkSizer, TextSplitter};
+use tree_sitter::{Node, Parser};
+use tree_sitter_language::LanguageFn;
+
+/// Separator of the keys in a key path e.g. `workspace.dependencies.lancedb`
+pub const KEY_SEPARATOR: &str = ".";
+
+/// Entry of a mapping or a sequence, `key` is the key of a mapping entry or `[index]`
+/// of a sequence item
+struct Entry<'t> {
+    key: String,
+    node: Node<'t>,
+    value: Node<'t>,
+}
+
+/// Section byte range of the file under a key path, sections with the same parent
+/// are merged into one chunk while they fit
+#[derive(Debug)]
+struct Section {
+    key_path: String,
+    parent: String,
+    start: usize,
+    end: usize,
+}
+
+/// Key path of a child of `path`, sequence indices are appended without a separator
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() || key.starts_with('[') {
+        format!("{}{}", path, key)
+    } else {
+        format!("{}{}{}", path, KEY_SEPARATOR, key)
+    }
+}
+
+/// Key of a mapping entry without its quotes, dotted toml keys are joined with the separator
+fn key_text(node: Node, source: &[u8]) -> String {
+    if node.kind() == "dotted_key" {
+        let mut cursor = node.walk();
+        return node
+            .named_children(&mut cursor)
+            .map(|key| key_text(key, source))
+            .collect::<Vec<_>>()
+            .join(KEY_SEPARATOR);
+    }
+    node.utf8_text(source)
+        .unwrap_or_default()
+        .trim()
+        .trim_matches(['"', '\''])
+        .to_string()
+}
+
+/// Entries of a json, yaml or toml node, scalars have none
+fn entries<'t>(node: Node<'t>, source: &[u8]) -> Vec<Entry<'t>> {
+    let mut cursor = node.walk();
+    let children: Vec<Node<'t>> = node.named_children(&mut cursor).collect();
+    match node.kind() {
+        // a yaml stream holds the documents of the file, a json document its single value
+        "stream" => children.iter().flat_map(|c| entries(*c, source)).collect(),
+        "document" if children.iter().all(|c| !is_toml_entry(*c)) => {
+            children.iter().flat_map(|c| entries(*c, source)).collect()
+        }
+        // the value of a yaml node comes after its tag and anchor
+        "block_node" | "flow_node" => children
+            .last()
+            .map(|c| entries(*c, source))
+            .unwrap_or_default(),
+        // pairs of a mapping, the top level pairs and tables of a toml document
+        "object"
+        | "block_mapping"
+        | "flow_mapping"
+        | "inline_table"
+        | "document"
+        | "table"
+        | "table_array_element" => children
+            .into_iter()
+            .filter_map(|child| mapping_entry(child, source))
+            .collect(),
+        "array" | "block_sequence" | "flow_sequence" => children
+            .into_iter()
+            .filter(|child| child.kind() != "comment")
+            .enumerate()
+            .map(|(i, child)| Entry {
+                key: format!("[{}]", i),
+                node: child,
+                value: match child.kind() {
+                    "block_sequence_item" => child.named_child(0).unwrap_or(child),
+                    _ => child,
+                },
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_toml_entry(node: Node) -> bool {
+    matches!(node.kind(), "pair" | "table" | "table_array_element")
+}
+
+/// Key and value of a pair, toml tables are entries under their header key
+fn mapping_entry<'t>(node: Node<'t>, source: &[u8]) -> Option<Entry<'t>> {
+    match node.kind() {
+        "pair" | "block_mapping_pair" | "flow_pair" => {
+            // toml pairs have no field names, the key comes first and the value last
+            let key = node.child_by_field_name("key").or(node.named_child(0))?;
+            let value = node
+                .child_by_field_name("value")
+                .or(node.named_child(node.named_child_count().checked_sub(1)?))?;
+            Some(Entry {
+                key: key_text(key, source),
+                node,
+                value,
+            })
+        }
+        "table" | "table_array_element" => Some(Entry {
+            key: key_text(node.named_child(0)?, source),
+            node,
+            value: node,
+        }),
+        _ => None,
+    }
+}
+
+/// Split a toml, yaml or json file on its keys, every chunk records the key path it was cut from.
+/// Entries that fit are kept whole and neighbouring entries of the same mapping are merged,
+/// entries too large for a chunk are split on their own entries, large scalars as text.
+/// Arguments:
+/// - grammar: LanguageFn tree-sitter grammar of the file
+/// - content: &str text of the file
+/// - chunk_config: ChunkConfig<Sizer>
+///
+/// Returns:
+/// - Result<Vec<(String, ChunkMetadata)>> chunks in file order, an error if the file doesn't parse
+pub fn split_key_paths(
+    grammar: LanguageFn,
+    content: &str,
+    chunk_config: ChunkConfig<Sizer>,
+) -> Result<Vec<(String, ChunkMetadata)>> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&grammar.into())
+        .map_err(|e| anyhow!("Failed to load tree-sitter grammar: {}", e))?;
+    let tree = parser
+        .parse(content, None)
+        .ok_or_else(|| anyhow!("Failed to parse file"))?;
+    if tree.root_node().has_error() {
+        return Err(anyhow!("Syntax error in file"));
+    }
+
+    let splitter = KeyPathSplitter {
+        content,
+        capacity: chunk_config.capacity().max(),
+        sizer: chunk_config.sizer().clone(),
+        text_splitter: TextSplitter::new(chunk_config),
+    };
+    // a file that fits is a single chunk with its comments
+    if splitter.fits(0, content.len()) {
+        let chunk = content.trim();
+        if chunk.is_empty() {
+            return Ok(Vec::new());
+        }
+        let offset = content.len() - content.trim_start().len();
+        return Ok(vec![(
+            chunk.to_string(),
+            ChunkMetadata::at_offset(offset, chunk),
+        )]);
+    }
+
+    let mut sections = Vec::new();
+    splitter.collect_sections(tree.root_node(), "", &mut sections);
+    Ok(splitter.merge_sections(sections))
+}
+
+struct KeyPathSplitter<'c> {
+    content: &'c str,
+    capacity: usize,
+    sizer: Sizer,
+    text_splitter: TextSplitter<Sizer>,
+}
+
+impl KeyPathSplitter<'_> {
+    fn fits(&self, start: usize, end: usize) -> bool {
+        self.sizer.size(&self.content[start..end]) <= self.capacity
+    }
+
+    fn collect_sections(&self, node: Node, path: &str, sections: &mut Vec<Section>) {
+        let entries = entries(node, self.content.as_bytes());
+        if entries.is_empty() {
+            // a scalar or an empty mapping too large for a chunk is split as text
+            let (start, end) = (node.start_byte(), node.end_byte());
+            for (offset, chunk) in self.text_splitter.chunk_indices(&self.content[start..end]) {
+                sections.push(Section {
+                    key_path: path.to_string(),
+                    parent: path.to_string(),
+                    start: start + offset,
+                    end: start + offset + chunk.len(),
+                });
+            }
+            return;
+        }
+
+        for entry in entries {
+            let key_path = child_path(path, &entry.key);
+            let (start, end) = (entry.node.start_byte(), entry.node.end_byte());
+            if self.fits(start, end) {
+                sections.push(Section {
+                    key_path,
+                    parent: path.to_string(),
+                    start,
+                    end,
+                });
+            } else {
+                self.collect_sections(entry.value, &key_path, sections);
+            }
+        }
+    }
+
+    /// Merge neighbouring sections of the same parent while they fit, a merged chunk
+    /// records the key path of the parent
+    fn merge_sections(&self, sections: Vec<Section>) -> Vec<(String, ChunkMetadata)> {
+        let mut chunks = Vec::new();
+        let mut sections = sections.into_iter().peekable();
+        while let Some(first) = sections.next() {
+            let mut end = first.end;
+            let mut merged = false;
+            while let Some(next) = sections
+                .next_if(|next| next.parent == first.parent && self.fits(first.start, next.end))
+            {
+                end = next.end;
+                merged = true;
+            }
+            let key_path = if merged { first.parent } else { first.key_path };
+            // toml tables end after the blank lines following them
+            let chunk = self.content[first.start..end].trim_end();
+            chunks.push((
+                chunk.to_string(),
+                ChunkMetadata {
+                    key_path: Some(key_path).filter(|p| !p.is_empty()),
+                    ..ChunkMetadata::at_offset(first.start, chunk)
+                },
+            ));
+        }
+        chunks
+    }
+}
@@ -1,6 +1,20 @@
+pub mod archive;
+pub mod chunk_policy;
+pub mod detect;
+pub mod embed_cache;
 pub mod embed_config;
 pub mod file_loader;
-
+pub mod html;
+pub mod key_path;
+pub mod logs;
+pub mod markdown;
+pub mod notebook;
+pub mod pdf;
+pub mod spark;
+pub mod symbols;
+pub mod walker;
+
+use anyhow::anyhow;
 use anyhow::Context;
 use anyhow::Result;
 use http_body_util::Full;
@@ -10,34 +24,127 @@ use std::str;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-#[allow(dead_code)]
+use configs::secret::redact_header;
 use configs::HttpsClient;
+use embed_cache::{content_hash, CacheKey, EmbedCache};
 use embed_config::{EmbedRequest, EmbedResponse};
 
-/// Fetch the embedding from the embedding service
+/// Fetch the embedding from the embedding service, cached embeddings are reused
 /// Arguments:
-/// - url: &str
 /// - embed_data: &Arc<RwLock<EmbedRequest>>
+/// - https_client: &HttpsClient
+/// - cache: Option<&EmbedCache> checked before calling the embedding service
 ///
 /// Returns:
 /// - EmbedResponse
 pub async fn fetch_embedding(
     embed_data: &Arc<RwLock<EmbedRequest>>,
     https_client: &HttpsClient,
+    cache: Option<&EmbedCache>,
 ) -> Result<EmbedResponse> {
     debug!("Running Embedding");
-    let embed_data = embed_data.read().await;
-
-    let embed_url = embed_data.get_embed_url();
+    let embed_url = embed_data.read().await.get_embed_url();
 
-    let response = create_embed_request(&embed_data, https_client)
+    let response = fetch_embedding_batch(std::slice::from_ref(embed_data), https_client, cache)
         .await
-        .with_context(|| format!("Failed to fetch embedding from api url {}", embed_url))?;
+        .with_context(|| format!("Failed to fetch embedding from api url {}", embed_url))?
+        .pop()
+        .ok_or_else(|| anyhow!("No embedding response from {}", embed_url))?;
 
     debug!("Finished Running Embedding");
     Ok(response)
 }
 
+/// Fetch the embeddings of several requests with a single call to the embedding service,
+/// the inputs are concatenated and the response is split back in request order. Inputs found
+/// in the cache are not sent and the fetched embeddings are added to the cache
+/// Arguments:
+/// - embed_requests: &[Arc<RwLock<EmbedRequest>>] sharing the provider, api url and model
+/// - https_client: &HttpsClient
+/// - cache: Option<&EmbedCache>
+///
+/// Returns:
+/// - Vec<EmbedResponse> one response per request
+pub async fn fetch_embedding_batch(
+    embed_requests: &[Arc<RwLock<EmbedRequest>>],
+    https_client: &HttpsClient,
+    cache: Option<&EmbedCache>,
+) -> Result<Vec<EmbedResponse>> {
+    let Some(first) = embed_requests.first() else {
+        return Ok(vec![]);
+    };
+
+    let mut batch_request = first.read().await.clone();
+    let mut inputs = Vec::new();
+    let mut input_counts = Vec::with_capacity(embed_requests.len());
+    for embed_request in embed_requests {
+        let embed_request = embed_request.read().await;
+        input_counts.push(embed_request.input.len());
+        inputs.extend(embed_request.input.iter().cloned());
+    }
+
+    let key = CacheKey::new(
+        &batch_request.provider,
+        &batch_request.model,
+        batch_request.dimensions,
+    );
+    let hashes: Vec<_> = inputs.iter().map(|input| content_hash(input)).collect();
+    let mut embeddings = match cache {
+        Some(cache) => cache.get_many(&key, &hashes)?,
+        None => vec![None; inputs.len()],
+    };
+    let missing: Vec<usize> = (0..inputs.len())
+        .filter(|&i| embeddings[i].is_none())
+        .collect();
+
+    let mut model = batch_request.model.clone();
+    if !missing.is_empty() {
+        batch_request.input = missing.iter().map(|&i| inputs[i].clone()).collect();
+        debug!(
+            "Running Embedding batch of {} requests with {} inputs, {} cached",
+            embed_requests.len(),
+            batch_request.input.len(),
+            inputs.len() - missing.len()
+        );
+        let response = create_embed_request(&batch_request, https_client)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to fetch embedding batch from api url {}",
+                    batch_request.get_embed_url()
+                )
+            })?;
+        if !response.model.is_empty() {
+            model = response.model;
+        }
+
+        let fetched: Vec<_> = missing
+            .iter()
+            .zip(response.embeddings)
+            .map(|(&i, embedding)| {
+                embeddings[i] = Some(embedding.clone());
+                (hashes[i], embedding)
+            })
+            .collect();
+        if let Some(cache) = cache {
+            cache.put_many(&key, fetched)?;
+        }
+    }
+
+    let mut embeddings = embeddings.into_iter().flatten();
+    let responses = input_counts
+        .into_iter()
+        .map(|count| {
+            EmbedResponse::NewEmbedResponse(
+                model.clone(),
+                embeddings.by_ref().take(count).collect(),
+            )
+        })
+        .collect();
+
+    Ok(responses)
+}
+
 /// Create an embedding request
 /// Arguments:
 /// - url: &str
@@ -66,17 +173,26 @@ pub async fn create_embed_request(
         .method("POST")
         .uri(&embed_url)
         .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", api_key))
+        .header("Authorization", format!("Bearer {}", api_key.expose()))
         .body(body)
         .context("Failed to build request")?;
 
+    for (name, value) in request.headers() {
+        debug!(
+            "Embed Request Header {}: {}",
+            name,
+            redact_header(name.as_str(), value.to_str().unwrap_or_default())
+        );
+    }
+
     // Send the request and await the response.
     let response_body = https_client
         .request(request)
         .await
         .with_context(|| format!("Failed to send request to {}", &embed_url))?;
 
-    debug!("Embedding Response status: {}", response_body.status());
+    let status = response_body.status();
+    debug!("Embedding Response status: {}", status);
 
     //collecting body bytes in Hyper 1.0
     let body_bytes = http_body_util::BodyExt::collect(response_body.into_body())
@@ -91,11 +207,23 @@ pub async fn create_embed_request(
     //     .await
     //     .context("Failed to read response body")?;
     let body = str::from_utf8(&body_bytes)?;
-    let response: EmbedResponse =
-        serde_json::from_str(body).context("Failed to parse Embedding response")?;
+    let response = EmbedResponse::from_provider_json(&req.provider, body)
+        .with_context(|| format!("Embedding request to {} failed with {}", embed_url, status))?;
+
+    if response.embeddings.len() != req.input.len() {
+        return Err(anyhow!(
+            "Expected {} embeddings from {} got {}",
+            req.input.len(),
+            embed_url,
+            response.embeddings.len()
+        ));
+    }
 
     debug!("Response: {:?}", response.model);
-    debug!("Response Length: {:?}", response.embeddings[0].len());
+    debug!(
+        "Response Length: {:?}",
+        response.embeddings.first().map(|e| e.len())
+    );
 
     Ok(response)
 }
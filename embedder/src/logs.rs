@@ -0,0 +1,372 @@
+use crate::file_loader::{ChunkMetadata, Sizer};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use configs::constants::{DEFAULT_LOG_LEVEL_PATTERN, DEFAULT_LOG_TIMESTAMP_PATTERN};
+use configs::settings::settings;
+use regex::Regex;
+use serde_json::{Map, Value};
+use text_splitter::{ChunkConfig, ChunkSizer, TextSplitter};
+
+/// Keys holding the timestamp of a json log entry
+const TIMESTAMP_KEYS: [&str; 6] = [
+    "timestamp",
+    "@timestamp",
+    "time",
+    "ts",
+    "datetime",
+    "asctime",
+];
+
+/// Keys holding the severity of a json log entry
+const LEVEL_KEYS: [&str; 5] = ["level", "severity", "lvl", "levelname", "log.level"];
+
+/// Severities from the least to the most severe, other levels rank below all of them
+const LEVELS: [&str; 6] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR", "FATAL"];
+
+/// Formats of the timestamps found in logs, parsed after `,` millis are turned into `.` millis
+const NAIVE_FORMATS: [&str; 3] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    // spark and hadoop
+    "%y/%m/%d %H:%M:%S",
+];
+
+/// LogFormat patterns of the timestamp and the severity of the entries of a log file
+#[derive(Debug, Clone)]
+pub struct LogFormat {
+    /// A line starting with a match starts a new entry, a `timestamp` group narrows the match
+    pub timestamp: Regex,
+    /// The first match after the timestamp is the severity, a `level` group narrows the match
+    pub level: Regex,
+}
+
+impl LogFormat {
+    pub fn new(timestamp_pattern: &str, level_pattern: &str) -> Result<Self> {
+        Ok(LogFormat {
+            timestamp: Regex::new(timestamp_pattern)
+                .with_context(|| format!("Invalid log timestamp pattern: {}", timestamp_pattern))?,
+            level: Regex::new(level_pattern)
+                .with_context(|| format!("Invalid log level pattern: {}", level_pattern))?,
+        })
+    }
+
+    /// Read the timestamp and level patterns from the settings
+    pub fn from_settings() -> Result<Self> {
+        let settings = settings();
+        LogFormat::new(
+            &settings.log_timestamp_pattern.value,
+            &settings.log_level_pattern.value,
+        )
+    }
+
+    /// Timestamp at the start of a line and the offset after it, only punctuation
+    /// e.g. `[` may come before it
+    fn line_timestamp<'l>(&self, line: &'l str) -> Option<(&'l str, usize)> {
+        let captures = self.timestamp.captures(line)?;
+        let whole = captures.get(0)?;
+        if !at_line_start(line, whole.start()) {
+            return None;
+        }
+        let timestamp = captures.name("timestamp").unwrap_or(whole);
+        Some((timestamp.as_str(), whole.end()))
+    }
+
+    /// First severity of a text, normalized
+    fn find_level(&self, text: &str) -> Option<String> {
+        let captures = self.level.captures(text)?;
+        let level = captures.name("level").or(captures.get(0))?;
+        Some(normalize_level(level.as_str()))
+    }
+
+    /// Severity at the start of a line e.g. `ERROR:root:failed` of logs without timestamps
+    fn line_level(&self, line: &str) -> Option<String> {
+        let captures = self.level.captures(line)?;
+        let whole = captures.get(0)?;
+        if !at_line_start(line, whole.start()) {
+            return None;
+        }
+        let level = captures.name("level").unwrap_or(whole);
+        Some(normalize_level(level.as_str()))
+    }
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat::new(DEFAULT_LOG_TIMESTAMP_PATTERN, DEFAULT_LOG_LEVEL_PATTERN)
+            .expect("default log patterns are valid")
+    }
+}
+
+/// LogEntry a record of a log file with its continuation lines e.g. a stack trace
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// Byte offset of the first line of the entry
+    pub start: usize,
+    /// Byte offset after the last line of the entry
+    pub end: usize,
+    /// Milliseconds since the epoch, timestamps without an offset are UTC
+    pub timestamp: Option<i64>,
+    /// Normalized severity e.g. `ERROR`
+    pub level: Option<String>,
+}
+
+/// A match only starts a line when nothing but punctuation comes before it
+fn at_line_start(line: &str, offset: usize) -> bool {
+    line[..offset]
+        .chars()
+        .all(|c| !c.is_alphanumeric() && !c.is_whitespace())
+}
+
+/// Upper case severity, `WARNING` is `WARN`, `ERR` is `ERROR` and `CRITICAL`, `SEVERE`
+/// and `PANIC` are `FATAL`
+pub fn normalize_level(level: &str) -> String {
+    let level = level.trim().to_uppercase();
+    match level.as_str() {
+        "WARNING" => "WARN".to_string(),
+        "ERR" => "ERROR".to_string(),
+        "CRITICAL" | "SEVERE" | "PANIC" => "FATAL".to_string(),
+        _ => level,
+    }
+}
+
+/// Rank of a normalized severity, 0 for levels that are not known
+fn severity(level: &str) -> usize {
+    LEVELS.iter().position(|l| *l == level).map_or(0, |i| i + 1)
+}
+
+/// The more severe of two levels, the first one on a tie
+fn most_severe(a: Option<String>, b: Option<String>) -> Option<String> {
+    match (a, b) {
+        (Some(a), Some(b)) if severity(&b) > severity(&a) => Some(b),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Milliseconds of an epoch in seconds or milliseconds, values below 1e11 are seconds
+fn epoch_millis(epoch: f64) -> i64 {
+    if epoch.abs() < 1e11 {
+        (epoch * 1000.0) as i64
+    } else {
+        epoch as i64
+    }
+}
+
+/// Parse a timestamp of a log or a date, RFC 3339, `2024-05-01 12:00:00,123`, spark
+/// `24/05/01 12:00:00`, `2024-05-01` and epoch seconds or milliseconds are understood
+/// Arguments:
+/// - timestamp: &str
+///
+/// Returns:
+/// - Option<i64> milliseconds since the epoch, timestamps without an offset are UTC
+pub fn parse_timestamp(timestamp: &str) -> Option<i64> {
+    let timestamp = timestamp.trim();
+    if let Ok(epoch) = timestamp.parse::<f64>() {
+        return Some(epoch_millis(epoch));
+    }
+    // log4j separates the milliseconds with a comma
+    let timestamp = timestamp.replacen(',', ".", 1);
+    if let Ok(time) = DateTime::parse_from_rfc3339(&timestamp) {
+        return Some(time.timestamp_millis());
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z"] {
+        if let Ok(time) = DateTime::parse_from_str(&timestamp, format) {
+            return Some(time.timestamp_millis());
+        }
+    }
+    for format in NAIVE_FORMATS {
+        if let Ok(time) = NaiveDateTime::parse_from_str(&timestamp, format) {
+            return Some(time.and_utc().timestamp_millis());
+        }
+    }
+    NaiveDate::parse_from_str(&timestamp, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc().timestamp_millis())
+}
+
+/// Lines of a text with the byte offset they start at, line endings included
+fn lines_with_offsets(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+    })
+}
+
+/// A json object on a single line
+fn json_object(line: &str) -> Option<Map<String, Value>> {
+    let line = line.trim();
+    if !line.starts_with('{') {
+        return None;
+    }
+    match serde_json::from_str(line) {
+        Ok(Value::Object(object)) => Some(object),
+        _ => None,
+    }
+}
+
+/// Timestamp of a json entry, a string or an epoch in seconds or milliseconds
+fn json_timestamp(object: &Map<String, Value>) -> Option<i64> {
+    TIMESTAMP_KEYS
+        .iter()
+        .filter_map(|key| object.get(*key))
+        .find_map(|value| match value {
+            Value::String(s) => parse_timestamp(s),
+            Value::Number(n) => n.as_f64().map(epoch_millis),
+            _ => None,
+        })
+}
+
+/// Severity of a json entry, numeric pino and bunyan levels are mapped to their names
+fn json_level(object: &Map<String, Value>) -> Option<String> {
+    LEVEL_KEYS
+        .iter()
+        .filter_map(|key| object.get(*key))
+        .find_map(|value| match value {
+            Value::String(s) => Some(normalize_level(s)),
+            Value::Number(n) => n
+                .as_u64()
+                .filter(|n| (10..=60).contains(n))
+                .map(|n| LEVELS[(n / 10 - 1) as usize].to_string()),
+            _ => None,
+        })
+}
+
+/// Split a log into its entries. Json lines logs have an entry per object, other logs start
+/// an entry on every line with a timestamp at its start, or with a level when no line has a
+/// timestamp. Lines that start no entry e.g. the frames of a java, python or rust stack trace
+/// belong to the entry above them, lines before the first entry are an entry of their own.
+/// Arguments:
+/// - content: &str text of the log
+/// - format: &LogFormat timestamp and level patterns
+///
+/// Returns:
+/// - Vec<LogEntry> entries in file order, empty when no line starts an entry
+pub fn parse_log_entries(content: &str, format: &LogFormat) -> Vec<LogEntry> {
+    let is_json = content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .and_then(json_object)
+        .is_some();
+    let has_timestamps = !is_json
+        && content
+            .lines()
+            .any(|line| format.line_timestamp(line).is_some());
+
+    let mut entries: Vec<LogEntry> = Vec::new();
+    let mut started = false;
+    for (offset, line) in lines_with_offsets(content) {
+        let entry = if is_json {
+            json_object(line).map(|object| (json_timestamp(&object), json_level(&object)))
+        } else if has_timestamps {
+            format.line_timestamp(line).map(|(timestamp, end)| {
+                (parse_timestamp(timestamp), format.find_level(&line[end..]))
+            })
+        } else {
+            format.line_level(line).map(|level| (None, Some(level)))
+        };
+
+        match entry {
+            Some((timestamp, level)) => {
+                started = true;
+                entries.push(LogEntry {
+                    start: offset,
+                    end: offset + line.len(),
+                    timestamp,
+                    level,
+                });
+            }
+            None => match entries.last_mut() {
+                Some(last) => last.end = offset + line.len(),
+                None => entries.push(LogEntry {
+                    start: offset,
+                    end: offset + line.len(),
+                    timestamp: None,
+                    level: None,
+                }),
+            },
+        }
+    }
+    if !started {
+        return Vec::new();
+    }
+    entries
+}
+
+/// Split a log on its entries, neighbouring entries are merged while they fit and an entry is
+/// only cut when it is too large for a chunk on its own. Every chunk records the timestamp of
+/// its first entry and its most severe level, logs without entries are split as text.
+/// Arguments:
+/// - content: &str text of the log
+/// - format: &LogFormat timestamp and level patterns
+/// - chunk_config: ChunkConfig<Sizer>
+///
+/// Returns:
+/// - Vec<(String, ChunkMetadata)> chunks in file order
+pub fn split_log(
+    content: &str,
+    format: &LogFormat,
+    chunk_config: ChunkConfig<Sizer>,
+) -> Vec<(String, ChunkMetadata)> {
+    let capacity = chunk_config.capacity().max();
+    let sizer = chunk_config.sizer().clone();
+    let text_splitter = TextSplitter::new(chunk_config);
+    let fits = |start: usize, end: usize| sizer.size(&content[start..end]) <= capacity;
+
+    let entries = parse_log_entries(content, format);
+    if entries.is_empty() {
+        return text_splitter
+            .chunk_indices(content)
+            .map(|(offset, chunk)| (chunk.to_string(), ChunkMetadata::at_offset(offset, chunk)))
+            .collect();
+    }
+
+    let mut chunks = Vec::new();
+    let mut entries = entries.into_iter().peekable();
+    while let Some(first) = entries.next() {
+        if !fits(first.start, first.end) {
+            // every piece of a large entry keeps its timestamp and level
+            for (offset, chunk) in text_splitter.chunk_indices(&content[first.start..first.end]) {
+                chunks.push(log_chunk(
+                    content,
+                    first.start + offset,
+                    first.start + offset + chunk.len(),
+                    first.timestamp,
+                    first.level.clone(),
+                ));
+            }
+            continue;
+        }
+
+        let (mut end, mut timestamp, mut level) = (first.end, first.timestamp, first.level);
+        while let Some(next) = entries.next_if(|next| fits(first.start, next.end)) {
+            end = next.end;
+            timestamp = timestamp.or(next.timestamp);
+            level = most_severe(level, next.level);
+        }
+        chunks.push(log_chunk(content, first.start, end, timestamp, level));
+    }
+    chunks.retain(|(chunk, _)| !chunk.is_empty());
+    chunks
+}
+
+/// Chunk of the byte range of a log without the blank lines around it
+fn log_chunk(
+    content: &str,
+    start: usize,
+    end: usize,
+    timestamp: Option<i64>,
+    level: Option<String>,
+) -> (String, ChunkMetadata) {
+    let text = &content[start..end];
+    let chunk = text.trim();
+    let offset = start + text.len() - text.trim_start().len();
+    (
+        chunk.to_string(),
+        ChunkMetadata {
+            log_timestamp: timestamp,
+            log_level: level,
+            ..ChunkMetadata::at_offset(offset, chunk)
+        },
+    )
+}
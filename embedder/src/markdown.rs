@@ -0,0 +1,185 @@
+use crate::file_loader::{ChunkMetadata, Sizer};
+use text_splitter::{ChunkConfig, MarkdownSplitter};
+
+/// Separator of the headings in a heading path e.g. `Usage > Commands`
+pub const HEADING_SEPARATOR: &str = " > ";
+
+/// An ATX heading `## Title` at a byte offset of the document
+#[derive(Debug)]
+struct Heading {
+    offset: usize,
+    level: usize,
+    title: String,
+}
+
+/// A fenced code block spanning a byte range of the document
+#[derive(Debug)]
+struct Fence {
+    start: usize,
+    end: usize,
+    language: String,
+}
+
+/// Headings and fenced code blocks of a markdown document, headings inside
+/// code blocks are ignored
+#[derive(Debug, Default)]
+struct Outline {
+    headings: Vec<Heading>,
+    fences: Vec<Fence>,
+}
+
+impl Outline {
+    fn parse(content: &str) -> Self {
+        let mut outline = Outline::default();
+        // marker, marker length and language of the open fence with its start offset
+        let mut open_fence: Option<(char, usize, String, usize)> = None;
+        let mut offset = 0;
+
+        for line in content.split_inclusive('\n') {
+            let trimmed = line.trim_end_matches(['\n', '\r']);
+            let indent = trimmed.len() - trimmed.trim_start_matches(' ').len();
+            let text = &trimmed[indent..];
+
+            if indent < 4 {
+                match &open_fence {
+                    Some((marker, len, language, start)) => {
+                        let closing = text.chars().take_while(|c| c == marker).count();
+                        if closing >= *len && text[closing..].trim().is_empty() {
+                            outline.fences.push(Fence {
+                                start: *start,
+                                end: offset + line.len(),
+                                language: language.clone(),
+                            });
+                            open_fence = None;
+                        }
+                    }
+                    None => {
+                        if let Some(fence) = parse_fence(text) {
+                            open_fence = Some((fence.0, fence.1, fence.2, offset));
+                        } else if let Some((level, title)) = parse_heading(text) {
+                            outline.headings.push(Heading {
+                                offset,
+                                level,
+                                title,
+                            });
+                        }
+                    }
+                }
+            }
+            offset += line.len();
+        }
+
+        // an unclosed fence runs to the end of the document
+        if let Some((_, _, language, start)) = open_fence {
+            outline.fences.push(Fence {
+                start,
+                end: content.len(),
+                language,
+            });
+        }
+        outline
+    }
+
+    /// Heading path in effect at a byte offset, a chunk starting with a heading includes it
+    fn heading_path(&self, offset: usize) -> Option<String> {
+        let mut path: Vec<&Heading> = Vec::new();
+        for heading in self.headings.iter().take_while(|h| h.offset <= offset) {
+            while path.last().is_some_and(|h| h.level >= heading.level) {
+                path.pop();
+            }
+            path.push(heading);
+        }
+        if path.is_empty() {
+            return None;
+        }
+        Some(
+            path.iter()
+                .map(|h| h.title.as_str())
+                .collect::<Vec<_>>()
+                .join(HEADING_SEPARATOR),
+        )
+    }
+
+    /// Languages of the fenced code blocks overlapping a byte range
+    fn code_languages(&self, start: usize, end: usize) -> Vec<String> {
+        let mut languages: Vec<String> = Vec::new();
+        for fence in self
+            .fences
+            .iter()
+            .filter(|f| f.start < end && f.end > start && !f.language.is_empty())
+        {
+            if !languages.contains(&fence.language) {
+                languages.push(fence.language.clone());
+            }
+        }
+        languages
+    }
+}
+
+/// Parse an opening fence "```rust" or "~~~ python", returns the marker, its length and the language
+fn parse_fence(text: &str) -> Option<(char, usize, String)> {
+    let marker = text.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = text.chars().take_while(|c| *c == marker).count();
+    if len < 3 {
+        return None;
+    }
+    let info = text[len..].trim();
+    // backtick fences can't have backticks in the info string
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    let language = info
+        .split(|c: char| c.is_whitespace() || c == '{' || c == ',')
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    Some((marker, len, language))
+}
+
+/// Parse an ATX heading "## Title ##", returns the level and the title
+fn parse_heading(text: &str) -> Option<(usize, String)> {
+    let level = text.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &text[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    // the closing `#` run needs a space before it, `## C#` keeps its `#`
+    let mut title = rest.trim();
+    let unclosed = title.trim_end_matches('#');
+    if unclosed.is_empty() || unclosed.ends_with([' ', '\t']) {
+        title = unclosed.trim_end();
+    }
+    if title.is_empty() {
+        return None;
+    }
+    Some((level, title.to_string()))
+}
+
+/// Split a markdown document with the markdown splitter, every chunk carries the heading
+/// path it belongs to, the languages of the code blocks it overlaps and its byte range
+/// Arguments:
+/// - content: &str markdown document
+/// - chunk_config: ChunkConfig<Sizer>
+///
+/// Returns:
+/// - Vec<(String, ChunkMetadata)> chunks in document order
+pub fn split_markdown(
+    content: &str,
+    chunk_config: ChunkConfig<Sizer>,
+) -> Vec<(String, ChunkMetadata)> {
+    let outline = Outline::parse(content);
+    MarkdownSplitter::new(chunk_config)
+        .chunk_indices(content)
+        .map(|(offset, chunk)| {
+            let metadata = ChunkMetadata {
+                heading: outline.heading_path(offset),
+                code_languages: outline.code_languages(offset, offset + chunk.len()),
+                ..ChunkMetadata::at_offset(offset, chunk)
+            };
+            (chunk.to_string(), metadata)
+        })
+        .collect()
+}
@@ -0,0 +1,120 @@
+use crate::file_loader::Language;
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+/// Text outputs of a code cell longer than this are dropped
+pub const MAX_OUTPUT_CHARS: usize = 1000;
+
+/// NotebookCell source of a cell of a jupyter notebook
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotebookCell {
+    /// Position of the cell in the notebook starting at 0
+    pub index: u32,
+    /// `code`, `markdown` or `raw`
+    pub cell_type: String,
+    /// Source of the cell, code cells end with their small text outputs
+    pub source: String,
+}
+
+/// Notebook cells of a jupyter notebook with the language of its kernel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notebook {
+    pub language: Language,
+    pub cells: Vec<NotebookCell>,
+}
+
+/// Parse the json of an `.ipynb` notebook, empty cells are skipped
+/// Arguments:
+/// - content: &str json of the notebook
+///
+/// Returns:
+/// - Result<Notebook>
+pub fn parse_notebook(content: &str) -> Result<Notebook> {
+    let notebook: Value = serde_json::from_str(content).context("Failed to parse notebook json")?;
+    let cells = notebook
+        .get("cells")
+        .and_then(Value::as_array)
+        .context("Notebook has no cells")?;
+
+    let cells = cells
+        .iter()
+        .enumerate()
+        .filter_map(|(index, cell)| {
+            let cell_type = cell.get("cell_type").and_then(Value::as_str)?.to_string();
+            let mut source = join_text(cell.get("source")?);
+            if cell_type == "code" {
+                for output in cell_outputs(cell) {
+                    source.push_str("\n\n");
+                    source.push_str(&output);
+                }
+            }
+            if source.trim().is_empty() {
+                return None;
+            }
+            Some(NotebookCell {
+                index: index as u32,
+                cell_type,
+                source,
+            })
+        })
+        .collect();
+
+    Ok(Notebook {
+        language: kernel_language(&notebook),
+        cells,
+    })
+}
+
+/// Language of the notebook kernel, python when the metadata doesn't name one
+fn kernel_language(notebook: &Value) -> Language {
+    let metadata = notebook.get("metadata");
+    let names = [
+        metadata
+            .and_then(|m| m.pointer("/kernelspec/language"))
+            .and_then(Value::as_str),
+        metadata
+            .and_then(|m| m.pointer("/language_info/name"))
+            .and_then(Value::as_str),
+    ];
+    names
+        .into_iter()
+        .flatten()
+        .map(Language::parse_name)
+        .find(|language| *language != Language::UNKNOWN)
+        .unwrap_or(Language::Python)
+}
+
+/// Text outputs of a code cell, images and outputs over `MAX_OUTPUT_CHARS` are dropped
+fn cell_outputs(cell: &Value) -> Vec<String> {
+    let Some(outputs) = cell.get("outputs").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    outputs
+        .iter()
+        .filter_map(|output| {
+            let text = match output.get("output_type").and_then(Value::as_str)? {
+                "stream" => output.get("text").map(join_text)?,
+                "execute_result" | "display_data" => {
+                    output.pointer("/data/text~1plain").map(join_text)?
+                }
+                "error" => {
+                    let name = output.get("ename").and_then(Value::as_str).unwrap_or("");
+                    let value = output.get("evalue").and_then(Value::as_str).unwrap_or("");
+                    format!("{}: {}", name, value)
+                }
+                _ => return None,
+            };
+            let text = text.trim_end().to_string();
+            (!text.trim().is_empty() && text.chars().count() <= MAX_OUTPUT_CHARS).then_some(text)
+        })
+        .collect()
+}
+
+/// Sources and outputs are a string or a list of lines
+fn join_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
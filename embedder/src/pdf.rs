@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use log::debug;
+use lopdf::Document;
+
+/// PdfText text of a PDF document or the reason it has none
+#[derive(Debug, Clone, PartialEq)]
+pub enum PdfText {
+    /// Page number starting at 1 and the text of every page with text
+    Pages(Vec<(u32, String)>),
+    /// The document needs a password
+    Encrypted,
+    /// No page has extractable text e.g. scanned documents
+    ImageOnly,
+}
+
+/// Extract the text of a PDF document page by page
+/// Arguments:
+/// - bytes: &[u8] content of the PDF file
+///
+/// Returns:
+/// - Result<PdfText> an error when the file is not a valid PDF
+pub fn extract_pdf_pages(bytes: &[u8]) -> Result<PdfText> {
+    let document = Document::load_mem(bytes).context("Failed to parse PDF")?;
+    if document.is_encrypted() {
+        return Ok(PdfText::Encrypted);
+    }
+
+    let mut pages = Vec::new();
+    for page_number in document.get_pages().into_keys() {
+        // a page with fonts the parser can't decode is skipped, not the whole document
+        let text = match document.extract_text(&[page_number]) {
+            Ok(text) => text,
+            Err(e) => {
+                debug!("Failed to extract text of page {}: {}", page_number, e);
+                continue;
+            }
+        };
+        if !text.trim().is_empty() {
+            pages.push((page_number, text));
+        }
+    }
+
+    if pages.is_empty() {
+        return Ok(PdfText::ImageOnly);
+    }
+    Ok(PdfText::Pages(pages))
+}
@@ -0,0 +1,435 @@
+use crate::file_loader::{ChunkMetadata, Sizer};
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use text_splitter::{ChunkConfig, TextSplitter};
+
+/// Lines kept of a stack trace or a failure reason, the rest is dropped
+const MAX_TRACE_LINES: usize = 20;
+
+/// Task failures of a stage attempt that failed the same way, counted together
+#[derive(Debug)]
+struct TaskFailure {
+    /// `Reason` of the task end e.g. `ExceptionFailure` or `FetchFailed`
+    reason: String,
+    description: String,
+    count: usize,
+    executors: BTreeSet<String>,
+    /// Stack trace of the first task failing this way
+    stack_trace: Option<String>,
+}
+
+/// Attempt of a stage with what went wrong while it ran
+#[derive(Debug, Default)]
+struct StageAttempt {
+    name: String,
+    num_tasks: Option<u64>,
+    failure_reason: Option<String>,
+    completion_time: Option<i64>,
+    task_failures: Vec<TaskFailure>,
+    executors_lost: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct Job {
+    stage_ids: Vec<u32>,
+    message: Option<String>,
+    stack_trace: Option<String>,
+    completion_time: Option<i64>,
+}
+
+/// Failure of a stage attempt or of a job in the order they completed
+#[derive(Debug, Clone, Copy)]
+enum Failure {
+    Stage(u32, u32),
+    Job(u32),
+}
+
+/// SparkEvents failures of a spark application collected from the events of its event log
+#[derive(Debug, Default)]
+struct SparkEvents {
+    app_id: Option<String>,
+    app_name: Option<String>,
+    stages: BTreeMap<(u32, u32), StageAttempt>,
+    jobs: BTreeMap<u32, Job>,
+    stage_jobs: HashMap<u32, u32>,
+    /// Stage attempts submitted and not completed yet, lost executors are blamed on them
+    running: BTreeSet<(u32, u32)>,
+    failures: Vec<Failure>,
+}
+
+fn str_field<'v>(value: &'v Value, key: &str) -> Option<&'v str> {
+    value.get(key).and_then(Value::as_str)
+}
+
+fn u32_field(value: &Value, key: &str) -> Option<u32> {
+    value
+        .get(key)
+        .and_then(Value::as_u64)
+        .and_then(|n| u32::try_from(n).ok())
+}
+
+/// First lines of a multi line text
+fn head_lines(text: &str, lines: usize) -> String {
+    text.lines()
+        .take(lines)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+/// Stack trace of a spark exception, a `Full Stack Trace` string or an array of frames
+fn stack_trace(value: &Value) -> Option<String> {
+    match value {
+        Value::String(trace) if !trace.trim().is_empty() => {
+            Some(head_lines(trace, MAX_TRACE_LINES))
+        }
+        Value::Array(frames) if !frames.is_empty() => Some(
+            frames
+                .iter()
+                .take(MAX_TRACE_LINES)
+                .map(|frame| {
+                    format!(
+                        "\tat {}.{}({}:{})",
+                        str_field(frame, "Declaring Class").unwrap_or_default(),
+                        str_field(frame, "Method Name").unwrap_or_default(),
+                        str_field(frame, "File Name").unwrap_or("Unknown Source"),
+                        frame
+                            .get("Line Number")
+                            .and_then(Value::as_i64)
+                            .unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        _ => None,
+    }
+}
+
+/// Description and stack trace of a failed `Task End Reason`
+fn task_failure(reason: &Value) -> (String, Option<String>) {
+    let field = |key| str_field(reason, key).unwrap_or_default();
+    match field("Reason") {
+        "ExceptionFailure" => (
+            format!("{}: {}", field("Class Name"), field("Description")),
+            reason
+                .get("Full Stack Trace")
+                .and_then(stack_trace)
+                .or_else(|| reason.get("Stack Trace").and_then(stack_trace)),
+        ),
+        "FetchFailed" => (head_lines(field("Message"), 1), None),
+        "ExecutorLostFailure" => (
+            format!(
+                "executor {}: {}",
+                field("Executor ID"),
+                field("Loss Reason")
+            ),
+            None,
+        ),
+        "TaskKilled" => (field("Kill Reason").to_string(), None),
+        _ => (String::new(), None),
+    }
+}
+
+impl SparkEvents {
+    fn add_event(&mut self, event: &Value) {
+        match str_field(event, "Event").unwrap_or_default() {
+            "SparkListenerApplicationStart" => {
+                self.app_id = str_field(event, "App ID").map(str::to_string);
+                self.app_name = str_field(event, "App Name").map(str::to_string);
+            }
+            "SparkListenerJobStart" => {
+                let Some(job_id) = u32_field(event, "Job ID") else {
+                    return;
+                };
+                let stage_ids: Vec<u32> = event
+                    .get("Stage IDs")
+                    .and_then(Value::as_array)
+                    .map(|ids| {
+                        ids.iter()
+                            .filter_map(|id| id.as_u64().and_then(|id| u32::try_from(id).ok()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                for stage_id in &stage_ids {
+                    self.stage_jobs.insert(*stage_id, job_id);
+                }
+                self.jobs.entry(job_id).or_default().stage_ids = stage_ids;
+            }
+            "SparkListenerStageSubmitted" | "SparkListenerStageCompleted" => {
+                let Some(info) = event.get("Stage Info") else {
+                    return;
+                };
+                let (Some(stage_id), Some(attempt)) = (
+                    u32_field(info, "Stage ID"),
+                    u32_field(info, "Stage Attempt ID"),
+                ) else {
+                    return;
+                };
+                let stage = self.stages.entry((stage_id, attempt)).or_default();
+                stage.name = str_field(info, "Stage Name")
+                    .unwrap_or_default()
+                    .to_string();
+                stage.num_tasks = info.get("Number of Tasks").and_then(Value::as_u64);
+                if str_field(event, "Event") == Some("SparkListenerStageSubmitted") {
+                    self.running.insert((stage_id, attempt));
+                    return;
+                }
+                self.running.remove(&(stage_id, attempt));
+                if let Some(reason) = str_field(info, "Failure Reason") {
+                    stage.failure_reason = Some(reason.to_string());
+                    stage.completion_time = info.get("Completion Time").and_then(Value::as_i64);
+                    self.failures.push(Failure::Stage(stage_id, attempt));
+                }
+            }
+            "SparkListenerTaskEnd" => {
+                let (Some(stage_id), Some(attempt), Some(reason)) = (
+                    u32_field(event, "Stage ID"),
+                    u32_field(event, "Stage Attempt ID"),
+                    event.get("Task End Reason"),
+                ) else {
+                    return;
+                };
+                let name = str_field(reason, "Reason").unwrap_or_default();
+                if name == "Success" {
+                    return;
+                }
+                let (description, trace) = task_failure(reason);
+                let executor = event
+                    .get("Task Info")
+                    .and_then(|info| str_field(info, "Executor ID"))
+                    .unwrap_or_default()
+                    .to_string();
+                let failures = &mut self
+                    .stages
+                    .entry((stage_id, attempt))
+                    .or_default()
+                    .task_failures;
+                match failures
+                    .iter_mut()
+                    .find(|f| f.reason == name && f.description == description)
+                {
+                    Some(failure) => {
+                        failure.count += 1;
+                        failure.executors.insert(executor);
+                    }
+                    None => failures.push(TaskFailure {
+                        reason: name.to_string(),
+                        description,
+                        count: 1,
+                        executors: BTreeSet::from([executor]),
+                        stack_trace: trace,
+                    }),
+                }
+            }
+            "SparkListenerExecutorRemoved" => {
+                let lost = format!(
+                    "executor {}: {}",
+                    str_field(event, "Executor ID").unwrap_or_default(),
+                    str_field(event, "Removed Reason").unwrap_or_default()
+                );
+                for key in &self.running {
+                    if let Some(stage) = self.stages.get_mut(key) {
+                        stage.executors_lost.push(lost.clone());
+                    }
+                }
+            }
+            "SparkListenerJobEnd" => {
+                let (Some(job_id), Some(result)) =
+                    (u32_field(event, "Job ID"), event.get("Job Result"))
+                else {
+                    return;
+                };
+                if str_field(result, "Result") != Some("JobFailed") {
+                    return;
+                }
+                let exception = result.get("Exception");
+                let job = self.jobs.entry(job_id).or_default();
+                job.message = exception
+                    .and_then(|e| str_field(e, "Message"))
+                    .map(str::to_string);
+                job.stack_trace =
+                    exception.and_then(|e| e.get("Stack Trace").and_then(stack_trace));
+                job.completion_time = event.get("Completion Time").and_then(Value::as_i64);
+                self.failures.push(Failure::Job(job_id));
+            }
+            _ => {}
+        }
+    }
+
+    /// First line of the chunk naming the application
+    fn app_line(&self) -> String {
+        match (&self.app_id, &self.app_name) {
+            (Some(id), Some(name)) => format!("Spark application {} ({})", id, name),
+            (Some(id), None) => format!("Spark application {}", id),
+            (None, Some(name)) => format!("Spark application {}", name),
+            (None, None) => "Spark application".to_string(),
+        }
+    }
+
+    fn stage_chunk(&self, stage_id: u32, attempt: u32) -> Option<(String, ChunkMetadata)> {
+        let stage = self.stages.get(&(stage_id, attempt))?;
+        let job_id = self.stage_jobs.get(&stage_id).copied();
+        let reason = stage.failure_reason.as_deref().unwrap_or_default();
+
+        let mut lines = vec![self.app_line()];
+        lines.push(match job_id {
+            Some(job_id) => format!(
+                "Stage {} attempt {} of job {} failed: {}",
+                stage_id, attempt, job_id, stage.name
+            ),
+            None => format!(
+                "Stage {} attempt {} failed: {}",
+                stage_id, attempt, stage.name
+            ),
+        });
+        lines.push(format!(
+            "Failure reason: {}",
+            head_lines(reason, MAX_TRACE_LINES)
+        ));
+        let failed_tasks: usize = stage.task_failures.iter().map(|f| f.count).sum();
+        if let Some(num_tasks) = stage.num_tasks {
+            lines.push(format!(
+                "Tasks: {}, failed tasks: {}",
+                num_tasks, failed_tasks
+            ));
+        }
+        if !stage.task_failures.is_empty() {
+            lines.push("Task failures:".to_string());
+        }
+        for failure in &stage.task_failures {
+            let executors: Vec<&str> = failure
+                .executors
+                .iter()
+                .filter(|e| !e.is_empty())
+                .map(String::as_str)
+                .collect();
+            lines.push(format!(
+                "- {} x {} {} on executors {}",
+                failure.count,
+                failure.reason,
+                failure.description,
+                executors.join(", ")
+            ));
+            lines.extend(failure.stack_trace.clone());
+        }
+        if !stage.executors_lost.is_empty() {
+            lines.push("Executors lost while the stage ran:".to_string());
+            lines.extend(
+                stage
+                    .executors_lost
+                    .iter()
+                    .map(|lost| format!("- {}", lost)),
+            );
+        }
+
+        Some((
+            lines.join("\n"),
+            ChunkMetadata {
+                spark_job_id: job_id,
+                spark_stage_id: Some(stage_id),
+                spark_stage_attempt: Some(attempt),
+                failure_reason: Some(head_lines(reason, 1)),
+                log_timestamp: stage.completion_time,
+                ..self.failure_metadata()
+            },
+        ))
+    }
+
+    fn job_chunk(&self, job_id: u32) -> Option<(String, ChunkMetadata)> {
+        let job = self.jobs.get(&job_id)?;
+        let message = job.message.as_deref().unwrap_or_default();
+        let failed_stages: Vec<String> = self
+            .stages
+            .iter()
+            .filter(|((stage_id, _), stage)| {
+                job.stage_ids.contains(stage_id) && stage.failure_reason.is_some()
+            })
+            .map(|((stage_id, attempt), _)| format!("{} attempt {}", stage_id, attempt))
+            .collect();
+
+        let mut lines = vec![self.app_line()];
+        lines.push(format!(
+            "Job {} failed: {}",
+            job_id,
+            head_lines(message, MAX_TRACE_LINES)
+        ));
+        if !job.stage_ids.is_empty() {
+            let stage_ids: Vec<String> = job.stage_ids.iter().map(u32::to_string).collect();
+            lines.push(format!("Stages: {}", stage_ids.join(", ")));
+        }
+        if !failed_stages.is_empty() {
+            lines.push(format!("Failed stages: {}", failed_stages.join(", ")));
+        }
+        lines.extend(job.stack_trace.clone());
+
+        Some((
+            lines.join("\n"),
+            ChunkMetadata {
+                spark_job_id: Some(job_id),
+                failure_reason: Some(head_lines(message, 1)),
+                log_timestamp: job.completion_time,
+                ..self.failure_metadata()
+            },
+        ))
+    }
+
+    fn failure_metadata(&self) -> ChunkMetadata {
+        ChunkMetadata {
+            spark_app_id: self.app_id.clone(),
+            log_level: Some("ERROR".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Split a spark event log into a chunk per failed stage attempt and per failed job. A stage
+/// chunk holds the failure reason, the task failures counted by cause with the first stack
+/// trace of each and the executors lost while it ran, a job chunk the exception of the job
+/// and its failed stages. Every chunk records the app id, job id, stage id, attempt and
+/// failure reason, chunks too large for the chunk size are split as text.
+/// Arguments:
+/// - content: &str json lines of the event log
+/// - chunk_config: ChunkConfig<Sizer>
+///
+/// Returns:
+/// - Result<Vec<(String, ChunkMetadata)>> chunks in the order the failures happened, an error
+///   when the file holds no spark listener events
+pub fn split_spark_events(
+    content: &str,
+    chunk_config: ChunkConfig<Sizer>,
+) -> Result<Vec<(String, ChunkMetadata)>> {
+    let mut events = SparkEvents::default();
+    let mut has_events = false;
+    for line in content.lines().filter(|line| !line.trim().is_empty()) {
+        // an event log still being written may end in a partial line
+        let Ok(event) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if str_field(&event, "Event").is_some_and(|e| e.starts_with("SparkListener")) {
+            has_events = true;
+            events.add_event(&event);
+        }
+    }
+    if !has_events {
+        return Err(anyhow!("No spark listener events"));
+    }
+
+    let splitter = TextSplitter::new(chunk_config);
+    Ok(events
+        .failures
+        .iter()
+        .filter_map(|failure| match *failure {
+            Failure::Stage(stage_id, attempt) => events.stage_chunk(stage_id, attempt),
+            Failure::Job(job_id) => events.job_chunk(job_id),
+        })
+        .flat_map(|(text, metadata)| {
+            splitter
+                .chunks(&text)
+                .map(|chunk| (chunk.to_string(), metadata.clone()))
+                .collect::<Vec<_>>()
+        })
+        .collect())
+}
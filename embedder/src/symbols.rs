@@ -0,0 +1,205 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use tree_sitter::{Node, Parser};
+use tree_sitter_language::LanguageFn;
+
+/// Separator of the symbols of a chunk in the `symbols` column
+pub const SYMBOL_SEPARATOR: &str = ",";
+
+/// Tree-sitter node kinds of definitions and the kind of symbol they define,
+/// kinds shared by several grammars e.g. `function_definition` map to the same symbol
+const SYMBOL_KINDS: [(&str, &str); 36] = [
+    // rust
+    ("mod_item", "module"),
+    ("impl_item", "impl"),
+    ("trait_item", "trait"),
+    ("function_item", "function"),
+    ("function_signature_item", "function"),
+    ("struct_item", "struct"),
+    ("enum_item", "enum"),
+    ("macro_definition", "macro"),
+    // python, c, c++, scala and bash
+    ("class_definition", "class"),
+    ("function_definition", "function"),
+    // java, javascript, typescript and c#
+    ("class_declaration", "class"),
+    ("abstract_class_declaration", "class"),
+    ("interface_declaration", "interface"),
+    ("enum_declaration", "enum"),
+    ("record_declaration", "record"),
+    ("method_declaration", "method"),
+    ("constructor_declaration", "constructor"),
+    ("function_declaration", "function"),
+    ("generator_function_declaration", "function"),
+    ("method_definition", "method"),
+    ("type_alias_declaration", "type"),
+    ("internal_module", "namespace"),
+    // go
+    ("type_spec", "type"),
+    // c and c++
+    ("struct_specifier", "struct"),
+    ("class_specifier", "class"),
+    ("enum_specifier", "enum"),
+    ("namespace_definition", "namespace"),
+    // scala
+    ("object_definition", "object"),
+    ("trait_definition", "trait"),
+    // c#
+    ("struct_declaration", "struct"),
+    ("namespace_declaration", "namespace"),
+    ("file_scoped_namespace_declaration", "namespace"),
+    // ruby
+    ("class", "class"),
+    ("module", "module"),
+    ("method", "method"),
+    ("singleton_method", "method"),
+];
+
+/// Symbol a named definition e.g. the function `main`, written as `function:main`
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// `module`, `impl`, `trait`, `function`, `class`, `method` ...
+    pub kind: String,
+    pub name: String,
+}
+
+impl Symbol {
+    pub fn new(kind: &str, name: &str) -> Self {
+        Symbol {
+            kind: kind.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    /// Parse a symbol written as `kind:name`
+    pub fn parse(s: &str) -> Option<Self> {
+        let (kind, name) = s.trim().split_once(':')?;
+        (!kind.is_empty() && !name.is_empty()).then(|| Symbol::new(kind, name))
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.kind, self.name)
+    }
+}
+
+/// Symbols of a `symbols` column value e.g. `impl:FileChunk,function:new`
+pub fn parse_symbols(s: &str) -> Vec<Symbol> {
+    s.split(SYMBOL_SEPARATOR)
+        .filter_map(Symbol::parse)
+        .collect()
+}
+
+/// Value of the `symbols` column, None without symbols
+pub fn format_symbols(symbols: &[Symbol]) -> Option<String> {
+    if symbols.is_empty() {
+        return None;
+    }
+    Some(
+        symbols
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join(SYMBOL_SEPARATOR),
+    )
+}
+
+/// SymbolSpan a symbol with the byte range of its definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolSpan {
+    pub symbol: Symbol,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Parse a source file and collect the definitions it contains
+/// Arguments:
+/// - grammar: LanguageFn tree-sitter grammar of the file
+/// - content: &str source of the file
+///
+/// Returns:
+/// - Result<Vec<SymbolSpan>> definitions in the order they start, outer definitions first
+pub fn extract_symbols(grammar: LanguageFn, content: &str) -> Result<Vec<SymbolSpan>> {
+    let mut parser = Parser::new();
+    parser
+        .set_language(&grammar.into())
+        .map_err(|e| anyhow!("Failed to load tree-sitter grammar: {}", e))?;
+    let tree = parser
+        .parse(content, None)
+        .ok_or_else(|| anyhow!("Failed to parse source"))?;
+
+    let mut spans = Vec::new();
+    let mut cursor = tree.walk();
+    // pre-order walk, parents are visited before their children
+    loop {
+        let node = cursor.node();
+        if let Some(symbol) = node_symbol(node, content.as_bytes()) {
+            spans.push(SymbolSpan {
+                symbol,
+                start: node.start_byte(),
+                end: node.end_byte(),
+            });
+        }
+        if cursor.goto_first_child() || cursor.goto_next_sibling() {
+            continue;
+        }
+        loop {
+            if !cursor.goto_parent() {
+                return Ok(spans);
+            }
+            if cursor.goto_next_sibling() {
+                break;
+            }
+        }
+    }
+}
+
+/// Symbols of the definitions overlapping a byte range, the definitions enclosing the range
+/// come before the ones inside it
+pub fn symbols_in_range(spans: &[SymbolSpan], start: usize, end: usize) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = Vec::new();
+    for span in spans
+        .iter()
+        .filter(|span| span.start < end.max(start + 1) && span.end > start)
+    {
+        if !symbols.contains(&span.symbol) {
+            symbols.push(span.symbol.clone());
+        }
+    }
+    symbols
+}
+
+/// Symbol defined by a node, declarations without a body e.g. `struct point *p;` define none
+fn node_symbol(node: Node, source: &[u8]) -> Option<Symbol> {
+    // keywords e.g. `class` share the kind of the ruby definitions
+    if !node.is_named() {
+        return None;
+    }
+    let (_, kind) = SYMBOL_KINDS.iter().find(|(k, _)| *k == node.kind())?;
+    if node.kind().ends_with("_specifier") && node.child_by_field_name("body").is_none() {
+        return None;
+    }
+    let name_node = match node.kind() {
+        "impl_item" => node.child_by_field_name("type"),
+        _ => node
+            .child_by_field_name("name")
+            .or_else(|| declarator_name(node)),
+    }?;
+    let name = name_node.utf8_text(source).ok()?;
+    // generic parameters are not part of the name e.g. `impl<T> Wrapper<T>`
+    let name = name.split('<').next().unwrap_or(name).trim();
+    if name.is_empty() || name.contains(SYMBOL_SEPARATOR) {
+        return None;
+    }
+    Some(Symbol::new(kind, name))
+}
+
+/// Innermost declarator of a c or c++ function e.g. `main` in `int *main(void)`
+fn declarator_name(node: Node) -> Option<Node> {
+    let mut declarator = node.child_by_field_name("declarator")?;
+    while let Some(inner) = declarator.child_by_field_name("declarator") {
+        declarator = inner;
+    }
+    Some(declarator)
+}
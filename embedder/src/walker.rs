@@ -0,0 +1,517 @@
+use crate::archive::{ArchiveFormat, ArchiveLimits};
+use anyhow::{Context, Result};
+use configs::constants::{
+    DEFAULT_MAX_ARCHIVE_ENTRIES, DEFAULT_MAX_ARCHIVE_SIZE, DEFAULT_MAX_FILE_SIZE,
+};
+use configs::settings::settings;
+use glob::Pattern;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::{DirEntry, WalkBuilder};
+use log::debug;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Ignore files read in every directory, later files win over `.git/info/exclude`
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// SkipReason why a path was not loaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum SkipReason {
+    /// Matched by a `.gitignore` or `.ignore` file, `.git/info/exclude` or the global gitignore
+    Ignored,
+    /// The `.git` directory of a repository
+    GitDirectory,
+    /// Matched by an exclude glob
+    Excluded,
+    /// Not matched by any include glob
+    NotIncluded,
+    /// Size in bytes above the size limit
+    TooLarge(u64),
+    /// Contains NUL bytes or is not valid UTF-8
+    Binary,
+    /// Symlink to a directory that is already being walked
+    SymlinkLoop,
+    /// No loader for the file type
+    Unsupported,
+    /// PDF that needs a password
+    Encrypted,
+    /// Document without extractable text e.g. a scanned PDF
+    NoText,
+    /// The file could not be read or parsed
+    Unreadable(String),
+    /// An archive stopped being read at its entry or size limit
+    ArchiveLimit(String),
+}
+
+impl SkipReason {
+    /// Short name of the reason without its details
+    pub fn label(&self) -> &'static str {
+        match self {
+            SkipReason::Ignored => "ignored",
+            SkipReason::GitDirectory => "git directory",
+            SkipReason::Excluded => "excluded",
+            SkipReason::NotIncluded => "not included",
+            SkipReason::TooLarge(_) => "too large",
+            SkipReason::Binary => "binary",
+            SkipReason::SymlinkLoop => "symlink loop",
+            SkipReason::Unsupported => "unsupported",
+            SkipReason::Encrypted => "encrypted",
+            SkipReason::NoText => "no text",
+            SkipReason::Unreadable(_) => "unreadable",
+            SkipReason::ArchiveLimit(_) => "archive limit",
+        }
+    }
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkipReason::Ignored => write!(f, "ignored by a gitignore or .ignore file"),
+            SkipReason::GitDirectory => write!(f, "git directory"),
+            SkipReason::Excluded => write!(f, "matches an exclude glob"),
+            SkipReason::NotIncluded => write!(f, "matches no include glob"),
+            SkipReason::TooLarge(size) => write!(f, "{} bytes is above the size limit", size),
+            SkipReason::Binary => write!(f, "binary file"),
+            SkipReason::SymlinkLoop => write!(f, "symlink loop"),
+            SkipReason::Unsupported => write!(f, "unsupported file type"),
+            SkipReason::Encrypted => write!(f, "encrypted"),
+            SkipReason::NoText => write!(f, "no extractable text"),
+            SkipReason::Unreadable(error) => write!(f, "unreadable: {}", error),
+            SkipReason::ArchiveLimit(limit) => write!(f, "archive limit reached: {}", limit),
+        }
+    }
+}
+
+/// One line count of the skipped paths per reason e.g. `Skipped 3 paths: 2 ignored, 1 binary`
+pub fn skip_summary(skipped: &[SkippedPath]) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for skipped in skipped {
+        let label = skipped.reason.label();
+        match counts.iter_mut().find(|(l, _)| *l == label) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((label, 1)),
+        }
+    }
+    let counts = counts
+        .iter()
+        .map(|(label, count)| format!("{} {}", count, label))
+        .collect::<Vec<_>>()
+        .join(", ");
+    match skipped.len() {
+        0 => "Skipped 0 paths".to_string(),
+        n => format!("Skipped {} paths: {}", n, counts),
+    }
+}
+
+/// SkippedPath a file or directory left out of a load
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkippedPath {
+    pub path: PathBuf,
+    pub reason: SkipReason,
+}
+
+impl fmt::Display for SkippedPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.reason)
+    }
+}
+
+/// WalkOptions which files of a directory are loaded
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    /// Globs relative to the loaded directory, when set only matching files are loaded
+    pub include: Vec<String>,
+    /// Globs relative to the loaded directory of files and directories left out
+    pub exclude: Vec<String>,
+    /// Files above this size in bytes are skipped, 0 is unlimited
+    pub max_file_size: u64,
+    /// Bytes an archive may expand to, its remaining entries are skipped, 0 is unlimited
+    pub max_archive_size: u64,
+    /// Entries read from an archive, its remaining entries are skipped, 0 is unlimited
+    pub max_archive_entries: usize,
+    /// Honor `.gitignore` and `.ignore` files, `.git/info/exclude` and the global gitignore
+    pub respect_ignore_files: bool,
+    /// Files read and split at the same time, 0 uses the number of CPUs
+    pub parallelism: usize,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            max_archive_size: DEFAULT_MAX_ARCHIVE_SIZE,
+            max_archive_entries: DEFAULT_MAX_ARCHIVE_ENTRIES,
+            respect_ignore_files: true,
+            parallelism: 0,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Walk options with the size limits and parallelism of the settings
+    pub fn from_settings() -> Self {
+        WalkOptions {
+            max_file_size: settings().max_file_size.value,
+            max_archive_size: settings().max_archive_size.value,
+            max_archive_entries: settings().max_archive_entries.value,
+            parallelism: settings().load_parallelism.value,
+            ..WalkOptions::default()
+        }
+    }
+}
+
+/// WalkedFiles files to load in a stable order and the paths left out
+#[derive(Debug, Default)]
+pub struct WalkedFiles {
+    pub files: Vec<PathBuf>,
+    pub skipped: Vec<SkippedPath>,
+}
+
+/// Walker recursive directory walk honoring ignore files, globs and the size limit
+pub struct Walker {
+    root: PathBuf,
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    max_file_size: u64,
+    archive_limits: ArchiveLimits,
+    respect_ignore_files: bool,
+}
+
+impl Walker {
+    /// Walker of a directory
+    /// Arguments:
+    /// - root: &Path directory to walk
+    /// - options: &WalkOptions
+    ///
+    /// Returns:
+    /// - Result<Walker> an error for an invalid glob
+    pub fn new(root: &Path, options: &WalkOptions) -> Result<Self> {
+        let parse = |globs: &[String]| {
+            globs
+                .iter()
+                .map(|glob| Pattern::new(glob).with_context(|| format!("Invalid glob: {}", glob)))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Walker {
+            root: root.to_path_buf(),
+            include: parse(&options.include)?,
+            exclude: parse(&options.exclude)?,
+            max_file_size: options.max_file_size,
+            archive_limits: ArchiveLimits {
+                max_size: options.max_archive_size,
+                max_entries: options.max_archive_entries,
+            },
+            respect_ignore_files: options.respect_ignore_files,
+        })
+    }
+
+    /// Path relative to the walked directory, globs are matched against it
+    pub fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(&self.root).unwrap_or(path)
+    }
+
+    /// Limits on the entries and expanded size of the archives found by the walk
+    pub fn archive_limits(&self) -> &ArchiveLimits {
+        &self.archive_limits
+    }
+
+    /// Check a single file against the globs and the size limit, archives are only matched
+    /// against the exclude globs and their entries are checked as they are read
+    pub fn check_file(&self, path: &Path) -> Option<SkipReason> {
+        let relative_path = self.relative_path(path);
+        let is_archive = ArchiveFormat::from_path(path).is_some();
+        if self.exclude.iter().any(|p| p.matches_path(relative_path)) {
+            return Some(SkipReason::Excluded);
+        }
+        if !is_archive && !self.is_included(relative_path) {
+            return Some(SkipReason::NotIncluded);
+        }
+        let max_size = if is_archive {
+            self.archive_limits.max_size
+        } else {
+            self.max_file_size
+        };
+        match fs::metadata(path) {
+            Ok(metadata) if max_size > 0 && metadata.len() > max_size => {
+                Some(SkipReason::TooLarge(metadata.len()))
+            }
+            Ok(_) => None,
+            Err(e) => Some(SkipReason::Unreadable(e.to_string())),
+        }
+    }
+
+    /// Check an archive entry against the globs and the size limit
+    /// Arguments:
+    /// - relative_path: &Path of the entry e.g. `logs.zip!/driver/stderr`
+    /// - size: u64 uncompressed size of the entry in bytes
+    ///
+    /// Returns:
+    /// - Option<SkipReason> None when the entry is loaded
+    pub fn check_entry(&self, relative_path: &Path, size: u64) -> Option<SkipReason> {
+        if self.exclude.iter().any(|p| p.matches_path(relative_path)) {
+            return Some(SkipReason::Excluded);
+        }
+        if !self.is_included(relative_path) {
+            return Some(SkipReason::NotIncluded);
+        }
+        if self.max_file_size > 0 && size > self.max_file_size {
+            return Some(SkipReason::TooLarge(size));
+        }
+        None
+    }
+
+    fn is_included(&self, relative_path: &Path) -> bool {
+        self.include.is_empty() || self.include.iter().any(|p| p.matches_path(relative_path))
+    }
+
+    /// Walk the directory, entries of a directory are visited in name order, symlinks are
+    /// followed and unreadable paths are skipped without stopping the walk
+    pub fn walk(&self) -> Result<WalkedFiles> {
+        let filter = Arc::new(EntryFilter::new(self));
+        let entry_filter = Arc::clone(&filter);
+        let walk = WalkBuilder::new(&self.root)
+            .standard_filters(false)
+            .follow_links(true)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry(move |entry| entry_filter.keep(entry))
+            .build();
+
+        let mut walked = WalkedFiles::default();
+        for entry in walk {
+            walked.skipped.append(&mut filter.take_skipped());
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) if error_depth(&e) == Some(0) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to walk {}", self.root.display()))
+                }
+                Err(e) => {
+                    walked.skipped.push(skipped_error(&self.root, e));
+                    continue;
+                }
+            };
+            if entry.file_type().is_some_and(|t| t.is_dir()) {
+                continue;
+            }
+            let path = entry.into_path();
+            debug!("File Path: {:?}", path);
+            match self.check_file(&path) {
+                Some(reason) => walked.skipped.push(SkippedPath { path, reason }),
+                None => walked.files.push(path),
+            }
+        }
+        walked.skipped.append(&mut filter.take_skipped());
+        Ok(walked)
+    }
+}
+
+/// EntryFilter prunes the `.git` directories, the excluded directories and the paths matched
+/// by the ignore files during a walk, the pruned paths are kept with their reason
+struct EntryFilter {
+    root: PathBuf,
+    exclude: Vec<Pattern>,
+    respect_ignore_files: bool,
+    /// Excludes file of the git config e.g. `~/.config/git/ignore`
+    global: Gitignore,
+    /// Absolute path of the walked directory, the parent ignore rules are matched against it
+    absolute_root: PathBuf,
+    /// Ignore rules of the directories above the walked one up to the repository root,
+    /// closest first
+    parents: Vec<Gitignore>,
+    /// Ignore rules of every walked directory, None when it has no ignore files
+    ignores: Mutex<HashMap<PathBuf, Option<Gitignore>>>,
+    skipped: Mutex<Vec<SkippedPath>>,
+}
+
+impl EntryFilter {
+    fn new(walker: &Walker) -> Self {
+        let global = if walker.respect_ignore_files {
+            let (global, error) = GitignoreBuilder::new(&walker.root).build_global();
+            if let Some(e) = error {
+                debug!("Failed to read the global gitignore: {}", e);
+            }
+            global
+        } else {
+            Gitignore::empty()
+        };
+        let absolute_root = fs::canonicalize(&walker.root).unwrap_or_else(|_| walker.root.clone());
+        let parents = if walker.respect_ignore_files {
+            parent_ignore_files(&absolute_root)
+        } else {
+            Vec::new()
+        };
+        EntryFilter {
+            root: walker.root.clone(),
+            exclude: walker.exclude.clone(),
+            respect_ignore_files: walker.respect_ignore_files,
+            global,
+            absolute_root,
+            parents,
+            ignores: Mutex::new(HashMap::new()),
+            skipped: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Whether the walk enters the entry, a pruned entry is recorded as skipped
+    fn keep(&self, entry: &DirEntry) -> bool {
+        let path = entry.path();
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        match self.check(path, is_dir) {
+            Some(reason) => {
+                self.skipped
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(SkippedPath {
+                        path: path.to_path_buf(),
+                        reason,
+                    });
+                false
+            }
+            None => true,
+        }
+    }
+
+    fn take_skipped(&self) -> Vec<SkippedPath> {
+        std::mem::take(&mut *self.skipped.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn check(&self, path: &Path, is_dir: bool) -> Option<SkipReason> {
+        if is_dir && path.file_name().is_some_and(|name| name == ".git") {
+            return Some(SkipReason::GitDirectory);
+        }
+        let relative_path = path.strip_prefix(&self.root).unwrap_or(path);
+        if is_dir && self.exclude.iter().any(|p| p.matches_path(relative_path)) {
+            return Some(SkipReason::Excluded);
+        }
+        if !self.respect_ignore_files {
+            return None;
+        }
+
+        // the ignore file closest to the path decides, the global gitignore comes last
+        let mut ignores = self.ignores.lock().unwrap_or_else(|e| e.into_inner());
+        let dirs = path
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root));
+        for dir in dirs {
+            let gitignore = ignores
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| ignore_files(dir));
+            let Some(gitignore) = gitignore else {
+                continue;
+            };
+            let matched = gitignore.matched(path, is_dir);
+            if matched.is_ignore() {
+                return Some(SkipReason::Ignored);
+            }
+            if matched.is_whitelist() {
+                return None;
+            }
+        }
+        let absolute_path = self.absolute_root.join(relative_path);
+        for gitignore in &self.parents {
+            let matched = gitignore.matched(&absolute_path, is_dir);
+            if matched.is_ignore() {
+                return Some(SkipReason::Ignored);
+            }
+            if matched.is_whitelist() {
+                return None;
+            }
+        }
+        self.global
+            .matched(path, is_dir)
+            .is_ignore()
+            .then_some(SkipReason::Ignored)
+    }
+}
+
+/// Ignore rules of the directories above a walked directory inside a git repository, up to the
+/// directory holding `.git`, so loading `repo/src` honors `repo/.gitignore`
+fn parent_ignore_files(absolute_root: &Path) -> Vec<Gitignore> {
+    let Some(repository) = absolute_root
+        .ancestors()
+        .find(|dir| dir.join(".git").exists())
+    else {
+        return Vec::new();
+    };
+    absolute_root
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(repository))
+        .filter_map(ignore_files)
+        .collect()
+}
+
+/// Ignore rules of a directory, None when it has no ignore files
+fn ignore_files(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    // later files win, the repository excludes come before the ignore files
+    let git_exclude = dir.join(".git").join("info").join("exclude");
+    let paths = std::iter::once(git_exclude).chain(IGNORE_FILES.iter().map(|name| dir.join(name)));
+    for path in paths {
+        if path.is_file() {
+            found = true;
+            if let Some(e) = builder.add(&path) {
+                debug!("Failed to read {}: {}", path.display(), e);
+            }
+        }
+    }
+    if !found {
+        return None;
+    }
+    match builder.build() {
+        Ok(gitignore) => Some(gitignore),
+        Err(e) => {
+            debug!("Failed to build ignore rules of {}: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+/// Depth of the path a walk error is about
+fn error_depth(error: &ignore::Error) -> Option<usize> {
+    match error {
+        ignore::Error::WithDepth { depth, .. } => Some(*depth),
+        ignore::Error::WithPath { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_depth(err)
+        }
+        _ => None,
+    }
+}
+
+/// Skipped path of a walk error, a directory reached again through a symlink is a loop
+fn skipped_error(root: &Path, error: ignore::Error) -> SkippedPath {
+    fn path_of(error: &ignore::Error) -> Option<&Path> {
+        match error {
+            ignore::Error::WithPath { path, .. } => Some(path),
+            ignore::Error::Loop { child, .. } => Some(child),
+            ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+                path_of(err)
+            }
+            _ => None,
+        }
+    }
+    fn is_loop(error: &ignore::Error) -> bool {
+        match error {
+            ignore::Error::Loop { .. } => true,
+            ignore::Error::WithPath { err, .. }
+            | ignore::Error::WithDepth { err, .. }
+            | ignore::Error::WithLineNumber { err, .. } => is_loop(err),
+            _ => false,
+        }
+    }
+
+    let path = path_of(&error).unwrap_or(root).to_path_buf();
+    let reason = if is_loop(&error) {
+        SkipReason::SymlinkLoop
+    } else {
+        SkipReason::Unreadable(error.to_string())
+    };
+    SkippedPath { path, reason }
+}
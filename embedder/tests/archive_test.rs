@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests {
+    use embedder::archive::ArchiveFormat;
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{load_codebase_into_chunks, load_files, LoadedFiles, Sizer};
+    use embedder::walker::{SkipReason, WalkOptions};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    const MAIN_RS: &str = "fn main() {\n    println!(\"hello\");\n}\n";
+    const DRIVER_LOG: &str =
+        "2024-05-01 12:00:01,500 ERROR Task 3 failed\njava.lang.IllegalStateException: boom\n";
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rag_archive_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) -> anyhow::Result<()> {
+        let mut zip = ZipWriter::new(std::fs::File::create(path)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, content) in entries {
+            zip.start_file(*name, options)?;
+            zip.write_all(content)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    fn tar_bytes(entries: &[(&str, &[u8])]) -> anyhow::Result<Vec<u8>> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (name, content) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *content)?;
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    fn paths(loaded: &LoadedFiles) -> Vec<String> {
+        let mut paths: Vec<String> = loaded
+            .chunks
+            .iter()
+            .filter_map(|c| c.metadata.path.clone())
+            .collect();
+        paths.dedup();
+        paths
+    }
+
+    fn skipped(loaded: &LoadedFiles, path: &Path) -> Option<SkipReason> {
+        loaded
+            .skipped
+            .iter()
+            .find(|s| s.path == path)
+            .map(|s| s.reason.clone())
+    }
+
+    #[test]
+    fn test_archive_format_from_path() {
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("bundle.ZIP")),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("logs.tar")),
+            Some(ArchiveFormat::Tar)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("logs.tar.gz")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("logs.tgz")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(ArchiveFormat::from_path(Path::new("main.gz")), None);
+    }
+
+    #[tokio::test]
+    async fn test_load_zip_entries() -> anyhow::Result<()> {
+        let dir = temp_dir("zip");
+        let archive = dir.join("bundle.zip");
+        write_zip(
+            &archive,
+            &[
+                ("src/main.rs", MAIN_RS.as_bytes()),
+                ("./logs/driver.log", DRIVER_LOG.as_bytes()),
+                ("image.png", &[0x89, b'P', b'N', b'G', 0, 0, 0, 0]),
+            ],
+        )?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(500, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(archive.to_str().unwrap(), &policy).await?;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].metadata.path.as_deref(),
+            Some("bundle.zip!/src/main.rs")
+        );
+        assert_eq!(chunks[0].metadata.language.as_deref(), Some("rust"));
+        assert_eq!(chunks[0].metadata.start_line, Some(1));
+        assert_eq!(
+            chunks[0].get_file_path(),
+            &dir.join("bundle.zip!/src/main.rs")
+        );
+        assert_eq!(
+            chunks[1].metadata.path.as_deref(),
+            Some("bundle.zip!/logs/driver.log")
+        );
+        assert_eq!(chunks[1].metadata.log_level.as_deref(), Some("ERROR"));
+
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &WalkOptions::default()).await?;
+        assert_eq!(
+            skipped(&loaded, &dir.join("bundle.zip!/image.png")),
+            Some(SkipReason::Unsupported)
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_tar_entries_with_globs() -> anyhow::Result<()> {
+        let dir = temp_dir("tar");
+        let entries: [(&str, &[u8]); 2] = [
+            ("app/src/main.rs", MAIN_RS.as_bytes()),
+            ("app/logs/driver.log", DRIVER_LOG.as_bytes()),
+        ];
+        std::fs::write(dir.join("app.tar"), tar_bytes(&entries)?)?;
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&tar_bytes(&entries)?)?;
+        std::fs::write(dir.join("app.tar.gz"), gz.finish()?)?;
+        std::fs::write(dir.join("lib.rs"), MAIN_RS)?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(500, Sizer::Characters));
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &WalkOptions::default()).await?;
+        assert_eq!(
+            paths(&loaded),
+            vec![
+                "app.tar!/app/src/main.rs",
+                "app.tar!/app/logs/driver.log",
+                "app.tar.gz!/app/src/main.rs",
+                "app.tar.gz!/app/logs/driver.log",
+                "lib.rs",
+            ]
+        );
+
+        // include globs are matched against the paths inside the archives
+        let options = WalkOptions {
+            include: vec!["**/*.rs".to_string()],
+            exclude: vec!["app.tar".to_string()],
+            ..WalkOptions::default()
+        };
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &options).await?;
+        assert_eq!(
+            paths(&loaded),
+            vec!["app.tar.gz!/app/src/main.rs", "lib.rs"]
+        );
+        assert_eq!(
+            skipped(&loaded, &dir.join("app.tar.gz!/app/logs/driver.log")),
+            Some(SkipReason::NotIncluded)
+        );
+        assert_eq!(
+            skipped(&loaded, &dir.join("app.tar")),
+            Some(SkipReason::Excluded)
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_archive_limits() -> anyhow::Result<()> {
+        let dir = temp_dir("limits");
+        let zeros = vec![b'0'; 4 * 1024 * 1024];
+        write_zip(
+            &dir.join("bomb.zip"),
+            &[("a.rs", MAIN_RS.as_bytes()), ("zeros.txt", &zeros)],
+        )?;
+        let module = MAIN_RS.repeat(15);
+        write_zip(
+            &dir.join("many.zip"),
+            &[
+                ("a.rs", module.as_bytes()),
+                ("b.rs", module.as_bytes()),
+                ("c.rs", module.as_bytes()),
+            ],
+        )?;
+        std::fs::write(dir.join("broken.tar.gz"), b"not a gzip stream")?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(500, Sizer::Characters));
+        let options = WalkOptions {
+            max_file_size: 0,
+            max_archive_entries: 2,
+            ..WalkOptions::default()
+        };
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &options).await?;
+        // entries read before a limit are kept
+        assert_eq!(
+            paths(&loaded),
+            vec!["bomb.zip!/a.rs", "many.zip!/a.rs", "many.zip!/b.rs"]
+        );
+        assert_eq!(
+            skipped(&loaded, &dir.join("bomb.zip!/zeros.txt")),
+            Some(SkipReason::ArchiveLimit(
+                "compression ratio above 100".to_string()
+            ))
+        );
+        assert_eq!(
+            skipped(&loaded, &dir.join("many.zip")),
+            Some(SkipReason::ArchiveLimit("more than 2 entries".to_string()))
+        );
+        assert!(matches!(
+            skipped(&loaded, &dir.join("broken.tar.gz")),
+            Some(SkipReason::Unreadable(_))
+        ));
+
+        // archives on disk above the size limit aren't opened
+        let options = WalkOptions {
+            max_file_size: 0,
+            max_archive_size: 1000,
+            ..WalkOptions::default()
+        };
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &options).await?;
+        assert!(matches!(
+            skipped(&loaded, &dir.join("bomb.zip")),
+            Some(SkipReason::TooLarge(_))
+        ));
+        assert_eq!(paths(&loaded), vec!["many.zip!/a.rs"]);
+        assert_eq!(
+            skipped(&loaded, &dir.join("many.zip")),
+            Some(SkipReason::ArchiveLimit(
+                "expands beyond 1000 bytes".to_string()
+            ))
+        );
+
+        let options = WalkOptions {
+            max_file_size: 100,
+            ..WalkOptions::default()
+        };
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &options).await?;
+        assert_eq!(
+            skipped(&loaded, &dir.join("many.zip!/b.rs")),
+            Some(SkipReason::TooLarge(module.len() as u64))
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
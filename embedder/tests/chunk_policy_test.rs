@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use configs::settings::ChunkRule;
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec, Splitter};
+    use embedder::file_loader::{load_codebase_into_chunks, Language, Sizer};
+    use std::path::{Path, PathBuf};
+
+    fn rule(language: Option<&str>, glob: Option<&str>) -> ChunkRule {
+        ChunkRule {
+            language: language.map(|l| l.to_string()),
+            glob: glob.map(|g| g.to_string()),
+            ..ChunkRule::default()
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rag_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_spec_for() -> anyhow::Result<()> {
+        let rules = vec![
+            ChunkRule {
+                size: Some(512),
+                overlap: Some(32),
+                ..rule(Some("rust"), None)
+            },
+            ChunkRule {
+                splitter: Some("markdown".to_string()),
+                trim: Some(false),
+                ..rule(None, Some("docs/**/*.md"))
+            },
+            ChunkRule {
+                size: Some(64),
+                ..rule(Some("py"), Some("tests/*"))
+            },
+        ];
+        let policy = ChunkPolicy::with_rules(ChunkSpec::new(2048, Sizer::Characters), &rules)?;
+
+        let spec = policy.spec_for(Path::new("src/main.rs"), Language::Rust);
+        assert_eq!(spec.size, 512);
+        assert_eq!(spec.overlap(), 32);
+        assert_eq!(spec.splitter, None);
+
+        let spec = policy.spec_for(Path::new("docs/guide/intro.md"), Language::UNKNOWN);
+        assert_eq!(spec.size, 2048);
+        assert_eq!(spec.splitter, Some(Splitter::Markdown));
+        assert!(!spec.trim);
+
+        // language and glob both have to match
+        let spec = policy.spec_for(Path::new("tests/test_app.py"), Language::Python);
+        assert_eq!(spec.size, 64);
+        let spec = policy.spec_for(Path::new("app/main.py"), Language::Python);
+        assert_eq!(spec.size, 2048);
+        // the default overlap is capped at a quarter of the chunk
+        assert_eq!(spec.overlap(), 256);
+        assert_eq!(ChunkSpec::new(100, Sizer::Characters).overlap(), 25);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let default = || ChunkSpec::new(2048, Sizer::Characters);
+        assert!(ChunkPolicy::with_rules(default(), &[rule(None, None)]).is_err());
+        assert!(ChunkPolicy::with_rules(default(), &[rule(Some("cobol"), None)]).is_err());
+        assert!(ChunkPolicy::with_rules(default(), &[rule(None, Some("[docs"))]).is_err());
+        let splitter = ChunkRule {
+            splitter: Some("sentences".to_string()),
+            ..rule(Some("rust"), None)
+        };
+        assert!(ChunkPolicy::with_rules(default(), &[splitter]).is_err());
+
+        let spec = ChunkSpec {
+            overlap: Some(100),
+            ..ChunkSpec::new(100, Sizer::Characters)
+        };
+        assert!(spec.chunk_config().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_with_policy() -> anyhow::Result<()> {
+        let dir = temp_dir("chunk_policy_load");
+        std::fs::create_dir_all(dir.join("docs"))?;
+        let section = "Some words about the project that fill a paragraph.\n\n";
+        std::fs::write(
+            dir.join("docs/guide.md"),
+            format!(
+                "# Guide\n\n{}## Usage\n\n{}",
+                section.repeat(4),
+                section.repeat(4)
+            ),
+        )?;
+        std::fs::write(
+            dir.join("notes.rst"),
+            "Notes\n=====\n\nnot matched by the glob",
+        )?;
+        std::fs::write(
+            dir.join("lib.rs"),
+            "fn one() -> u32 {\n    1\n}\n\nfn two() -> u32 {\n    2\n}\n".repeat(8),
+        )?;
+
+        let rules = vec![
+            ChunkRule {
+                splitter: Some("markdown".to_string()),
+                size: Some(300),
+                ..rule(None, Some("docs/*.md"))
+            },
+            ChunkRule {
+                size: Some(100),
+                ..rule(Some("rust"), None)
+            },
+        ];
+        let policy = ChunkPolicy::with_rules(ChunkSpec::new(2048, Sizer::Characters), &rules)?;
+        let chunks = load_codebase_into_chunks(dir.to_str().unwrap(), &policy).await?;
+
+        let guide: Vec<_> = chunks
+            .iter()
+            .filter(|c| c.get_file_name() == "guide.md")
+            .collect();
+        assert!(guide.len() > 1);
+        assert!(guide.iter().all(|c| c.get_content().chars().count() <= 300));
+        assert!(guide[0].get_content().starts_with("# Guide"));
+
+        let code: Vec<_> = chunks
+            .iter()
+            .filter(|c| c.get_file_name() == "lib.rs")
+            .collect();
+        assert!(code.len() > 1);
+        assert!(code.iter().all(|c| c.get_content().chars().count() <= 100));
+
+        // a file outside the glob has no splitter and no supported extension
+        assert!(chunks.iter().all(|c| c.get_file_name() != "notes.rst"));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::detect::{
+        detect_bytes, detect_file, detect_language, detect_path, Detection, DetectionMethod,
+        DETECT_BYTES, MIN_CONFIDENCE,
+    };
+    use embedder::file_loader::{load_files, Language, Sizer};
+    use embedder::walker::{SkipReason, WalkOptions};
+    use std::path::Path;
+
+    fn detect(path: &str, content: &str) -> (Language, DetectionMethod) {
+        let detection = detect_language(Path::new(path), content);
+        (detection.language, detection.method)
+    }
+
+    #[test]
+    fn test_detect_path() {
+        let jenkinsfile = detect_path(Path::new("ci/Jenkinsfile"));
+        assert_eq!(jenkinsfile.language, Language::Groovy);
+        assert_eq!(jenkinsfile.method, DetectionMethod::FileName);
+        assert_eq!(jenkinsfile.confidence, 1.0);
+
+        let template = detect_path(Path::new("build.rs.in"));
+        assert_eq!(template.language, Language::Rust);
+        assert_eq!(template.method, DetectionMethod::Extension);
+        assert!(template.confidence < detect_path(Path::new("build.rs")).confidence);
+
+        assert_eq!(detect_path(Path::new(".bashrc")).language, Language::Bash);
+        assert_eq!(
+            detect_path(Path::new("Dockerfile.release")).language,
+            Language::Dockerfile
+        );
+        assert_eq!(detect_path(Path::new("notes.in")), Detection::undetected());
+        assert_eq!(detect_path(Path::new("LICENSE")), Detection::undetected());
+    }
+
+    #[test]
+    fn test_detect_shebang_and_modeline() {
+        let cases = [
+            ("#!/usr/bin/env python3\nprint('hi')\n", Language::Python),
+            ("#!/usr/bin/env -S python3.12 -u\n", Language::Python),
+            ("#!/bin/bash\nset -e\n", Language::Bash),
+            ("#!/usr/bin/env node\n", Language::JavaScript),
+            ("#!/usr/bin/env ruby -w\n", Language::Ruby),
+        ];
+        for (content, language) in cases {
+            assert_eq!(
+                detect("bin/tool", content),
+                (language, DetectionMethod::Shebang)
+            );
+        }
+        assert_eq!(detect("bin/tool", "#!/usr/bin/perl\n").0, Language::UNKNOWN);
+
+        let cases = [
+            ("# vim: set ft=python:\nx = 1\n", Language::Python),
+            ("x\ny\n// vi: syntax=rust\n", Language::Rust),
+            ("# -*- mode: ruby; coding: utf-8 -*-\n", Language::Ruby),
+            ("/* -*- c++ -*- */\n", Language::Cpp),
+            ("# vim: ts=4 filetype=sh\n", Language::Bash),
+        ];
+        for (content, language) in cases {
+            assert_eq!(
+                detect("conf/tool", content),
+                (language, DetectionMethod::Modeline)
+            );
+        }
+        // the name and extension win over the content
+        assert_eq!(
+            detect("tool.py", "#!/bin/bash\n"),
+            (Language::Python, DetectionMethod::Extension)
+        );
+    }
+
+    #[test]
+    fn test_detect_content() {
+        let rust = "use std::fs;\n\npub struct Loader;\n\nimpl Loader {\n    pub fn load() {}\n}\n";
+        let detection = detect_language(Path::new("loader"), rust);
+        assert_eq!(detection.language, Language::Rust);
+        assert_eq!(detection.method, DetectionMethod::Content);
+        assert!(detection.confidence >= MIN_CONFIDENCE && detection.confidence < 0.8);
+
+        let dockerfile = "FROM rust:1.85\nWORKDIR /app\nCOPY . .\nRUN cargo build\n";
+        assert_eq!(detect("image", dockerfile).0, Language::Dockerfile);
+        let sql =
+            "create table jobs (id int);\ninsert into jobs values (1);\nselect * from jobs;\n";
+        assert_eq!(detect("schema", sql).0, Language::Sql);
+        assert_eq!(detect("data", "{\"a\": [1, 2]}").0, Language::Json);
+        assert_eq!(detect("data", "{\"a\": [1, 2").0, Language::UNKNOWN);
+
+        // prose and words that only start like a signature are not code
+        assert_eq!(
+            detect("NOTES", "end of the first part\nthen the second\nfind it\n").0,
+            Language::UNKNOWN
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_extensionless_files() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("rag_detect_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(
+            dir.join("deploy"),
+            "#!/usr/bin/env bash\nset -e\necho deploying\n",
+        )?;
+        std::fs::write(dir.join("NOTES"), "some notes without a language\n")?;
+
+        assert_eq!(detect_file(&dir.join("deploy"))?.language, Language::Bash);
+
+        // only the heads of long files are parsed and their modelines may be at the end
+        let rows: Vec<String> = (0..1000).map(|i| format!("{{\"id\": {}}}", i)).collect();
+        let json = format!("[{}]", rows.join(", "));
+        assert!(json.len() as u64 > DETECT_BYTES);
+        std::fs::write(dir.join("rows"), &json)?;
+        assert_eq!(detect_file(&dir.join("rows"))?.language, Language::Json);
+        assert_eq!(
+            detect_bytes(Path::new("rows"), json.as_bytes()).language,
+            Language::Json
+        );
+        let script = format!("{}# vim: set ft=python:\n", "x = 1\n".repeat(2000));
+        std::fs::write(dir.join("long"), &script)?;
+        let detection = detect_file(&dir.join("long"))?;
+        assert_eq!(
+            (detection.language, detection.method),
+            (Language::Python, DetectionMethod::Modeline)
+        );
+        std::fs::remove_file(dir.join("rows"))?;
+        std::fs::remove_file(dir.join("long"))?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(200, Sizer::Characters));
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &WalkOptions::default()).await?;
+        assert_eq!(loaded.chunks.len(), 1);
+        assert_eq!(loaded.chunks[0].metadata.path.as_deref(), Some("deploy"));
+        assert_eq!(loaded.chunks[0].metadata.language.as_deref(), Some("bash"));
+        assert!(loaded
+            .skipped
+            .iter()
+            .any(|s| s.path.ends_with("NOTES") && s.reason == SkipReason::Unsupported));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
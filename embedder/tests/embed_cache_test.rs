@@ -0,0 +1,199 @@
+#[cfg(test)]
+mod tests {
+    use embedder::embed_cache::{self, content_hash, CacheKey, EmbedCache};
+    use embedder::embed_config::EmbedRequest;
+    use std::path::PathBuf;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn temp_cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rag_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_cache_round_trip() -> anyhow::Result<()> {
+        let dir = temp_cache_dir("embed_cache_round_trip");
+        let key = CacheKey::new("Ollama", "nomic-embed-text", None);
+        let hashes = [content_hash("fn main() {}"), content_hash("hello")];
+
+        let cache = EmbedCache::open(&dir)?;
+        assert_eq!(cache.get_many(&key, &hashes)?, vec![None, None]);
+        cache.put_many(&key, vec![(hashes[0], vec![0.5, 1.5])])?;
+        assert_eq!(
+            cache.get_many(&key, &hashes)?,
+            vec![Some(vec![0.5, 1.5]), None]
+        );
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 3);
+        cache.save_stats()?;
+
+        // entries survive a reopen and other models or sizes do not share them
+        let cache = EmbedCache::open(&dir)?;
+        assert_eq!(
+            cache.get_many(&key, &hashes[..1])?,
+            vec![Some(vec![0.5, 1.5])]
+        );
+        let other = CacheKey::new("ollama", "nomic-embed-text", Some(256));
+        assert_eq!(cache.get_many(&other, &hashes[..1])?, vec![None]);
+
+        let summaries = embed_cache::summarize(&dir)?;
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].key, key);
+        assert_eq!(summaries[0].entries, 1);
+        assert_eq!(summaries[0].stats.hits, 1);
+        assert_eq!(summaries[0].stats.misses, 3);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_models() -> anyhow::Result<()> {
+        let dir = temp_cache_dir("embed_cache_prune");
+        let old = CacheKey::new("ollama", "mxbai-embed-large", None);
+        let current = CacheKey::new("ollama", "nomic-embed-text", None);
+
+        let cache = EmbedCache::open(&dir)?;
+        cache.put_many(&old, vec![(content_hash("a"), vec![1.0])])?;
+        cache.put_many(&current, vec![(content_hash("a"), vec![2.0])])?;
+
+        let removed = embed_cache::prune(&dir, &["mxbai-embed-large".to_string()])?;
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].key, old);
+
+        let summaries = embed_cache::summarize(&dir)?;
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].key, current);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_truncated_record_is_dropped() -> anyhow::Result<()> {
+        let dir = temp_cache_dir("embed_cache_truncated");
+        let key = CacheKey::new("ollama", "qwen2:7b", None);
+        let cache = EmbedCache::open(&dir)?;
+        cache.put_many(&key, vec![(content_hash("a"), vec![1.0, 2.0])])?;
+
+        // simulate a load interrupted in the middle of a write
+        let path = std::fs::read_dir(&dir)?
+            .map(|e| e.unwrap().path())
+            .find(|p| p.extension().is_some_and(|e| e == "embcache"))
+            .unwrap();
+        let mut data = std::fs::read(&path)?;
+        data.extend_from_slice(&[7u8; 20]);
+        std::fs::write(&path, data)?;
+
+        let cache = EmbedCache::open(&dir)?;
+        let hashes = [content_hash("a"), content_hash("b")];
+        assert_eq!(cache.get_many(&key, &hashes)?[0], Some(vec![1.0, 2.0]));
+        cache.put_many(&key, vec![(hashes[1], vec![3.0, 4.0])])?;
+
+        let cache = EmbedCache::open(&dir)?;
+        assert_eq!(
+            cache.get_many(&key, &hashes)?,
+            vec![Some(vec![1.0, 2.0]), Some(vec![3.0, 4.0])]
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_sanitized_names_do_not_share_a_file() -> anyhow::Result<()> {
+        let dir = temp_cache_dir("embed_cache_names");
+        let keys = [
+            CacheKey::new("ollama", "qwen2:7b", None),
+            CacheKey::new("ollama", "qwen2_7b", None),
+            CacheKey::new("ollama", "BAAI/bge-m3", None),
+            CacheKey::new("ollama", "BAAI_bge-m3", None),
+        ];
+        let hash = content_hash("a");
+
+        let cache = EmbedCache::open(&dir)?;
+        for (i, key) in keys.iter().enumerate() {
+            cache.put_many(key, vec![(hash, vec![i as f32])])?;
+        }
+        let cache = EmbedCache::open(&dir)?;
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(cache.get_many(key, &[hash])?, vec![Some(vec![i as f32])]);
+        }
+        assert_eq!(embed_cache::summarize(&dir)?.len(), 4);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_file_of_another_key_is_rejected() -> anyhow::Result<()> {
+        let dir = temp_cache_dir("embed_cache_header");
+        let key = CacheKey::new("ollama", "nomic-embed-text", None);
+        let other = CacheKey::new("openai", "text-embedding-3-small", None);
+        let cache = EmbedCache::open(&dir)?;
+        cache.put_many(&other, vec![(content_hash("a"), vec![1.0])])?;
+        cache.put_many(&key, vec![(content_hash("a"), vec![2.0])])?;
+
+        // a file holding another key under this key's name is never read as this key
+        let path_of = |model: &str| {
+            std::fs::read_dir(&dir)
+                .unwrap()
+                .map(|e| e.unwrap().path())
+                .find(|p| p.to_string_lossy().contains(model))
+                .unwrap()
+        };
+        std::fs::rename(path_of("text-embedding"), path_of("nomic"))?;
+        let cache = EmbedCache::open(&dir)?;
+        assert!(cache.get_many(&key, &[content_hash("a")]).is_err());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cached_inputs_are_not_sent() -> anyhow::Result<()> {
+        let dir = temp_cache_dir("embed_cache_fetch");
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let api_url = format!("http://{}", listener.local_addr()?);
+        // the server answers a single request, the second fetch has to come from the cache
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await?;
+            let body = r#"{"model":"nomic-embed-text","embeddings":[[1.0],[2.0]]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await?;
+            anyhow::Ok(String::from_utf8_lossy(&buf[..n]).to_string())
+        });
+
+        let request = EmbedRequest::NewArcEmbedRequest(
+            "ollama",
+            &api_url,
+            "",
+            "nomic-embed-text",
+            &["a".to_string(), "b".to_string()],
+            &"main.rs".to_string(),
+            None,
+        );
+        request.write().await.set_dimensions(None);
+
+        let cache = EmbedCache::open(&dir)?;
+        let https_client = configs::HttpsClientBuilder::new().build()?;
+        let first = embedder::fetch_embedding(&request, &https_client, Some(&cache)).await?;
+        let second = embedder::fetch_embedding(&request, &https_client, Some(&cache)).await?;
+        assert_eq!(first.embeddings, vec![vec![1.0], vec![2.0]]);
+        assert_eq!(second.embeddings, first.embeddings);
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(cache.stats().misses, 2);
+        server.await??;
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
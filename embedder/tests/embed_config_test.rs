@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+    use embedder::embed_config::{EmbedRequest, EmbedResponse};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn request(provider: &str, api_url: &str) -> EmbedRequest {
+        let mut request = EmbedRequest::NewEmbedRequest(
+            provider,
+            api_url,
+            "sk-test",
+            "text-embedding-3-small",
+            vec!["fn main() {}", "hello"],
+            Some(0),
+        );
+        request.set_dimensions(None);
+        request
+    }
+
+    #[test]
+    fn test_embed_url() {
+        assert_eq!(
+            request("openai", "").get_embed_url(),
+            "https://api.openai.com/v1/embeddings"
+        );
+        assert_eq!(
+            request("OpenAI", "https://api.openai.com").get_embed_url(),
+            "https://api.openai.com/v1/embeddings"
+        );
+        // OpenAI compatible servers are usually configured with the `/v1` base url
+        assert_eq!(
+            request("openai", "http://localhost:8000/v1/").get_embed_url(),
+            "http://localhost:8000/v1/embeddings"
+        );
+        assert_eq!(
+            request("openai", "http://localhost:1234").get_embed_url(),
+            "http://localhost:1234/v1/embeddings"
+        );
+        assert_eq!(
+            request("ollama", "http://localhost:11434").get_embed_url(),
+            "http://localhost:11434/api/embed"
+        );
+    }
+
+    #[test]
+    fn test_request_body() -> anyhow::Result<()> {
+        let mut request = request("openai", "");
+        let body: serde_json::Value = serde_json::from_str(&request.to_json()?)?;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "model": "text-embedding-3-small",
+                "input": ["fn main() {}", "hello"],
+            })
+        );
+
+        request.set_dimensions(Some(256));
+        let body: serde_json::Value = serde_json::from_str(&request.to_json()?)?;
+        assert_eq!(body["dimensions"], 256);
+        assert!(!request.to_json()?.contains("sk-test"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_openai_response() -> anyhow::Result<()> {
+        let json = r#"{
+            "object": "list",
+            "data": [
+                {"object": "embedding", "index": 1, "embedding": [0.3, 0.4]},
+                {"object": "embedding", "index": 0, "embedding": [0.1, 0.2]}
+            ],
+            "model": "text-embedding-3-small",
+            "usage": {"prompt_tokens": 5, "total_tokens": 5}
+        }"#;
+        let response = EmbedResponse::from_provider_json("openai", json)?;
+        assert_eq!(response.model, "text-embedding-3-small");
+        assert_eq!(response.embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ollama_response() -> anyhow::Result<()> {
+        let json = r#"{"model": "nomic-embed-text", "embeddings": [[0.1, 0.2]]}"#;
+        let response = EmbedResponse::from_provider_json("ollama", json)?;
+        assert_eq!(response.model, "nomic-embed-text");
+        assert_eq!(response.embeddings, vec![vec![0.1, 0.2]]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_error_response() {
+        let json = r#"{"error": {"message": "Invalid API key", "type": "invalid_request_error"}}"#;
+        let error = EmbedResponse::from_provider_json("openai", json).unwrap_err();
+        assert!(error.to_string().contains("Invalid API key"));
+
+        let json = r#"{"error": "model \"missing\" not found"}"#;
+        let error = EmbedResponse::from_provider_json("ollama", json).unwrap_err();
+        assert!(error.to_string().contains("not found"));
+    }
+
+    #[tokio::test]
+    async fn test_openai_compatible_server() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let api_url = format!("http://{}/v1", listener.local_addr()?);
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await?;
+            let body = r#"{"data":[{"index":0,"embedding":[1.0,0.0]},{"index":1,"embedding":[0.0,1.0]}],"model":"local"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await?;
+            anyhow::Ok(String::from_utf8_lossy(&buf[..n]).to_string())
+        });
+
+        let https_client = configs::HttpsClientBuilder::new().build()?;
+        let response =
+            embedder::create_embed_request(&request("openai", &api_url), &https_client).await?;
+        assert_eq!(response.embeddings.len(), 2);
+        assert_eq!(response.embeddings[1], vec![0.0, 1.0]);
+
+        let request = server.await??;
+        assert!(request.starts_with("POST /v1/embeddings HTTP/1.1"));
+        assert!(request.contains("authorization: Bearer sk-test"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_fetch_embedding_batch() -> anyhow::Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let api_url = format!("http://{}", listener.local_addr()?);
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await?;
+            let mut buf = vec![0u8; 4096];
+            let n = socket.read(&mut buf).await?;
+            let body = r#"{"model":"nomic-embed-text","embeddings":[[1.0],[2.0],[3.0]]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await?;
+            anyhow::Ok(String::from_utf8_lossy(&buf[..n]).to_string())
+        });
+
+        let requests: Vec<_> = [
+            vec!["a".to_string(), "b".to_string()],
+            vec!["c".to_string()],
+        ]
+        .iter()
+        .map(|input| {
+            EmbedRequest::NewArcEmbedRequest(
+                "ollama",
+                &api_url,
+                "",
+                "nomic-embed-text",
+                input,
+                &"main.rs".to_string(),
+                None,
+            )
+        })
+        .collect();
+
+        let https_client = configs::HttpsClientBuilder::new().build()?;
+        let responses = embedder::fetch_embedding_batch(&requests, &https_client, None).await?;
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].embeddings, vec![vec![1.0], vec![2.0]]);
+        assert_eq!(responses[1].embeddings, vec![vec![3.0]]);
+
+        // one call carries the inputs of every request in order
+        let request = server.await??;
+        assert!(request.contains(r#""input":["a","b","c"]"#));
+        Ok(())
+    }
+}
@@ -1,10 +1,15 @@
 #[cfg(test)]
 mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
     use embedder::file_loader::chunk_embed_request;
     use embedder::file_loader::is_supported_file;
+    use embedder::file_loader::EmbedMode;
     use embedder::file_loader::FileChunk;
     use embedder::file_loader::Language;
+    use embedder::file_loader::{load_codebase_into_chunks, load_files, Sizer};
+    use embedder::walker::WalkOptions;
     use std::path::PathBuf;
+    use text_splitter::ChunkSizer;
 
     #[test]
     fn test_language_from_str() {
@@ -39,17 +44,39 @@ mod tests {
         let api_key = "test_key";
         let model = "test_model";
 
-        let embed_request = chunk_embed_request(&chunk, provider, api_url, api_key, model);
+        let embed_request =
+            chunk_embed_request(&chunk, provider, api_url, api_key, model, EmbedMode::Chunk);
 
         assert_eq!(embed_request.provider, provider);
         assert_eq!(embed_request.api_url, api_url);
-        assert_eq!(embed_request.api_key, api_key);
+        assert_eq!(embed_request.api_key.expose(), api_key);
         assert_eq!(embed_request.model, model);
-        assert_eq!(embed_request.input, chunk.content);
+        assert_eq!(embed_request.input, vec![chunk.get_content()]);
         assert_eq!(embed_request.metadata, Some("test.py".to_string()));
         assert_eq!(embed_request.chunk_number, Some(chunk_number));
     }
 
+    #[test]
+    fn test_embed_mode() {
+        let chunk = FileChunk::new(
+            "fn main() {\n\n    println!(\"hi\");\n}".to_string(),
+            PathBuf::from("main.rs"),
+            3,
+        );
+
+        // a chunk is embedded as one vector with the full chunk text
+        let input = chunk.embed_input(EmbedMode::Chunk);
+        assert_eq!(input, vec![chunk.get_content()]);
+
+        // line mode skips the blank lines
+        let input = chunk.embed_input(EmbedMode::Line);
+        assert_eq!(input, vec!["fn main() {", "    println!(\"hi\");", "}"]);
+
+        assert_eq!(EmbedMode::parse_mode("LINE").unwrap(), EmbedMode::Line);
+        assert_eq!(EmbedMode::default(), EmbedMode::Chunk);
+        assert!(EmbedMode::parse_mode("file").is_err());
+    }
+
     #[test]
     fn test_is_supported_file() {
         let supported_path = std::path::Path::new("test.rs");
@@ -64,5 +91,188 @@ mod tests {
         assert!(!is_supported);
     }
 
+    #[test]
+    fn test_language_from_path() {
+        let cases = [
+            ("include/point.h", Language::Header),
+            ("lib/job.rb", Language::Ruby),
+            ("Gemfile", Language::Ruby),
+            ("scripts/build.sh", Language::Bash),
+            ("app/Main.kt", Language::Kotlin),
+            ("src/Program.cs", Language::CSharp),
+            ("db/schema.sql", Language::Sql),
+            ("Cargo.toml", Language::Toml),
+            (".github/workflows/ci.yml", Language::Yaml),
+            ("package.json", Language::Json),
+            ("docker/Dockerfile", Language::Dockerfile),
+            ("Dockerfile.dev", Language::Dockerfile),
+            ("Makefile", Language::Makefile),
+            ("rules.mk", Language::Makefile),
+            ("LICENSE", Language::UNKNOWN),
+        ];
+        for (path, language) in cases {
+            assert_eq!(Language::from_path(std::path::Path::new(path)), language);
+            // names read back into the same language
+            assert_eq!(Language::parse_name(language.name()), language);
+        }
+        assert_eq!(Language::parse_name("c#"), Language::CSharp);
+    }
+
+    #[test]
+    fn test_parse_sizer() -> anyhow::Result<()> {
+        assert!(matches!(
+            Sizer::parse_sizer("characters")?,
+            Sizer::Characters
+        ));
+        let sizer = Sizer::parse_sizer("tiktoken:cl100k_base")?;
+        assert_eq!(sizer.size("An apple a"), 3);
+        // models resolve to their encoding
+        let sizer = Sizer::parse_sizer("tiktoken:text-embedding-3-small")?;
+        assert_eq!(format!("{:?}", sizer), "tiktoken:text-embedding-3-small");
+
+        // a word level tokenizer.json counts whitespace separated words
+        let path = std::env::temp_dir().join(format!("rag_tokenizer_{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"version": "1.0", "truncation": null, "padding": null, "added_tokens": [],
+                "normalizer": null, "pre_tokenizer": {"type": "WhitespaceSplit"},
+                "post_processor": null, "decoder": null,
+                "model": {"type": "WordLevel", "vocab": {"[UNK]": 0, "fn": 1}, "unk_token": "[UNK]"}}"#,
+        )?;
+        let sizer = Sizer::parse_sizer(&format!("hf:{}", path.display()))?;
+        assert_eq!(sizer.size("fn main is four"), 4);
+        std::fs::remove_file(&path)?;
+
+        assert!(Sizer::parse_sizer("tiktoken:no-such-encoding").is_err());
+        assert!(Sizer::parse_sizer("hf:/no/such/tokenizer.json").is_err());
+        assert!(Sizer::parse_sizer("words").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_token_sized_chunks() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("rag_tokens_{}.txt", std::process::id()));
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(40);
+        std::fs::write(&path, &text)?;
+
+        let sizer = Sizer::parse_sizer("tiktoken:cl100k_base")?;
+        let chunks = load_codebase_into_chunks(
+            path.to_str().unwrap(),
+            &ChunkPolicy::new(ChunkSpec::new(32, sizer.clone())),
+        )
+        .await?;
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(sizer.size(&chunk.get_content()) <= 32);
+        }
+
+        // the same size in characters gives far more chunks
+        let characters = load_codebase_into_chunks(
+            path.to_str().unwrap(),
+            &ChunkPolicy::new(ChunkSpec::new(32, Sizer::Characters)),
+        )
+        .await?;
+        assert!(characters.len() > chunks.len());
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_parallel_load_is_deterministic() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("rag_parallel_{}", std::process::id()));
+        for i in 0..40 {
+            let sub_dir = dir.join(format!("mod_{}", i % 4));
+            std::fs::create_dir_all(&sub_dir)?;
+            let body = format!("fn f_{}() -> u32 {{\n    {}\n}}\n\n", i, i).repeat(10 + i);
+            std::fs::write(sub_dir.join(format!("file_{:02}.rs", i)), body)?;
+        }
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(120, Sizer::Characters));
+        let load = |parallelism: usize| {
+            let options = WalkOptions {
+                parallelism,
+                ..WalkOptions::default()
+            };
+            let policy = policy.clone();
+            let dir = dir.clone();
+            async move {
+                let loaded = load_files(dir.to_str().unwrap(), &policy, &options).await?;
+                Ok::<_, anyhow::Error>(
+                    loaded
+                        .chunks
+                        .iter()
+                        .map(|c| {
+                            (
+                                c.get_file_path().clone(),
+                                c.get_chunk_number(),
+                                c.get_content(),
+                            )
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            }
+        };
+
+        let sequential = load(1).await?;
+        assert!(sequential.len() > 40);
+        // files come in walk order, every file numbers its chunks from 0
+        assert!(sequential[0].0.ends_with("mod_0/file_00.rs"));
+        assert_eq!(sequential[0].1, 0);
+        for _ in 0..3 {
+            assert_eq!(load(8).await?, sequential);
+        }
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chunks_record_path_and_span() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("rag_span_{}", std::process::id()));
+        let content = "fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n";
+        for krate in ["app", "chat"] {
+            std::fs::create_dir_all(dir.join(krate).join("src"))?;
+            std::fs::write(dir.join(krate).join("src/lib.rs"), content)?;
+        }
+        std::fs::write(dir.join("notes.md"), "# Notes\n\nSome text\n")?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(20, Sizer::Characters));
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &WalkOptions::default()).await?;
+
+        let mut paths: Vec<_> = loaded
+            .chunks
+            .iter()
+            .map(|c| c.metadata.path.clone().unwrap())
+            .collect();
+        paths.dedup();
+        assert_eq!(paths, vec!["app/src/lib.rs", "chat/src/lib.rs", "notes.md"]);
+
+        for chunk in &loaded.chunks {
+            let metadata = &chunk.metadata;
+            let (start, end) = (metadata.byte_start.unwrap(), metadata.byte_end.unwrap());
+            let text = match metadata.path.as_deref() {
+                Some("notes.md") => "# Notes\n\nSome text\n",
+                _ => content,
+            };
+            assert_eq!(&text[start as usize..end as usize], chunk.get_content());
+            let first_line = text[..start as usize].matches('\n').count() as u32 + 1;
+            assert_eq!(metadata.start_line, Some(first_line));
+        }
+
+        let second = &loaded.chunks[1];
+        assert_eq!(second.metadata.language.as_deref(), Some("rust"));
+        assert_eq!(second.get_content(), "fn b() {\n    2\n}");
+        assert_eq!(
+            (second.metadata.start_line, second.metadata.end_line),
+            (Some(5), Some(7))
+        );
+        let notes = loaded.chunks.last().unwrap();
+        assert_eq!(notes.metadata.language.as_deref(), Some("markdown"));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
     // @TODO tests for other functions .
 }
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{is_supported_file, load_codebase_into_chunks, Language, Sizer};
+    use embedder::html::html_to_markdown;
+    use std::path::Path;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <title>Deploy   Guide</title>
+  <link rel="canonical" href="https://wiki.example.com/deploy">
+  <style>body { color: red; }</style>
+  <script>console.log("tracking");</script>
+</head>
+<body>
+  <nav><a href="/">Home</a> <a href="/docs">Docs</a></nav>
+  <main>
+    <h1>Deploy</h1>
+    <p>Run the <code>deploy</code> task
+       from the release branch.</p>
+    <h2>Rollback</h2>
+    <ul><li>Stop traffic</li><li>Restore the snapshot</li></ul>
+    <pre><code class="language-bash">./rollback.sh --env prod
+echo done</code></pre>
+  </main>
+  <script>window.analytics = {};</script>
+</body>
+</html>"#;
+
+    #[test]
+    fn test_html_extensions() {
+        for name in ["index.html", "page.htm"] {
+            assert_eq!(is_supported_file(Path::new(name)), (Language::Html, true));
+        }
+    }
+
+    #[test]
+    fn test_html_to_markdown() {
+        let page = html_to_markdown(PAGE);
+        assert_eq!(page.title.as_deref(), Some("Deploy Guide"));
+        assert_eq!(
+            page.canonical_url.as_deref(),
+            Some("https://wiki.example.com/deploy")
+        );
+        assert_eq!(
+            page.markdown,
+            "# Deploy\n\nRun the `deploy` task from the release branch.\n\n## Rollback\n\n\
+             - Stop traffic\n\n- Restore the snapshot\n\n\
+             ```bash\n./rollback.sh --env prod\necho done\n```"
+        );
+        for boilerplate in ["Home", "color: red", "tracking", "analytics"] {
+            assert!(!page.markdown.contains(boilerplate));
+        }
+    }
+
+    #[test]
+    fn test_html_without_metadata() {
+        let page = html_to_markdown("<p>Just text</p>");
+        assert_eq!(page.title, None);
+        assert_eq!(page.canonical_url, None);
+        assert_eq!(page.markdown, "Just text");
+    }
+
+    #[tokio::test]
+    async fn test_load_html_file() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("rag_page_{}.html", std::process::id()));
+        std::fs::write(&path, PAGE)?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(60, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(path.to_str().unwrap(), &policy).await?;
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| {
+            c.metadata.title.as_deref() == Some("Deploy Guide")
+                && c.metadata.url.as_deref() == Some("https://wiki.example.com/deploy")
+        }));
+
+        let code = chunks
+            .iter()
+            .find(|c| c.get_content().contains("rollback.sh"))
+            .unwrap();
+        assert_eq!(code.metadata.heading.as_deref(), Some("Deploy > Rollback"));
+        assert_eq!(code.metadata.code_languages, vec!["bash"]);
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,130 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{load_codebase_into_chunks, Sizer};
+    use embedder::key_path::split_key_paths;
+    use text_splitter::ChunkConfig;
+
+    const CARGO_TOML: &str = r#"# workspace manifest
+[workspace]
+members = ["app", "embedder"]
+
+[workspace.dependencies]
+anyhow = "1.0.97"
+tokio = { version = "1.44.1", features = ["full"] }
+lancedb = { version = "0.18.2", default-features = false, features = ["polars", "fp16kernels", "remote"] }
+"#;
+
+    fn key_paths(
+        grammar: tree_sitter_language::LanguageFn,
+        content: &str,
+        size: usize,
+    ) -> Vec<(String, Option<String>)> {
+        let chunk_config = ChunkConfig::new(size).with_sizer(Sizer::Characters);
+        split_key_paths(grammar, content, chunk_config)
+            .unwrap()
+            .into_iter()
+            .map(|(chunk, metadata)| (chunk, metadata.key_path))
+            .collect()
+    }
+
+    #[test]
+    fn test_toml_key_paths() {
+        let chunks = key_paths(tree_sitter_toml_ng::LANGUAGE, CARGO_TOML, 110);
+        let paths: Vec<_> = chunks.iter().map(|(_, p)| p.as_deref()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Some("workspace"),
+                Some("workspace.dependencies"),
+                Some("workspace.dependencies.lancedb")
+            ]
+        );
+        assert_eq!(
+            chunks[0].0,
+            "[workspace]\nmembers = [\"app\", \"embedder\"]"
+        );
+        assert!(chunks[1].0.starts_with("anyhow = "));
+        assert!(chunks[1].0.ends_with("features = [\"full\"] }"));
+        assert!(chunks[2].0.starts_with("lancedb = "));
+
+        // a file that fits is a single chunk with its comments
+        let chunks = key_paths(tree_sitter_toml_ng::LANGUAGE, CARGO_TOML, 1000);
+        assert_eq!(chunks, vec![(CARGO_TOML.trim().to_string(), None)]);
+    }
+
+    #[test]
+    fn test_yaml_and_json_key_paths() {
+        let yaml = "name: ci\njobs:\n  build:\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/checkout@v4\n      - run: cargo build --workspace --all-targets\n      - run: cargo test --workspace --all-features\n";
+        let paths: Vec<_> = key_paths(tree_sitter_yaml::LANGUAGE, yaml, 60)
+            .into_iter()
+            .map(|(_, p)| p.unwrap_or_default())
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                "name",
+                "jobs.build.runs-on",
+                "jobs.build.steps[0]",
+                "jobs.build.steps[1]",
+                "jobs.build.steps[2]"
+            ]
+        );
+
+        let json = r#"{"name": "app", "scripts": {"build": "tsc --build tsconfig.json", "test": "jest --coverage --runInBand"}}"#;
+        let chunks = key_paths(tree_sitter_json::LANGUAGE, json, 50);
+        let paths: Vec<_> = chunks.iter().map(|(_, p)| p.as_deref()).collect();
+        assert_eq!(
+            paths,
+            vec![Some("name"), Some("scripts.build"), Some("scripts.test")]
+        );
+        assert_eq!(chunks[2].0, r#""test": "jest --coverage --runInBand""#);
+    }
+
+    #[test]
+    fn test_invalid_file_is_an_error() {
+        let chunk_config = ChunkConfig::new(100).with_sizer(Sizer::Characters);
+        assert!(split_key_paths(tree_sitter_json::LANGUAGE, "{\"a\": ", chunk_config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_config_files() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("rag_key_path_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("Cargo.toml"), CARGO_TOML)?;
+        std::fs::write(dir.join("broken.json"), "{\"a\": ")?;
+        std::fs::write(dir.join("Dockerfile"), "FROM rust:1.85\nRUN cargo build\n")?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(110, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(dir.to_str().unwrap(), &policy).await?;
+        let find = |path: &str| {
+            chunks
+                .iter()
+                .filter(|c| c.metadata.path.as_deref() == Some(path))
+                .collect::<Vec<_>>()
+        };
+
+        let manifest = find("Cargo.toml");
+        assert_eq!(manifest.len(), 3);
+        assert_eq!(manifest[0].metadata.language.as_deref(), Some("toml"));
+        assert_eq!(
+            manifest[2].metadata.key_path.as_deref(),
+            Some("workspace.dependencies.lancedb")
+        );
+        assert_eq!(manifest[2].metadata.start_line, Some(8));
+
+        // files that don't parse are split as text
+        let broken = find("broken.json");
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].metadata.key_path, None);
+
+        let dockerfile = find("Dockerfile");
+        assert_eq!(
+            dockerfile[0].metadata.language.as_deref(),
+            Some("dockerfile")
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{load_codebase_into_chunks, Sizer};
+    use embedder::logs::{
+        normalize_level, parse_log_entries, parse_timestamp, split_log, LogFormat,
+    };
+    use text_splitter::ChunkConfig;
+
+    const JAVA_LOG: &str = "2024-05-01 12:00:00,123 INFO  Starting executor\n\
+2024-05-01 12:00:01,500 ERROR Task 3 failed\n\
+java.lang.IllegalStateException: boom\n\
+\tat com.example.Job.run(Job.java:42)\n\
+Caused by: java.io.IOException: disk full\n\
+\t... 3 more\n\
+2024-05-01 12:00:02,000 WARN  Retrying task 3\n";
+
+    // 2024-05-01T12:00:00Z
+    const MAY_FIRST_NOON: i64 = 1_714_564_800_000;
+
+    fn log_chunks(content: &str, size: usize) -> Vec<(String, Option<i64>, Option<String>)> {
+        let chunk_config = ChunkConfig::new(size).with_sizer(Sizer::Characters);
+        split_log(content, &LogFormat::default(), chunk_config)
+            .into_iter()
+            .map(|(chunk, metadata)| (chunk, metadata.log_timestamp, metadata.log_level))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(
+            parse_timestamp("2024-05-01 12:00:00,123"),
+            Some(MAY_FIRST_NOON + 123)
+        );
+        assert_eq!(
+            parse_timestamp("2024-05-01T14:00:00+02:00"),
+            Some(MAY_FIRST_NOON)
+        );
+        assert_eq!(parse_timestamp("24/05/01 12:00:00"), Some(MAY_FIRST_NOON));
+        assert_eq!(
+            parse_timestamp("2024-05-01"),
+            Some(MAY_FIRST_NOON - 12 * 3600 * 1000)
+        );
+        assert_eq!(parse_timestamp("1714564800"), Some(MAY_FIRST_NOON));
+        assert_eq!(parse_timestamp("1714564800000"), Some(MAY_FIRST_NOON));
+        assert_eq!(parse_timestamp("yesterday"), None);
+
+        assert_eq!(normalize_level("warning"), "WARN");
+        assert_eq!(normalize_level("CRITICAL"), "FATAL");
+    }
+
+    #[test]
+    fn test_stack_traces_stay_with_their_entry() {
+        let entries = parse_log_entries(JAVA_LOG, &LogFormat::default());
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].timestamp, Some(MAY_FIRST_NOON + 1500));
+        assert_eq!(entries[1].level.as_deref(), Some("ERROR"));
+        assert!(JAVA_LOG[entries[1].start..entries[1].end].ends_with("\t... 3 more\n"));
+
+        // every entry is a chunk of its own when neighbours don't fit
+        let chunks = log_chunks(JAVA_LOG, 180);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[1].0.starts_with("2024-05-01 12:00:01,500 ERROR"));
+        assert!(chunks[1].0.contains("Caused by: java.io.IOException"));
+        assert_eq!(chunks[2].2.as_deref(), Some("WARN"));
+
+        // a merged chunk has the first timestamp and the most severe level
+        let chunks = log_chunks(JAVA_LOG, 1000);
+        assert_eq!(
+            chunks,
+            vec![(
+                JAVA_LOG.trim().to_string(),
+                Some(MAY_FIRST_NOON + 123),
+                Some("ERROR".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn test_python_and_rust_traces() {
+        let python = "ERROR:root:request failed\nTraceback (most recent call last):\n  File \"app.py\", line 3, in <module>\n    main()\nValueError: bad input\nINFO:root:shutting down\n";
+        let entries = parse_log_entries(python, &LogFormat::default());
+        assert_eq!(entries.len(), 2);
+        assert!(python[entries[0].start..entries[0].end].ends_with("ValueError: bad input\n"));
+        assert_eq!(entries[0].timestamp, None);
+
+        let rust = "[2024-05-01T12:00:00Z INFO  app] started\n[2024-05-01T12:00:05Z ERROR app] worker died\nthread 'worker' panicked at src/main.rs:10:5:\nindex out of bounds\nstack backtrace:\n   0: rust_begin_unwind\n";
+        let entries = parse_log_entries(rust, &LogFormat::default());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].timestamp, Some(MAY_FIRST_NOON + 5000));
+        assert!(rust[entries[1].start..entries[1].end].contains("panicked at"));
+    }
+
+    #[test]
+    fn test_json_lines_and_custom_patterns() {
+        let json = "{\"ts\": 1714564800.5, \"level\": \"warning\", \"msg\": \"slow\"}\n{\"time\": \"2024-05-01T12:00:01Z\", \"level\": 50, \"msg\": \"failed\"}\n";
+        let entries = parse_log_entries(json, &LogFormat::default());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].timestamp, Some(MAY_FIRST_NOON + 500));
+        assert_eq!(entries[0].level.as_deref(), Some("WARN"));
+        assert_eq!(entries[1].timestamp, Some(MAY_FIRST_NOON + 1000));
+        assert_eq!(entries[1].level.as_deref(), Some("ERROR"));
+
+        let format = LogFormat::new(r"@(?P<timestamp>\d{10})", r"<(?P<level>\w+)>").unwrap();
+        let entries = parse_log_entries("@1714564800 <crit> down\n  retry\n", &format);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].timestamp, Some(MAY_FIRST_NOON));
+        assert_eq!(entries[0].level.as_deref(), Some("CRIT"));
+        assert!(LogFormat::new("(", r"\w+").is_err());
+
+        // plain text without entries is split as text
+        assert_eq!(
+            parse_log_entries("hello\nworld\n", &LogFormat::default()),
+            vec![]
+        );
+        assert_eq!(log_chunks("hello\nworld\n", 100)[0].1, None);
+    }
+
+    #[tokio::test]
+    async fn test_load_log_files() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("rag_logs_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("executor.log"), JAVA_LOG)?;
+        std::fs::write(dir.join("executor.log.1"), JAVA_LOG)?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(180, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(dir.to_str().unwrap(), &policy).await?;
+        assert_eq!(chunks.len(), 6);
+        let error = chunks
+            .iter()
+            .find(|c| c.metadata.path.as_deref() == Some("executor.log") && c.chunk_number == 1)
+            .unwrap();
+        assert_eq!(error.metadata.language.as_deref(), Some("log"));
+        assert_eq!(error.metadata.log_level.as_deref(), Some("ERROR"));
+        assert_eq!(error.metadata.log_timestamp, Some(MAY_FIRST_NOON + 1500));
+        assert_eq!(error.metadata.start_line, Some(2));
+        assert_eq!(error.metadata.end_line, Some(6));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
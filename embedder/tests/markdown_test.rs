@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{is_supported_file, load_codebase_into_chunks, Language, Sizer};
+    use embedder::markdown::split_markdown;
+    use std::path::Path;
+
+    const README: &str = r#"# Project
+
+Intro paragraph about the project.
+
+## Usage
+
+How to run it.
+
+### Commands
+
+```rust
+fn main() {}
+```
+
+```sh
+# not a heading inside a code block
+cargo run
+```
+
+## C#
+
+Calling it from dotnet needs the native lib.
+
+## License ##
+
+MIT
+"#;
+
+    #[test]
+    fn test_markdown_extensions() {
+        for name in ["README.md", "guide.markdown", "page.mdx"] {
+            assert_eq!(
+                is_supported_file(Path::new(name)),
+                (Language::Markdown, true)
+            );
+        }
+        assert_eq!(Language::parse_name("markdown"), Language::Markdown);
+    }
+
+    #[test]
+    fn test_heading_path_and_code_languages() -> anyhow::Result<()> {
+        let config = ChunkSpec::new(60, Sizer::Characters).chunk_config()?;
+        let chunks = split_markdown(README, config);
+        assert!(chunks.len() > 3);
+
+        let find = |text: &str| {
+            chunks
+                .iter()
+                .find(|(chunk, _)| chunk.contains(text))
+                .map(|(_, metadata)| metadata.clone())
+                .unwrap()
+        };
+
+        assert_eq!(find("Intro paragraph").heading.as_deref(), Some("Project"));
+        assert_eq!(
+            find("How to run").heading.as_deref(),
+            Some("Project > Usage")
+        );
+
+        let commands = find("fn main");
+        assert_eq!(
+            commands.heading.as_deref(),
+            Some("Project > Usage > Commands")
+        );
+        assert_eq!(commands.code_languages, vec!["rust"]);
+
+        // the comment in the sh block does not open a new section
+        let run = find("cargo run");
+        assert_eq!(run.heading.as_deref(), Some("Project > Usage > Commands"));
+        assert!(run.code_languages.contains(&"sh".to_string()));
+
+        assert_eq!(find("from dotnet").heading.as_deref(), Some("Project > C#"));
+
+        let license = find("MIT");
+        assert_eq!(license.heading.as_deref(), Some("Project > License"));
+        assert!(license.code_languages.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_markdown_file() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("rag_readme_{}.md", std::process::id()));
+        std::fs::write(&path, README)?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(2048, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(path.to_str().unwrap(), &policy).await?;
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].metadata.heading.as_deref(), Some("Project"));
+        assert_eq!(chunks[0].metadata.code_languages, vec!["rust", "sh"]);
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{is_supported_file, load_codebase_into_chunks, Language, Sizer};
+    use embedder::notebook::{parse_notebook, MAX_OUTPUT_CHARS};
+    use serde_json::json;
+    use std::path::Path;
+
+    fn notebook(language: &str) -> String {
+        json!({
+            "metadata": {
+                "kernelspec": { "name": "kernel", "language": language },
+                "language_info": { "name": language }
+            },
+            "nbformat": 4,
+            "cells": [
+                {
+                    "cell_type": "markdown",
+                    "metadata": {},
+                    "source": ["# Skew analysis\n", "Count rows per partition."]
+                },
+                {
+                    "cell_type": "code",
+                    "metadata": {},
+                    "source": "df = spark.read.parquet(\"events\")\ndf.groupBy(\"day\").count().show()",
+                    "outputs": [
+                        { "output_type": "stream", "name": "stdout", "text": ["day count\n", "1 42\n"] },
+                        { "output_type": "execute_result", "data": {
+                            "text/plain": "x".repeat(MAX_OUTPUT_CHARS + 1),
+                            "image/png": "iVBORw0KGgo="
+                        } }
+                    ]
+                },
+                { "cell_type": "code", "metadata": {}, "source": [], "outputs": [] },
+                { "cell_type": "raw", "metadata": {}, "source": "raw text" }
+            ]
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_notebook_extension() {
+        assert_eq!(
+            is_supported_file(Path::new("analysis.ipynb")),
+            (Language::Notebook, true)
+        );
+    }
+
+    #[test]
+    fn test_parse_notebook() -> anyhow::Result<()> {
+        let parsed = parse_notebook(&notebook("python"))?;
+        assert_eq!(parsed.language, Language::Python);
+        // the empty code cell is skipped, the others keep their index
+        let cells: Vec<_> = parsed
+            .cells
+            .iter()
+            .map(|c| (c.index, c.cell_type.as_str()))
+            .collect();
+        assert_eq!(cells, vec![(0, "markdown"), (1, "code"), (3, "raw")]);
+
+        let code = &parsed.cells[1].source;
+        assert!(code.starts_with("df = spark.read"));
+        assert!(code.contains("1 42"));
+        assert!(!code.contains("xxxx"));
+        assert!(!code.contains("iVBOR"));
+
+        assert_eq!(
+            parse_notebook(&notebook("scala"))?.language,
+            Language::Scala
+        );
+        assert!(parse_notebook("{}").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_notebook() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("rag_notebook_{}.ipynb", std::process::id()));
+        std::fs::write(&path, notebook("python"))?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(2048, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(path.to_str().unwrap(), &policy).await?;
+        let cells: Vec<_> = chunks
+            .iter()
+            .map(|c| (c.metadata.cell_index, c.metadata.cell_type.as_deref()))
+            .collect();
+        assert_eq!(
+            cells,
+            vec![
+                (Some(0), Some("markdown")),
+                (Some(1), Some("code")),
+                (Some(3), Some("raw"))
+            ]
+        );
+        let numbers: Vec<_> = chunks.iter().map(|c| c.get_chunk_number()).collect();
+        assert_eq!(numbers, vec![0, 1, 2]);
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
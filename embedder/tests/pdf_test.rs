@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{is_supported_file, load_codebase_into_chunks, Language, Sizer};
+    use embedder::pdf::{extract_pdf_pages, PdfText};
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Document, Object, Stream};
+    use std::path::Path;
+
+    /// Build a PDF with a page per entry, None is a page without text
+    fn build_pdf(pages: &[Option<&str>], encrypted: bool) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Courier",
+        });
+        let resources_id = doc.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let mut kids = Vec::new();
+        for text in pages {
+            let operations = match text {
+                Some(text) => vec![
+                    Operation::new("BT", vec![]),
+                    Operation::new("Tf", vec!["F1".into(), 12.into()]),
+                    Operation::new("Td", vec![72.into(), 700.into()]),
+                    Operation::new("Tj", vec![Object::string_literal(*text)]),
+                    Operation::new("ET", vec![]),
+                ],
+                None => vec![],
+            };
+            let content = Content { operations };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "Contents" => content_id,
+            });
+            kids.push(page_id.into());
+        }
+
+        let count = kids.len() as i64;
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => kids,
+                "Count" => count,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        doc.trailer.set("Root", catalog_id);
+        if encrypted {
+            let encrypt_id = doc.add_object(dictionary! {
+                "Filter" => "Standard",
+                "V" => 1,
+                "R" => 2,
+            });
+            doc.trailer.set("Encrypt", encrypt_id);
+        }
+
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_pdf_extension() {
+        assert_eq!(
+            is_supported_file(Path::new("runbook.pdf")),
+            (Language::Pdf, true)
+        );
+    }
+
+    #[test]
+    fn test_extract_pages() -> anyhow::Result<()> {
+        let bytes = build_pdf(
+            &[Some("Restart the service"), None, Some("Check the logs")],
+            false,
+        );
+        match extract_pdf_pages(&bytes)? {
+            PdfText::Pages(pages) => {
+                assert_eq!(pages.len(), 2);
+                assert_eq!(pages[0].0, 1);
+                assert!(pages[0].1.contains("Restart the service"));
+                assert_eq!(pages[1].0, 3);
+                assert!(pages[1].1.contains("Check the logs"));
+            }
+            other => panic!("Expected pages got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_and_image_only() -> anyhow::Result<()> {
+        let encrypted = build_pdf(&[Some("secret")], true);
+        assert_eq!(extract_pdf_pages(&encrypted)?, PdfText::Encrypted);
+
+        let scanned = build_pdf(&[None, None], false);
+        assert_eq!(extract_pdf_pages(&scanned)?, PdfText::ImageOnly);
+
+        assert!(extract_pdf_pages(b"not a pdf").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_pdf_directory() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("rag_pdf_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(
+            dir.join("runbook.pdf"),
+            build_pdf(&[Some("Page one text"), Some("Page two text")], false),
+        )?;
+        std::fs::write(dir.join("locked.pdf"), build_pdf(&[Some("secret")], true))?;
+        std::fs::write(dir.join("scan.pdf"), build_pdf(&[None], false))?;
+        std::fs::write(dir.join("broken.pdf"), b"%PDF-1.5 truncated")?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(2048, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(dir.to_str().unwrap(), &policy).await?;
+
+        // the skipped files don't abort the load
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|c| c.get_file_name() == "runbook.pdf"));
+        let mut pages: Vec<_> = chunks.iter().map(|c| c.metadata.page).collect();
+        pages.sort();
+        assert_eq!(pages, vec![Some(1), Some(2)]);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::detect::detect_language;
+    use embedder::file_loader::{load_codebase_into_chunks, Language, Sizer};
+    use embedder::spark::split_spark_events;
+    use std::path::Path;
+    use text_splitter::ChunkConfig;
+
+    const EVENT_LOG: &str = r#"{"Event":"SparkListenerLogStart","Spark Version":"3.5.1"}
+{"Event":"SparkListenerApplicationStart","App Name":"WordCount","App ID":"app-20240501120000-0001","Timestamp":1714564800000,"User":"spark"}
+{"Event":"SparkListenerJobStart","Job ID":3,"Submission Time":1714564801000,"Stage IDs":[11,12]}
+{"Event":"SparkListenerStageSubmitted","Stage Info":{"Stage ID":11,"Stage Attempt ID":0,"Stage Name":"map at WordCount.scala:20","Number of Tasks":2}}
+{"Event":"SparkListenerStageCompleted","Stage Info":{"Stage ID":11,"Stage Attempt ID":0,"Stage Name":"map at WordCount.scala:20","Number of Tasks":2,"Completion Time":1714564802000}}
+{"Event":"SparkListenerStageSubmitted","Stage Info":{"Stage ID":12,"Stage Attempt ID":0,"Stage Name":"collect at WordCount.scala:42","Number of Tasks":4}}
+{"Event":"SparkListenerTaskEnd","Stage ID":12,"Stage Attempt ID":0,"Task Type":"ResultTask","Task End Reason":{"Reason":"Success"},"Task Info":{"Task ID":40,"Executor ID":"1"}}
+{"Event":"SparkListenerTaskEnd","Stage ID":12,"Stage Attempt ID":0,"Task Type":"ResultTask","Task End Reason":{"Reason":"ExceptionFailure","Class Name":"java.lang.RuntimeException","Description":"boom","Stack Trace":[{"Declaring Class":"com.example.WordCount","Method Name":"count","File Name":"WordCount.scala","Line Number":42}]},"Task Info":{"Task ID":41,"Executor ID":"1"}}
+{"Event":"SparkListenerTaskEnd","Stage ID":12,"Stage Attempt ID":0,"Task Type":"ResultTask","Task End Reason":{"Reason":"ExceptionFailure","Class Name":"java.lang.RuntimeException","Description":"boom","Stack Trace":[]},"Task Info":{"Task ID":42,"Executor ID":"2"}}
+{"Event":"SparkListenerExecutorRemoved","Timestamp":1714564803000,"Executor ID":"3","Removed Reason":"Container killed by YARN for exceeding memory limits"}
+{"Event":"SparkListenerTaskEnd","Stage ID":12,"Stage Attempt ID":0,"Task Type":"ResultTask","Task End Reason":{"Reason":"ExecutorLostFailure","Executor ID":"3","Exit Caused By App":true,"Loss Reason":"Container killed by YARN for exceeding memory limits"},"Task Info":{"Task ID":43,"Executor ID":"3"}}
+{"Event":"SparkListenerStageCompleted","Stage Info":{"Stage ID":12,"Stage Attempt ID":0,"Stage Name":"collect at WordCount.scala:42","Number of Tasks":4,"Completion Time":1714564804000,"Failure Reason":"Job aborted due to stage failure: Task 1 in stage 12.0 failed 4 times\nDriver stacktrace:"}}
+{"Event":"SparkListenerJobEnd","Job ID":3,"Completion Time":1714564805000,"Job Result":{"Result":"JobFailed","Exception":{"Message":"Job aborted due to stage failure: Task 1 in stage 12.0 failed 4 times","Stack Trace":[{"Declaring Class":"org.apache.spark.scheduler.DAGScheduler","Method Name":"failJobAndIndependentStages","File Name":"DAGScheduler.scala","Line Number":2856}]}}}
+{"Event":"SparkListenerApplicationEnd","Timestamp":1714564806000}
+"#;
+
+    fn spark_chunks(size: usize) -> Vec<(String, embedder::file_loader::ChunkMetadata)> {
+        let chunk_config = ChunkConfig::new(size).with_sizer(Sizer::Characters);
+        split_spark_events(EVENT_LOG, chunk_config).unwrap()
+    }
+
+    #[test]
+    fn test_failed_stage_chunk() {
+        let chunks = spark_chunks(2000);
+        assert_eq!(chunks.len(), 2);
+
+        let (stage, metadata) = &chunks[0];
+        assert!(stage.starts_with("Spark application app-20240501120000-0001 (WordCount)\nStage 12 attempt 0 of job 3 failed: collect at WordCount.scala:42"));
+        assert!(stage.contains("Tasks: 4, failed tasks: 3"));
+        assert!(stage.contains(
+            "- 2 x ExceptionFailure java.lang.RuntimeException: boom on executors 1, 2\n\tat com.example.WordCount.count(WordCount.scala:42)"
+        ));
+        assert!(stage.contains(
+            "Executors lost while the stage ran:\n- executor 3: Container killed by YARN"
+        ));
+        assert_eq!(
+            metadata.spark_app_id.as_deref(),
+            Some("app-20240501120000-0001")
+        );
+        assert_eq!(metadata.spark_job_id, Some(3));
+        assert_eq!(metadata.spark_stage_id, Some(12));
+        assert_eq!(metadata.spark_stage_attempt, Some(0));
+        assert_eq!(
+            metadata.failure_reason.as_deref(),
+            Some("Job aborted due to stage failure: Task 1 in stage 12.0 failed 4 times")
+        );
+        assert_eq!(metadata.log_timestamp, Some(1_714_564_804_000));
+        assert_eq!(metadata.log_level.as_deref(), Some("ERROR"));
+    }
+
+    #[test]
+    fn test_failed_job_chunk() {
+        let chunks = spark_chunks(2000);
+        let (job, metadata) = &chunks[1];
+        assert!(job.contains("Job 3 failed: Job aborted due to stage failure"));
+        assert!(job.contains("Stages: 11, 12\nFailed stages: 12 attempt 0"));
+        assert!(job.contains("DAGScheduler.failJobAndIndependentStages"));
+        assert_eq!(metadata.spark_job_id, Some(3));
+        assert_eq!(metadata.spark_stage_id, None);
+
+        // large failures are split, every piece keeps the metadata
+        let chunks = spark_chunks(120);
+        assert!(chunks.len() > 2);
+        assert!(chunks
+            .iter()
+            .all(|(_, metadata)| metadata.spark_job_id == Some(3)));
+
+        let chunk_config = ChunkConfig::new(100).with_sizer(Sizer::Characters);
+        assert!(split_spark_events("{\"level\": \"info\"}\n", chunk_config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_event_logs() -> anyhow::Result<()> {
+        assert_eq!(
+            detect_language(Path::new("app-20240501120000-0001.inprogress"), EVENT_LOG).language,
+            Language::SPARKLOG
+        );
+
+        let dir = std::env::temp_dir().join(format!("rag_spark_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("app-20240501120000-0001"), EVENT_LOG)?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(2000, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(dir.to_str().unwrap(), &policy).await?;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].metadata.language.as_deref(), Some("sparklog"));
+        assert_eq!(chunks[0].metadata.spark_stage_id, Some(12));
+        assert_eq!(chunks[0].metadata.start_line, None);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
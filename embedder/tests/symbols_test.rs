@@ -0,0 +1,148 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{load_codebase_into_chunks, Sizer};
+    use embedder::symbols::{
+        extract_symbols, format_symbols, parse_symbols, symbols_in_range, Symbol,
+    };
+
+    const RUST: &str = r#"mod pipeline {
+    pub trait Loader {
+        fn load(&self);
+    }
+
+    pub struct FileLoader<T> {
+        inner: T,
+    }
+
+    impl<T> Loader for FileLoader<T> {
+        fn load(&self) {}
+    }
+
+    pub async fn run_embedding_pipeline(path: &str) {
+        println!("{}", path);
+    }
+}
+"#;
+
+    fn names(language: tree_sitter_language::LanguageFn, source: &str) -> Vec<String> {
+        extract_symbols(language, source)
+            .unwrap()
+            .iter()
+            .map(|span| span.symbol.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_symbols() {
+        assert_eq!(
+            names(tree_sitter_rust::LANGUAGE, RUST),
+            vec![
+                "module:pipeline",
+                "trait:Loader",
+                "function:load",
+                "struct:FileLoader",
+                "impl:FileLoader",
+                "function:load",
+                "function:run_embedding_pipeline"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_language_symbols() {
+        let python =
+            "class Job:\n    def run(self):\n        pass\n\ndef main():\n    Job().run()\n";
+        assert_eq!(
+            names(tree_sitter_python::LANGUAGE, python),
+            vec!["class:Job", "function:run", "function:main"]
+        );
+
+        let go = "package main\n\ntype Server struct{}\n\nfunc (s *Server) Start() {}\n\nfunc main() {}\n";
+        assert_eq!(
+            names(tree_sitter_go::LANGUAGE, go),
+            vec!["type:Server", "method:Start", "function:main"]
+        );
+
+        let java = "class Job {\n    Job() {}\n    void run() {}\n}\n";
+        assert_eq!(
+            names(tree_sitter_java::LANGUAGE, java),
+            vec!["class:Job", "constructor:Job", "method:run"]
+        );
+
+        // a struct used in a declaration is not a definition
+        let c = "struct point { int x; };\nstruct point *origin;\nint *make(int x) { return 0; }\n";
+        assert_eq!(
+            names(tree_sitter_c::LANGUAGE, c),
+            vec!["struct:point", "function:make"]
+        );
+
+        let ruby = "module Jobs\n  class Runner < Base\n    def run; end\n    def self.build; end\n  end\nend\n";
+        assert_eq!(
+            names(tree_sitter_ruby::LANGUAGE, ruby),
+            vec!["module:Jobs", "class:Runner", "method:run", "method:build"]
+        );
+
+        let csharp = "namespace Jobs;\npublic struct Point { public Point() {} void Move() {} }\n";
+        assert_eq!(
+            names(tree_sitter_c_sharp::LANGUAGE, csharp),
+            vec![
+                "namespace:Jobs",
+                "struct:Point",
+                "constructor:Point",
+                "method:Move"
+            ]
+        );
+
+        let bash = "build() { cargo build; }\nfunction clean { rm -rf target; }\n";
+        assert_eq!(
+            names(tree_sitter_bash::LANGUAGE, bash),
+            vec!["function:build", "function:clean"]
+        );
+    }
+
+    #[test]
+    fn test_symbols_in_range() {
+        let spans = extract_symbols(tree_sitter_rust::LANGUAGE, RUST).unwrap();
+        let start = RUST.find("println").unwrap();
+        assert_eq!(
+            symbols_in_range(&spans, start, start + 7),
+            vec![
+                Symbol::new("module", "pipeline"),
+                Symbol::new("function", "run_embedding_pipeline")
+            ]
+        );
+
+        let symbols = symbols_in_range(&spans, 0, RUST.len());
+        let column = format_symbols(&symbols).unwrap();
+        assert!(column.starts_with("module:pipeline,trait:Loader,function:load"));
+        assert_eq!(parse_symbols(&column), symbols);
+        assert_eq!(format_symbols(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn test_load_code_chunks_with_symbols() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("rag_symbols_{}.rs", std::process::id()));
+        std::fs::write(&path, RUST)?;
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(120, Sizer::Characters));
+        let chunks = load_codebase_into_chunks(path.to_str().unwrap(), &policy).await?;
+        assert!(chunks.len() > 1);
+        // every chunk is inside the module
+        assert!(chunks
+            .iter()
+            .all(|c| c.metadata.symbols.first() == Some(&Symbol::new("module", "pipeline"))));
+
+        let pipeline = chunks
+            .iter()
+            .find(|c| c.get_content().contains("println"))
+            .unwrap();
+        assert!(pipeline
+            .metadata
+            .symbols
+            .contains(&Symbol::new("function", "run_embedding_pipeline")));
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+#[cfg(test)]
+mod tests {
+    use embedder::chunk_policy::{ChunkPolicy, ChunkSpec};
+    use embedder::file_loader::{load_files, Sizer};
+    use embedder::walker::{skip_summary, SkipReason, WalkOptions, Walker};
+    use std::path::{Path, PathBuf};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(dir: &Path, path: &str, content: &[u8]) {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// A small repository with ignored, binary and oversized files
+    fn sample_repo(name: &str) -> PathBuf {
+        let dir = temp_dir(name);
+        write(&dir, ".gitignore", b"target/\n*.log\n!keep.log\n");
+        write(&dir, ".git/HEAD", b"ref: refs/heads/main\n");
+        write(&dir, "src/main.rs", b"fn main() {}\n");
+        write(&dir, "src/lib.rs", b"pub fn lib() {}\n");
+        write(&dir, "src/.ignore", b"generated.rs\n");
+        write(&dir, "src/generated.rs", b"pub fn generated() {}\n");
+        write(&dir, "target/debug/build.rs", b"fn build() {}\n");
+        write(&dir, "debug.log", b"ERROR failed\n");
+        write(&dir, "keep.log", b"ERROR kept\n");
+        write(&dir, "docs/notes.txt", b"Some notes about the project\n");
+        write(&dir, "data.txt", b"binary\0content");
+        write(&dir, "big.txt", "x".repeat(2048).as_bytes());
+        dir
+    }
+
+    fn relative(dir: &Path, paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|p| p.strip_prefix(dir).unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_walk_honors_ignore_files() -> anyhow::Result<()> {
+        let dir = sample_repo("walker_ignore");
+        let options = WalkOptions {
+            max_file_size: 1024,
+            ..WalkOptions::default()
+        };
+        let walked = Walker::new(&dir, &options)?.walk()?;
+
+        assert_eq!(
+            relative(&dir, &walked.files),
+            vec![
+                ".gitignore",
+                "data.txt",
+                "docs/notes.txt",
+                "keep.log",
+                "src/.ignore",
+                "src/lib.rs",
+                "src/main.rs"
+            ]
+        );
+
+        let reason = |path: &str| {
+            walked
+                .skipped
+                .iter()
+                .find(|s| s.path == dir.join(path))
+                .map(|s| s.reason.clone())
+        };
+        assert_eq!(reason(".git"), Some(SkipReason::GitDirectory));
+        assert_eq!(reason("target"), Some(SkipReason::Ignored));
+        assert_eq!(reason("debug.log"), Some(SkipReason::Ignored));
+        assert_eq!(reason("src/generated.rs"), Some(SkipReason::Ignored));
+        assert_eq!(reason("big.txt"), Some(SkipReason::TooLarge(2048)));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_include_and_exclude_globs() -> anyhow::Result<()> {
+        let dir = sample_repo("walker_globs");
+        let options = WalkOptions {
+            include: vec!["src/*.rs".to_string(), "*.txt".to_string()],
+            exclude: vec!["docs".to_string(), "src/main.rs".to_string()],
+            max_file_size: 0,
+            respect_ignore_files: false,
+            ..WalkOptions::default()
+        };
+        let walked = Walker::new(&dir, &options)?.walk()?;
+
+        // without ignore files generated.rs is walked, big.txt has no size limit
+        assert_eq!(
+            relative(&dir, &walked.files),
+            vec!["big.txt", "data.txt", "src/generated.rs", "src/lib.rs"]
+        );
+        let reasons: Vec<_> = walked
+            .skipped
+            .iter()
+            .filter(|s| s.path == dir.join("docs") || s.path == dir.join("src/main.rs"))
+            .map(|s| s.reason.clone())
+            .collect();
+        assert_eq!(reasons, vec![SkipReason::Excluded, SkipReason::Excluded]);
+        assert!(walked
+            .skipped
+            .iter()
+            .any(|s| s.path == dir.join("keep.log") && s.reason == SkipReason::NotIncluded));
+        assert!(walked
+            .skipped
+            .iter()
+            .any(|s| s.path == dir.join("target/debug/build.rs")
+                && s.reason == SkipReason::NotIncluded));
+
+        assert!(Walker::new(
+            &dir,
+            &WalkOptions {
+                include: vec!["[".to_string()],
+                ..WalkOptions::default()
+            }
+        )
+        .is_err());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_loop() -> anyhow::Result<()> {
+        let dir = temp_dir("walker_symlink");
+        write(&dir, "a/file.txt", b"text\n");
+        std::os::unix::fs::symlink(&dir, dir.join("a/back"))?;
+        std::os::unix::fs::symlink(dir.join("missing"), dir.join("broken"))?;
+
+        let walked = Walker::new(&dir, &WalkOptions::default())?.walk()?;
+        assert_eq!(relative(&dir, &walked.files), vec!["a/file.txt"]);
+        assert!(walked
+            .skipped
+            .iter()
+            .any(|s| s.path == dir.join("a/back") && s.reason == SkipReason::SymlinkLoop));
+        assert!(
+            walked
+                .skipped
+                .iter()
+                .any(|s| s.path == dir.join("broken")
+                    && matches!(s.reason, SkipReason::Unreadable(_)))
+        );
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_git_info_exclude() -> anyhow::Result<()> {
+        let dir = sample_repo("walker_git_exclude");
+        write(&dir, ".git/info/exclude", b"docs/\nkeep.log\n");
+        let walked = Walker::new(&dir, &WalkOptions::default())?.walk()?;
+
+        // the .gitignore negation of keep.log wins over the repository excludes
+        let files = relative(&dir, &walked.files);
+        assert!(!files.contains(&"docs/notes.txt".to_string()));
+        assert!(files.contains(&"keep.log".to_string()));
+        assert!(walked
+            .skipped
+            .iter()
+            .any(|s| s.path == dir.join("docs") && s.reason == SkipReason::Ignored));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_subdirectory_honors_parent_ignore_files() -> anyhow::Result<()> {
+        let dir = temp_dir("walker_parent_ignore");
+        write(&dir, ".git/HEAD", b"ref: refs/heads/main\n");
+        write(&dir, ".gitignore", b"*.gen.rs\nsrc/local/\n");
+        write(&dir, "src/main.rs", b"fn main() {}\n");
+        write(&dir, "src/out.gen.rs", b"pub fn generated() {}\n");
+        write(&dir, "src/local/notes.rs", b"pub fn notes() {}\n");
+
+        let src = dir.join("src");
+        let walked = Walker::new(&src, &WalkOptions::default())?.walk()?;
+        assert_eq!(relative(&src, &walked.files), vec!["main.rs"]);
+        let reason = |path: &str| {
+            walked
+                .skipped
+                .iter()
+                .find(|s| s.path == src.join(path))
+                .map(|s| s.reason.clone())
+        };
+        assert_eq!(reason("out.gen.rs"), Some(SkipReason::Ignored));
+        assert_eq!(reason("local"), Some(SkipReason::Ignored));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_directory_is_skipped() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_dir("walker_unreadable");
+        write(&dir, "a/file.txt", b"text\n");
+        write(&dir, "locked/secret.txt", b"text\n");
+        write(&dir, "z/file.txt", b"text\n");
+        let locked = dir.join("locked");
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000))?;
+        // permissions don't apply to root
+        if std::fs::read_dir(&locked).is_ok() {
+            std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755))?;
+            std::fs::remove_dir_all(&dir)?;
+            return Ok(());
+        }
+
+        let walked = Walker::new(&dir, &WalkOptions::default())?.walk()?;
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755))?;
+        assert_eq!(
+            relative(&dir, &walked.files),
+            vec!["a/file.txt", "z/file.txt"]
+        );
+        assert!(walked
+            .skipped
+            .iter()
+            .any(|s| s.path == locked && matches!(s.reason, SkipReason::Unreadable(_))));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_load_reports_skipped_files() -> anyhow::Result<()> {
+        let dir = sample_repo("walker_load");
+        write(&dir, "image.png", b"\x89PNG\r\n\x1a\n\0\0");
+        write(&dir, "latin1.txt", b"caf\xe9\n");
+
+        let policy = ChunkPolicy::new(ChunkSpec::new(2048, Sizer::Characters));
+        let options = WalkOptions {
+            max_file_size: 1024,
+            ..WalkOptions::default()
+        };
+        let loaded = load_files(dir.to_str().unwrap(), &policy, &options).await?;
+
+        let mut files: Vec<_> = loaded.chunks.iter().map(|c| c.get_file_name()).collect();
+        files.dedup();
+        assert_eq!(files, vec!["notes.txt", "keep.log", "lib.rs", "main.rs"]);
+
+        let reason = |path: &str| {
+            loaded
+                .skipped
+                .iter()
+                .find(|s| s.path == dir.join(path))
+                .map(|s| s.reason.clone())
+        };
+        assert_eq!(reason("data.txt"), Some(SkipReason::Binary));
+        assert_eq!(reason("latin1.txt"), Some(SkipReason::Binary));
+        assert_eq!(reason("image.png"), Some(SkipReason::Unsupported));
+        assert_eq!(reason("keep.log"), None);
+
+        let summary = skip_summary(&loaded.skipped);
+        assert!(summary.starts_with(&format!("Skipped {} paths: ", loaded.skipped.len())));
+        assert!(summary.contains("2 binary"));
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
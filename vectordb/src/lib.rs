@@ -5,20 +5,27 @@ pub mod vector_schema;
 
 use ::anyhow::Context;
 use ::anyhow::Result;
-use embedder::fetch_embedding;
+use configs::constants::EMBED_WRITE_BUFFER_ROWS;
+use embedder::embed_cache::{cache_dir, EmbedCache};
+use embedder::embed_config::EmbedRequest;
 use embedder::file_loader as code_loader;
 use embedder::file_loader::chunk_embed_request_arc;
-use http_body_util::Full;
-use hyper::body::Bytes;
-use hyper_rustls::HttpsConnector;
-use hyper_util::client::legacy::connect::HttpConnector;
-use hyper_util::client::legacy::Client as LegacyClient;
+use embedder::{fetch_embedding, fetch_embedding_batch};
+use futures::stream::{self, StreamExt};
 use vector_schema::TableSchema;
 // use hyper::client::HttpConnector;
 // use hyper::Client;
-use ::log::debug;
+use ::log::{debug, info};
 use ::std::path::PathBuf;
-pub type HttpsClient = LegacyClient<HttpsConnector<HttpConnector>, Full<Bytes>>;
+use ::std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub use configs::HttpsClient;
+pub use embedder::chunk_policy::{ChunkPolicy, ChunkSpec, Splitter};
+pub use embedder::embed_config::EmbedOptions;
+pub use embedder::file_loader::{EmbedMode, Sizer};
+pub use embedder::logs::LogFormat;
+pub use embedder::walker::WalkOptions;
 
 fn get_file_name(root_dir: &str) -> String {
     let root_path = PathBuf::from(root_dir);
@@ -45,23 +52,51 @@ impl EmbeddingStore {
     }
 }
 
+/// Embed a single request to find the output dimension of the model
+/// Arguments:
+/// - embed_request: &Arc<RwLock<EmbedRequest>>
+/// - https_client: &HttpsClient
+/// - cache: &EmbedCache
+///
+/// Returns:
+/// - Result<i32> - The length of the returned embedding
+async fn probe_embedding_dim(
+    embed_request: &Arc<RwLock<EmbedRequest>>,
+    https_client: &HttpsClient,
+    cache: &EmbedCache,
+) -> Result<i32> {
+    let response = fetch_embedding(embed_request, https_client, Some(cache)).await?;
+    match response.embeddings.first().map(|e| e.len()) {
+        Some(dim) if dim > 0 => i32::try_from(dim).context("Embedding dimension is too large"),
+        _ => Err(anyhow::anyhow!(
+            "Model {} returned an empty embedding",
+            response.model
+        )),
+    }
+}
+
 /// Run the LanceVectorDB pipeline
 /// 1. Load the codebase into chunks
 /// 2. Extract the embed requests from the chunks
 /// 3. Initialize the database
-/// 4. Create a table
+/// 4. Probe the embedding dimension and create a table
 /// 5. Load embeddings
 /// 6. Create an index
 /// # Arguments
 /// * `path` - The path to the codebase
-/// * `chunk_size` - The size of the chunks
+/// * `chunk_policy` - Chunk size, overlap, sizer and splitter per language and glob
+/// * `walk_options` - Include and exclude globs and the size limit of the loaded files
+/// * `options` - Embed mode, chunks per embedding request and requests in flight
 /// * `embed_url` - The URL of the embedding API
 /// * `http_client` - The HTTP client
 /// # Returns
 /// * `Result<()>` - The result of the operation
+#[allow(clippy::too_many_arguments)]
 pub async fn run_embedding_pipeline(
     path: &str,
-    chunk_size: usize,
+    chunk_policy: &ChunkPolicy,
+    walk_options: &WalkOptions,
+    options: &EmbedOptions,
     provider: &str,
     embed_url: &str,
     api_key: &str,
@@ -69,18 +104,29 @@ pub async fn run_embedding_pipeline(
     https_client: &HttpsClient,
 ) -> Result<EmbeddingStore> {
     // Load the codebase into chunks
-    let chunks = code_loader::load_codebase_into_chunks(path, chunk_size)
+    let loaded = code_loader::load_files(path, chunk_policy, walk_options)
         .await
         .context("Failed to split codebase into chunks")?;
+    for skipped in &loaded.skipped {
+        info!("Skipped {}", skipped);
+    }
+    println!("{}", embedder::walker::skip_summary(&loaded.skipped));
+    let chunks = loaded.chunks;
 
-    // Extract embed requests from the chunks
+    // Extract embed requests from the chunks, the chunk index is the row id
     let embed_requests: Vec<_> = chunks
         .iter()
-        .map(|chunk| chunk_embed_request_arc(chunk, provider, embed_url, api_key, model))
+        .enumerate()
+        .filter(|(_, chunk)| chunk.content.iter().any(|line| !line.trim().is_empty()))
+        .map(|(id, chunk)| {
+            let embed_request =
+                chunk_embed_request_arc(chunk, provider, embed_url, api_key, model, options.mode);
+            (id as i32, embed_request)
+        })
         .collect();
 
     // Log embed requests for debugging
-    for embed_request in &embed_requests {
+    for (_, embed_request) in &embed_requests {
         let embed_request = embed_request.read().await;
         debug!("Embed Request Metadata: {:?}", embed_request.metadata);
     }
@@ -93,64 +139,87 @@ pub async fn run_embedding_pipeline(
         .await
         .context("Failed to connect to the database")?;
 
+    // Chunks embedded by an earlier load with the same provider and model are reused
+    let cache = EmbedCache::open(cache_dir(&db_uri)).context("Failed to open embedding cache")?;
+
+    // Size the vector column for the model, the probe embedding lands in the cache
+    let vector_dim = match embed_requests.first() {
+        Some((_, embed_request)) => probe_embedding_dim(embed_request, https_client, &cache)
+            .await
+            .with_context(|| format!("Failed to probe the dimension of model {}", model))?,
+        None => configs::settings::settings().vector_dim.value,
+    };
+    info!("Embedding model {} has {} dimensions", model, vector_dim);
+
     // Create table
     let table_name = format!("{}_{}", &file_name, "table");
-    let table_schema = TableSchema::new(&table_name);
+    let table_schema = TableSchema::with_embedding(&table_name, vector_dim, Some(model));
 
     vector_schema::create_lance_table(&mut db, &table_schema)
         .await
         .context("Failed to create table")?;
 
-    // Load embeddings in parallel to improve performance
-    let mut tasks = Vec::new();
+    // Embed the chunks in batches with a bounded number of requests in flight
     let table = db
         .open_table(&table_name)
         .execute()
         .await
         .context("Failed to open table")?;
-    for (id, embed_request) in embed_requests.into_iter().enumerate() {
-        // let embed_url = embed_url.to_string();
-        let https_client = https_client.clone();
-        let table_schema = table_schema.clone();
-        let table = table.clone();
-
-        // Spawn a task to fetch and insert embeddings in parallel
-        let task = tokio::spawn(async move {
-            // Fetch embeddings
-            let embed_response = fetch_embedding(&embed_request, &https_client)
-                .await
-                .context("Failed to fetch embeddings")?;
-            debug!("Embedding Response: {:?}", embed_response.embeddings.len());
+    let total = embed_requests.len();
+    let mut embed_batches = stream::iter(embed_requests.chunks(options.batch_size.max(1)))
+        .map(|batch| {
+            let https_client = https_client.clone();
+            let cache = &cache;
+            async move {
+                let requests: Vec<_> = batch.iter().map(|(_, request)| request.clone()).collect();
+                let responses = fetch_embedding_batch(&requests, &https_client, Some(cache))
+                    .await
+                    .context("Failed to fetch embeddings")?;
+                Ok::<_, anyhow::Error>((batch, responses))
+            }
+        })
+        .buffer_unordered(options.max_in_flight.max(1));
 
-            // Create record batch
+    // Buffer the record batches so the table gets a few large writes
+    let mut buffer = Vec::new();
+    let mut buffered_rows = 0;
+    let mut embedded = 0;
+    while let Some(result) = embed_batches.next().await {
+        let (batch, responses) = result?;
+        for ((id, embed_request), embed_response) in batch.iter().zip(responses) {
+            debug!("Embedding Response: {:?}", embed_response.embeddings.len());
             let record_batch = vector_load::create_record_batch(
-                id as i32,
-                embed_request,
+                *id,
+                embed_request.clone(),
                 embed_response,
                 &table_schema,
             )
             .await
             .context("Failed to create record batch")?;
+            buffered_rows += record_batch.num_rows();
+            buffer.push(record_batch);
+        }
+        embedded += batch.len();
+        info!("Embedded {}/{} chunks", embedded, total);
 
-            // Insert embeddings into the table
-            vector_load::insert_embeddings(&table_schema, record_batch, table)
-                .await
-                .context("Failed to insert embeddings")?;
-
-            // debug!("Embeddings inserted successfully");
-            Ok::<(), anyhow::Error>(())
-        });
-
-        tasks.push(task);
-    }
-
-    // Wait for all tasks to complete
-    for task in tasks {
-        // task.await??;
-        task.await
-            .context("Failed to run task")?
-            .context("Insert Task failed")?;
+        if buffered_rows >= EMBED_WRITE_BUFFER_ROWS {
+            vector_load::insert_record_batches(
+                &table_schema,
+                std::mem::take(&mut buffer),
+                table.clone(),
+            )
+            .await
+            .context("Failed to insert embeddings")?;
+            buffered_rows = 0;
+        }
     }
+    vector_load::insert_record_batches(&table_schema, buffer, table)
+        .await
+        .context("Failed to insert embeddings")?;
+    println!("{}", cache.stats());
+    cache
+        .save_stats()
+        .context("Failed to save embedding cache stats")?;
 
     // Create an index on the embedding column
     let embedding_col = table_schema.vector.name();
@@ -1,5 +1,7 @@
 use embedder;
 use embedder::embed_config::EmbedRequest;
+use embedder::logs::parse_timestamp;
+use embedder::symbols::parse_symbols;
 // use hyper::client::HttpConnector;
 // use ::hyper::Client as HttpClient;
 use anyhow::{anyhow, Context, Result};
@@ -7,6 +9,7 @@ use arrow_array::{Array, StringArray};
 use arrow_array::{Int32Array, RecordBatch};
 use arrow_schema::DataType::{Int32, Utf8};
 use arrow_schema::SchemaRef;
+use chrono::DateTime;
 use configs::HttpsClient;
 use futures::StreamExt;
 use lancedb::arrow::SendableRecordBatchStream;
@@ -14,7 +17,144 @@ use lancedb::query::ExecutableQuery;
 use lancedb::query::IntoQueryVector;
 use lancedb::query::QueryBase;
 use lancedb::{Connection, Table};
-use log::{debug, error};
+use log::{debug, error, warn};
+
+use crate::vector_schema::table_embedding;
+
+/// SymbolMatch how an exact symbol name changes the nearest chunks of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymbolMatch {
+    /// Only the nearest chunks with the symbol are returned
+    Filter,
+    /// Chunks with the symbol come first, followed by the other nearest chunks
+    #[default]
+    Boost,
+}
+
+impl SymbolMatch {
+    pub fn parse_mode(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "filter" => Ok(SymbolMatch::Filter),
+            "boost" => Ok(SymbolMatch::Boost),
+            _ => Err(anyhow!(
+                "Unsupported symbol match: {} expected filter or boost",
+                s
+            )),
+        }
+    }
+}
+
+/// SymbolQuery exact name of a definition e.g. `run_embedding_pipeline` and how it is matched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolQuery {
+    pub name: String,
+    pub mode: SymbolMatch,
+}
+
+impl SymbolQuery {
+    pub fn new(name: &str, mode: SymbolMatch) -> Self {
+        SymbolQuery {
+            name: name.to_string(),
+            mode,
+        }
+    }
+
+    /// True when a `symbols` column value has a definition with exactly this name
+    pub fn matches(&self, symbols: &str) -> bool {
+        parse_symbols(symbols).iter().any(|s| s.name == self.name)
+    }
+
+    /// Filter keeping the rows that may have the symbol, `matches` checks them exactly
+    fn sql_filter(&self) -> String {
+        format!("symbols LIKE '%:{}%'", self.name.replace('\'', "''"))
+    }
+}
+
+/// TimeWindow range of log timestamps the nearest chunks are filtered on, `since` is inclusive
+/// and `until` exclusive, both in milliseconds since the epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeWindow {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+}
+
+impl TimeWindow {
+    pub fn new(since: Option<i64>, until: Option<i64>) -> Self {
+        TimeWindow { since, until }
+    }
+
+    /// Parse the `--since` and `--until` bounds e.g. `2024-05-01` or `2024-05-01T12:00:00Z`
+    /// Arguments:
+    /// - since: Option<&str>
+    /// - until: Option<&str>
+    ///
+    /// Returns:
+    /// - Result<Option<TimeWindow>> None when neither bound is set
+    pub fn parse_window(since: Option<&str>, until: Option<&str>) -> Result<Option<Self>> {
+        let parse = |bound: Option<&str>| {
+            bound
+                .map(|b| parse_timestamp(b).ok_or_else(|| anyhow!("Invalid timestamp: {}", b)))
+                .transpose()
+        };
+        let window = TimeWindow::new(parse(since)?, parse(until)?);
+        if let (Some(since), Some(until)) = (window.since, window.until) {
+            if since >= until {
+                return Err(anyhow!("--since must be before --until"));
+            }
+        }
+        Ok(Some(window).filter(|w| w.since.is_some() || w.until.is_some()))
+    }
+
+    /// Filter keeping the log chunks inside the window, chunks without a timestamp are dropped
+    fn sql_filter(&self) -> String {
+        let literal = |millis: i64| {
+            let time = DateTime::from_timestamp_millis(millis).unwrap_or_default();
+            format!("TIMESTAMP '{}'", time.format("%Y-%m-%d %H:%M:%S%.3f"))
+        };
+        let mut filters = vec!["log_timestamp IS NOT NULL".to_string()];
+        if let Some(since) = self.since {
+            filters.push(format!("log_timestamp >= {}", literal(since)));
+        }
+        if let Some(until) = self.until {
+            filters.push(format!("log_timestamp < {}", literal(until)));
+        }
+        filters.join(" AND ")
+    }
+}
+
+/// SparkTarget stage or job of a spark application a question asks about
+/// e.g. `why did stage 12 fail`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparkTarget {
+    Stage(u32),
+    Job(u32),
+}
+
+impl SparkTarget {
+    /// First `stage <id>` or `job <id>` of a question
+    pub fn from_question(question: &str) -> Option<Self> {
+        let question = question.to_lowercase();
+        let words: Vec<&str> = question
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .collect();
+        words
+            .windows(2)
+            .find_map(|pair| match (pair[0], pair[1].parse().ok()?) {
+                ("stage", id) => Some(SparkTarget::Stage(id)),
+                ("job", id) => Some(SparkTarget::Job(id)),
+                _ => None,
+            })
+    }
+
+    /// Filter keeping the failure chunks of the stage, or the chunk of the job itself
+    fn sql_filter(&self) -> String {
+        match self {
+            SparkTarget::Stage(id) => format!("spark_stage_id = {}", id),
+            SparkTarget::Job(id) => format!("spark_job_id = {} AND spark_stage_id IS NULL", id),
+        }
+    }
+}
 
 /// Run the query to get the nearest embeddings
 /// Arguments:
@@ -25,6 +165,9 @@ use log::{debug, error};
 /// - db_config: VectorDbConfig
 /// - http_client: &HttpClient<HttpConnector>
 /// - whole_query: bool
+/// - file_context: bool
+/// - symbol: Option<&SymbolQuery> exact symbol name the chunks are filtered or boosted on
+/// - time_window: Option<&TimeWindow> log timestamps the chunks are filtered on
 ///
 /// Returns:
 /// - Result<Vec<String>>
@@ -40,6 +183,8 @@ pub async fn run_query(
     http_client: &HttpsClient,
     whole_query: bool,
     file_context: bool,
+    symbol: Option<&SymbolQuery>,
+    time_window: Option<&TimeWindow>,
 ) -> Result<Vec<String>> {
     // colog::init();
 
@@ -68,22 +213,97 @@ pub async fn run_query(
 
     let embed_url = query_request_arc.read().await.get_embed_url();
 
-    let query_response = embedder::fetch_embedding(&query_request_arc, http_client)
+    // queries are one off, only the loaded chunks go through the embedding cache
+    let query_response = embedder::fetch_embedding(&query_request_arc, http_client, None)
         .await
         .with_context(|| format!("Failed to fetch embedding response from {}", &embed_url))?;
 
     let query_vector = query_response.embeddings[0].clone();
 
-    // query the vector table
-    let content = query_vector_table(db, vector_table, query_vector, whole_query, file_context)
+    check_query_embedding(db, vector_table, embed_model, query_vector.len())
         .await
-        .context("Failed to query table")?;
+        .context("Query embedding does not match the table")?;
+
+    // query the vector table
+    let content = query_vector_table(
+        db,
+        vector_table,
+        query_vector,
+        whole_query,
+        file_context,
+        symbol,
+        time_window,
+    )
+    .await
+    .context("Failed to query table")?;
+
+    // the failure chunks of a spark stage or job named in the question come first
+    let content = match SparkTarget::from_question(&input_list.join(" ")) {
+        Some(target) if !whole_query => {
+            let table = db
+                .open_table(vector_table)
+                .execute()
+                .await
+                .context("Failed to open a table")?;
+            let mut failures = query_spark_failures(&table, target, time_window).await?;
+            debug!("Failure chunks of {:?}: {}", target, failures.len());
+            for chunk in content {
+                if !failures.contains(&chunk) {
+                    failures.push(chunk);
+                }
+            }
+            failures
+        }
+        _ => content,
+    };
 
     debug!("Finishes running query");
 
     Ok(content)
 }
 
+/// Reject a query embedding whose size differs from the embeddings stored in the table
+/// Arguments:
+/// - db: &mut Connection
+/// - table_name: &str
+/// - embed_model: &str model of the query embedding
+/// - query_dim: usize length of the query embedding
+///
+/// Returns:
+/// - Result<()>
+pub async fn check_query_embedding(
+    db: &mut Connection,
+    table_name: &str,
+    embed_model: &str,
+    query_dim: usize,
+) -> Result<()> {
+    let table = db
+        .open_table(table_name)
+        .execute()
+        .await
+        .context("Failed to open a table")?;
+    let table_embedding = table_embedding(&table).await?;
+    let table_model = table_embedding.embed_model.as_deref();
+
+    if table_embedding.vector_dim as usize != query_dim {
+        return Err(anyhow!(
+            "Model {} returns {} dimensional embeddings but table {} was loaded with {} which has {} dimensions, query with the same embedding model or load the table again",
+            embed_model,
+            query_dim,
+            table_name,
+            table_model.unwrap_or("an unknown model"),
+            table_embedding.vector_dim
+        ));
+    }
+    if let Some(table_model) = table_model.filter(|m| *m != embed_model) {
+        warn!(
+            "Table {} was loaded with model {}, querying with {}",
+            table_name, table_model, embed_model
+        );
+    }
+    Ok(())
+}
+
 /// Queries a vector table in the database, either fetching all content or querying the nearest vectors.
 ///
 /// # Arguments
@@ -92,6 +312,8 @@ pub async fn run_query(
 /// * `query_vector` - The vector to query against the table.
 /// * `whole_query` - If true, fetches all content from the table. If false, queries the nearest vectors.
 /// * `file_context` - If true, fetches the entire file context for the nearest vectors.
+/// * `symbol` - Exact symbol name the nearest vectors are filtered or boosted on.
+/// * `time_window` - Range of log timestamps the nearest vectors are filtered on.
 ///
 /// # Returns
 /// A `Result` containing a vector of strings representing the queried content, or an error if the operation fails.
@@ -101,6 +323,8 @@ pub async fn query_vector_table(
     query_vector: impl IntoQueryVector,
     whole_query: bool,
     file_context: bool,
+    symbol: Option<&SymbolQuery>,
+    time_window: Option<&TimeWindow>,
 ) -> Result<Vec<String>> {
     let table = db
         .open_table(table_name)
@@ -118,50 +342,158 @@ pub async fn query_vector_table(
 
         Ok(content)
     } else {
-        let stream = query_nearest_vector(query_vector, &table).await?;
+        if symbol.is_some_and(|symbol| symbol.mode == SymbolMatch::Filter) {
+            require_column(&table, "symbols", "--symbol").await?;
+        }
+        if time_window.is_some() {
+            require_column(&table, "log_timestamp", "--since and --until").await?;
+        }
+        let filters: Vec<String> = symbol
+            .filter(|symbol| symbol.mode == SymbolMatch::Filter)
+            .map(SymbolQuery::sql_filter)
+            .into_iter()
+            .chain(time_window.map(TimeWindow::sql_filter))
+            .collect();
+        let filter = Some(filters.join(" AND ")).filter(|f| !f.is_empty());
+        let stream = query_nearest_vector(query_vector, &table, filter).await?;
         batches = stream.collect::<Vec<_>>().await;
+        let mut rows = chunk_rows(&batches).context("Failed to get chunks from record batch")?;
+
+        if let Some(symbol) = symbol {
+            let (mut matched, rest): (Vec<_>, Vec<_>) = rows
+                .into_iter()
+                .partition(|row| symbol.matches(&row.symbols));
+            rows = match symbol.mode {
+                SymbolMatch::Filter => matched,
+                SymbolMatch::Boost => {
+                    // definitions of the symbol outside the nearest chunks are added after them,
+                    // tables loaded before the symbols column have none to add
+                    if !existing_columns(&table, &["symbols"]).await?.is_empty() {
+                        let stream =
+                            query_content_based_on_symbol(&table, symbol, time_window).await?;
+                        let symbol_batches = stream.collect::<Vec<_>>().await;
+                        for row in chunk_rows(&symbol_batches)
+                            .context("Failed to get chunks from record batch")?
+                        {
+                            if symbol.matches(&row.symbols) && !matched.contains(&row) {
+                                matched.push(row);
+                            }
+                        }
+                    }
+                    matched.extend(rest);
+                    matched
+                }
+            };
+            debug!(
+                "Chunks after matching symbol {}: {}",
+                symbol.name,
+                rows.len()
+            );
+        }
+
         match file_context {
             true => {
-                // Fetch the whole file context
-                let files = get_content_from_stream(&batches, "metadata")
-                    .context("Failed to get file names from metadata")?;
-
-                // files remove duplicates
-                let files_unique: Vec<String> = files
-                    .into_iter()
-                    .filter(|x| x != "NULL")
-                    .collect::<std::collections::HashSet<_>>()
-                    .into_iter()
-                    .collect();
-
-                debug!("Unique file names after deduplication: {:?}", &files_unique);
-
-                // query the content based on file names
-                let file_content = query_content_based_on_metadata(&table, files_unique)
+                // Fetch the whole file context, files are matched on their full path
+                let mut files_unique: Vec<String> = Vec::new();
+                for row in &rows {
+                    if row.path != "NULL" && !files_unique.contains(&row.path) {
+                        files_unique.push(row.path.clone());
+                    }
+                }
+
+                debug!("Unique file paths after deduplication: {:?}", &files_unique);
+
+                // chunks loaded without a path have no file to expand to
+                if files_unique.is_empty() {
+                    return Ok(rows.into_iter().map(|row| row.content).collect());
+                }
+
+                // query the content based on file paths
+                let file_content = query_content_based_on_path(&table, files_unique)
                     .await
-                    .context("Failed to query content based on file name metadata")?;
+                    .context("Failed to query content based on file path")?;
 
-                // convert the stream to a vector
+                // the chunks of a file may be spread over several batches and come back unordered
                 let file_batch = file_content.collect::<Vec<_>>().await;
-                // debug!("File Batch: {:?}", &file_batch); // add if required
-                // get the content from the record batch
-                let file_data = get_content_from_stream(&file_batch, "content")
-                    .context("Failed to get content from record batch")?;
-                // debug!("Chunk Data: {:?}", &file_data); // add if required
-                Ok(file_data)
+                let mut file_rows =
+                    chunk_rows(&file_batch).context("Failed to get chunks from record batch")?;
+                file_rows.sort_by(|a, b| (&a.path, a.chunk_number).cmp(&(&b.path, b.chunk_number)));
+                Ok(file_rows.into_iter().map(|row| row.content).collect())
             }
             false => {
                 debug!("Number of batches retrieved from query: {}", &batches.len());
-                let content = get_content_from_stream(&batches, "content")
-                    .context("Failed to get content from record batch")?;
-
-                Ok(content)
+                Ok(rows.into_iter().map(|row| row.content).collect())
             }
         }
     }
 }
 
-/// Get content from the record stream based on the column name for example "metadata" has the file names
+/// ChunkRow content of a queried chunk with the path of its file, its position in the file
+/// and its symbols
+#[derive(Debug, Clone, PartialEq)]
+struct ChunkRow {
+    content: String,
+    path: String,
+    chunk_number: i32,
+    symbols: String,
+}
+
+/// Rows of all the record batches of a query, values of missing columns e.g. of tables
+/// loaded before the column was added are `NULL`
+fn chunk_rows(batches: &[lancedb::error::Result<RecordBatch>]) -> Result<Vec<ChunkRow>> {
+    let mut rows = Vec::new();
+    for batch in batches {
+        let batch = batch
+            .as_ref()
+            .map_err(|e| anyhow!(format!("Failed to get RecordBatch: {}", e)))?;
+        let column = |name: &str| match batch.schema().column_with_name(name) {
+            Some(_) => get_column_data_from_batch(name, batch, batch.schema()),
+            None => Ok(Vec::new()),
+        };
+        let paths = column("path")?;
+        let chunk_numbers = column("chunk_number")?;
+        let symbols = column("symbols")?;
+        let value = |values: &[String], i: usize| {
+            values.get(i).cloned().unwrap_or_else(|| "NULL".to_string())
+        };
+        for (i, content) in column("content")?.into_iter().enumerate() {
+            rows.push(ChunkRow {
+                content,
+                path: value(&paths, i),
+                chunk_number: value(&chunk_numbers, i).parse().unwrap_or_default(),
+                symbols: value(&symbols, i),
+            });
+        }
+    }
+    Ok(rows)
+}
+
+/// Columns of the table among the given ones, tables loaded before a column was added lack it
+async fn existing_columns(table: &Table, columns: &[&str]) -> Result<Vec<String>> {
+    let schema = table.schema().await.context("Failed to get table schema")?;
+    Ok(columns
+        .iter()
+        .filter(|column| schema.field_with_name(column).is_ok())
+        .map(|column| column.to_string())
+        .collect())
+}
+
+/// Fail with a hint to reload the table when it lacks the column an option filters on, the
+/// filter would otherwise fail with a DataFusion schema error
+async fn require_column(table: &Table, column: &str, option: &str) -> Result<()> {
+    if existing_columns(table, &[column]).await?.is_empty() {
+        return Err(anyhow!(
+            "Table {} has no {} column, reload it to use {}",
+            table.name(),
+            column,
+            option
+        ));
+    }
+    Ok(())
+}
+
+/// Get content from the record stream based on the column name for example "metadata" has the file names,
+/// the values of every batch are returned in stream order
 /// Arguments:
 /// - batches: &Vec<lancedb::error::Result<RecordBatch>>
 /// - table_column: &str
@@ -172,6 +504,7 @@ pub fn get_content_from_stream(
     batches: &Vec<lancedb::error::Result<RecordBatch>>,
     table_column: &str,
 ) -> Result<Vec<String>> {
+    let mut content = Vec::new();
     for batch in batches {
         // to avoid moving the elements and instead borrow them,
         // iterate over references to the elements:
@@ -180,13 +513,10 @@ pub fn get_content_from_stream(
             .map_err(|e| anyhow!(format!("Failed to get RecordBatch: {}", e)))?;
         let schema = batch_ref.schema(); // Bind schema to a variable
 
-        let content = get_column_data_from_batch(table_column, batch_ref, schema);
-        if content.is_ok() {
-            return content;
-        }
+        content.extend(get_column_data_from_batch(table_column, batch_ref, schema)?);
     }
 
-    Ok(Vec::new())
+    Ok(content)
 }
 
 /// Helper function to Get list of metadata from the record batch based on the column name returns a list of chunks or file names
@@ -266,19 +596,30 @@ async fn query_all_content(table: &Table) -> Result<SendableRecordBatchStream> {
     Ok(stream)
 }
 
-/// Queries the nearest vector to the given query vector.
+/// Queries the nearest vector to the given query vector, the path and symbols are selected
+/// when the table has them.
 /// Returns a stream of record batches containing the queried data.
 /// Arguments:
 /// - query_vector: impl IntoQueryVector + Sized
 /// - table: &Table
+/// - filter: Option<String> rows searched for the nearest vectors, applied before the search
 ///
 /// Returns:
 /// - Result<SendableRecordBatchStream>
 async fn query_nearest_vector(
     query_vector: impl IntoQueryVector + Sized,
     table: &Table,
+    filter: Option<String>,
 ) -> Result<SendableRecordBatchStream> {
-    let stream: SendableRecordBatchStream = table
+    let mut columns = vec!["_distance".to_string()];
+    columns.extend(
+        existing_columns(
+            table,
+            &["chunk_number", "metadata", "path", "symbols", "content"],
+        )
+        .await?,
+    );
+    let query = table
         .query()
         .nearest_to(query_vector) // Find the nearest vectors to the query vector
         .context("Failed to select nearest vector")?
@@ -287,15 +628,13 @@ async fn query_nearest_vector(
         .refine_factor(10)
         .limit(30)
         .nprobes(40) // default is 20
-        .postfilter()
         // .only_if("_distance > 0.3 AND _distance < 1")
-        .select(lancedb::query::Select::Columns(vec![
-            "_distance".to_string(),
-            "chunk_number".to_string(),
-            "metadata".to_string(),
-            "content".to_string(),
-        ]))
-        .only_if("content IS NOT NULL")
+        .select(lancedb::query::Select::Columns(columns));
+    let query = match filter {
+        Some(filter) => query.only_if(format!("content IS NOT NULL AND {}", filter)),
+        None => query.postfilter().only_if("content IS NOT NULL"),
+    };
+    let stream: SendableRecordBatchStream = query
         .execute()
         .await
         .context("Failed to execute query and fetch records")?;
@@ -366,3 +705,116 @@ pub async fn query_content_based_on_metadata(
         .context("Failed to execute chunk based query and fetch records")?;
     Ok(stream)
 }
+
+/// Query content based on path selects all the records of the files with the given paths,
+/// files with the same name in different directories are kept apart
+/// Arguments:
+/// - table: &Table
+/// - paths: Vec<String> paths relative to the loaded directory
+///
+/// Returns:
+/// - Result<SendableRecordBatchStream>
+pub async fn query_content_based_on_path(
+    table: &Table,
+    paths: Vec<String>,
+) -> Result<SendableRecordBatchStream> {
+    // path in  ["app/src/main.rs", "vectordb/src/lib.rs"]
+
+    let stream = table
+        .query()
+        .only_if(format!(
+            "path IN ({})",
+            paths
+                .iter()
+                .map(|p| format!("'{}'", p.replace('\'', "''")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+        .select(lancedb::query::Select::Columns(vec![
+            "id".to_string(),
+            "path".to_string(),
+            "chunk_number".to_string(),
+            "content".to_string(),
+        ]))
+        .limit(1000)
+        .execute()
+        .await
+        .context("Failed to execute path based query and fetch records")?;
+    Ok(stream)
+}
+
+/// Query the failure chunks of a spark stage or job, tables loaded without the spark
+/// columns have none
+/// Arguments:
+/// - table: &Table
+/// - target: SparkTarget stage or job the chunks are selected on
+/// - time_window: Option<&TimeWindow> log timestamps the records are filtered on
+///
+/// Returns:
+/// - Result<Vec<String>> content of the failure chunks
+pub async fn query_spark_failures(
+    table: &Table,
+    target: SparkTarget,
+    time_window: Option<&TimeWindow>,
+) -> Result<Vec<String>> {
+    let schema = table.schema().await.context("Failed to get table schema")?;
+    if schema.field_with_name("spark_stage_id").is_err() {
+        return Ok(Vec::new());
+    }
+    if time_window.is_some() {
+        require_column(table, "log_timestamp", "--since and --until").await?;
+    }
+    let filter = match time_window {
+        Some(window) => format!("{} AND {}", target.sql_filter(), window.sql_filter()),
+        None => target.sql_filter(),
+    };
+    let batches = table
+        .query()
+        .only_if(filter)
+        .select(lancedb::query::Select::Columns(vec!["content".to_string()]))
+        .limit(100)
+        .execute()
+        .await
+        .context("Failed to execute spark failure query and fetch records")?
+        .collect::<Vec<_>>()
+        .await;
+    get_content_from_stream(&batches, "content")
+}
+
+/// Query content based on symbol selects the records whose symbols may include the name,
+/// `SymbolQuery::matches` checks them exactly
+/// Arguments:
+/// - table: &Table
+/// - symbol: &SymbolQuery
+/// - time_window: Option<&TimeWindow> log timestamps the records are filtered on
+///
+/// Returns:
+/// - Result<SendableRecordBatchStream>
+pub async fn query_content_based_on_symbol(
+    table: &Table,
+    symbol: &SymbolQuery,
+    time_window: Option<&TimeWindow>,
+) -> Result<SendableRecordBatchStream> {
+    require_column(table, "symbols", "--symbol").await?;
+    if time_window.is_some() {
+        require_column(table, "log_timestamp", "--since and --until").await?;
+    }
+    let filter = match time_window {
+        Some(window) => format!("{} AND {}", symbol.sql_filter(), window.sql_filter()),
+        None => symbol.sql_filter(),
+    };
+    let stream = table
+        .query()
+        .only_if(filter)
+        .select(lancedb::query::Select::Columns(vec![
+            "path".to_string(),
+            "chunk_number".to_string(),
+            "symbols".to_string(),
+            "content".to_string(),
+        ]))
+        .limit(1000)
+        .execute()
+        .await
+        .context("Failed to execute symbol based query and fetch records")?;
+    Ok(stream)
+}
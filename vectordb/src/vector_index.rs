@@ -1,8 +1,8 @@
-use lancedb::Connection;
+use anyhow::Context;
 use configs::constants::LANCEDB_DISTANCE_FN;
-use lancedb::index::Index;
 use lancedb::index::scalar::FtsIndexBuilder;
-use anyhow::Context;
+use lancedb::index::Index;
+use lancedb::Connection;
 
 /// Create an index on the embedding column
 /// IVF_PQ Index: LanceDB also supports the IVF_PQ (Inverted File with Product Quantization) index,
@@ -95,4 +95,4 @@ pub async fn create_inverted_index(
     );
 
     anyhow::Ok(())
-}
\ No newline at end of file
+}
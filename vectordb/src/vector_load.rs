@@ -1,17 +1,16 @@
+use crate::vector_schema::{TableSchema, METADATA_COLUMNS};
 use anyhow::Result;
 use anyhow::{Context, Ok};
 use arrow::array::{FixedSizeListArray, StringArray, TimestampSecondArray};
 use arrow_array::types::Float32Type;
-use arrow_array::{Int32Array, RecordBatch, RecordBatchIterator};
-use arrow_schema::{Schema};
-use configs::constants::{VECTOR_DB_DIM_SIZE};
+use arrow_array::{ArrayRef, Int32Array, RecordBatch, RecordBatchIterator};
+use arrow_schema::Schema;
 use embedder::embed_config::{EmbedRequest, EmbedResponse};
 use lancedb::{Connection, Table};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use std::vec;
 use tokio::sync::RwLock;
-use crate::vector_schema::TableSchema;
 
 #[allow(dead_code)]
 /// Insert an empty batch into the database
@@ -20,7 +19,7 @@ use crate::vector_schema::TableSchema;
 /// - table_schema: &TableSchema
 /// - table_name: &str
 /// - arrow_schema: Arc<Schema>
-/// 
+///
 /// Returns:
 /// - Result<(), Box<dyn Error>>
 async fn insert_empty_batch(
@@ -54,7 +53,7 @@ async fn insert_empty_batch(
 /// - table_schema: &TableSchema
 /// - records: RecordBatch (Arrow)
 /// - table: Table (lancedb)
-/// 
+///
 /// Returns:
 /// - Result<(), Box<dyn Error>>
 pub async fn insert_embeddings(
@@ -62,12 +61,37 @@ pub async fn insert_embeddings(
     records: RecordBatch,
     table: Table,
 ) -> Result<()> {
+    insert_record_batches(table_schema, vec![records], table).await
+}
+
+/// Insert several record batches into the database with a single write
+/// Arguments:
+/// - table_schema: &TableSchema
+/// - records: Vec<RecordBatch> (Arrow)
+/// - table: Table (lancedb)
+///
+/// Returns:
+/// - Result<(), Box<dyn Error>>
+pub async fn insert_record_batches(
+    table_schema: &TableSchema,
+    records: Vec<RecordBatch>,
+    table: Table,
+) -> Result<()> {
+    if records.is_empty() {
+        return Ok(());
+    }
     let arrow_schema = Arc::new(table_schema.create_schema());
-    let record_iter = vec![records].into_iter().map(std::result::Result::Ok);
+    let record_iter = records.into_iter().map(std::result::Result::Ok);
     let record_batch = RecordBatchIterator::new(record_iter, arrow_schema);
 
-    let mut writer =
-        table.merge_insert(&["content", "metadata", "vector", "model", "chunk_number"]);
+    let mut writer = table.merge_insert(&[
+        "content",
+        "path",
+        "metadata",
+        "vector",
+        "model",
+        "chunk_number",
+    ]);
     // add merge options to writer
     writer.when_not_matched_insert_all();
 
@@ -89,7 +113,7 @@ pub async fn insert_embeddings(
 /// - request: Arc<RwLock<EmbedRequest>>
 /// - response: EmbedResponse
 /// - table_schema: &TableSchema
-/// 
+///
 /// Returns:
 /// - Result<RecordBatch, Box<dyn Error>> - The RecordBatch (Arrow)
 pub async fn create_record_batch(
@@ -101,6 +125,20 @@ pub async fn create_record_batch(
     if response.embeddings.is_empty() {
         return Err(anyhow::Error::msg("No embeddings found in the response"));
     }
+    let vector_dim = table_schema.vector_dim();
+    if let Some(embedding) = response
+        .embeddings
+        .iter()
+        .find(|e| e.len() != vector_dim as usize)
+    {
+        return Err(anyhow::anyhow!(
+            "Embedding from model {} has {} dimensions but table {} stores {}",
+            response.model,
+            embedding.len(),
+            table_schema.name,
+            vector_dim
+        ));
+    }
     let request = request.read().await;
 
     // let num_embeddings = response.embeddings.len();
@@ -144,9 +182,8 @@ pub async fn create_record_batch(
         })
         .collect(); // Collect into Vec<Option<Vec<Option<f32>>>>
 
-    let embedding_array = Arc::new(
-        FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(vectors, VECTOR_DB_DIM_SIZE),
-    );
+    let embedding_array =
+        Arc::new(FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(vectors, vector_dim));
 
     let created_at_array = Arc::new(TimestampSecondArray::from_iter_values((0..len).map(|_| {
         SystemTime::now()
@@ -159,20 +196,23 @@ pub async fn create_record_batch(
         (0..len).map(|_| request.chunk_number.unwrap_or(0)),
     ));
 
-    let record_batch = RecordBatch::try_new(
-        Arc::new(table_schema.create_schema()),
-        vec![
-            id_array,
-            content_array,
-            metadata_array,
-            embedding_array,
-            model_array,
-            created_at_array,
-            chunk_number_array,
-        ],
-    )
-    .context("Failed to create a Embedding Records")?;
+    let mut columns: Vec<ArrayRef> = vec![
+        id_array,
+        content_array,
+        metadata_array,
+        embedding_array,
+        model_array,
+        created_at_array,
+        chunk_number_array,
+    ];
+    columns.extend(
+        METADATA_COLUMNS
+            .iter()
+            .map(|column| (column.values)(&request.chunk_metadata, len)),
+    );
+
+    let record_batch = RecordBatch::try_new(Arc::new(table_schema.create_schema()), columns)
+        .context("Failed to create a Embedding Records")?;
 
     Ok(record_batch)
 }
-
@@ -1,11 +1,191 @@
-use lancedb::Connection;
-use std::sync::Arc;
-use arrow_schema::{DataType, Field, Schema as ArrowSchema, TimeUnit};
-use arrow_array::{FixedSizeListArray, Int32Array, RecordBatch, StringArray, TimestampSecondArray};
+use anyhow::Context;
 use arrow_array::types::Float32Type;
-use configs::constants::VECTOR_DB_DIM_SIZE;
+use arrow_array::{
+    new_null_array, ArrayRef, FixedSizeListArray, Int32Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray, TimestampSecondArray,
+};
+use arrow_schema::{DataType, Field, Schema as ArrowSchema, TimeUnit};
+use embedder::file_loader::ChunkMetadata;
+use embedder::symbols::format_symbols;
+use lancedb::{Connection, Table};
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::SystemTime;
-use anyhow::Context;
+
+/// Table metadata keys recording the embeddings a table was loaded with
+pub const EMBEDDING_DIM_KEY: &str = "embedding_dim";
+pub const EMBEDDING_MODEL_KEY: &str = "embedding_model";
+
+/// MetadataColumn a nullable column filled from the `ChunkMetadata` of a chunk, the schema,
+/// the empty batch and the record batches are all built from `METADATA_COLUMNS`
+pub struct MetadataColumn {
+    pub name: &'static str,
+    pub data_type: DataType,
+    /// Array of `len` rows holding the value of the chunk
+    pub values: fn(&ChunkMetadata, usize) -> ArrayRef,
+}
+
+/// Columns after `chunk_number` in table order
+pub static METADATA_COLUMNS: [MetadataColumn; 22] = [
+    // Markdown heading path of the chunk e.g. `Usage > Commands`
+    MetadataColumn {
+        name: "heading",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.heading.clone(), len),
+    },
+    // Comma separated languages of the fenced code blocks in the chunk
+    MetadataColumn {
+        name: "code_languages",
+        data_type: DataType::Utf8,
+        values: |m, len| {
+            let languages = Some(m.code_languages.join(",")).filter(|l| !l.is_empty());
+            strings(languages, len)
+        },
+    },
+    // Page number of the chunk in a PDF document
+    MetadataColumn {
+        name: "page",
+        data_type: DataType::Int32,
+        values: |m, len| int32s(m.page, len),
+    },
+    // Title of the html page of the chunk
+    MetadataColumn {
+        name: "title",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.title.clone(), len),
+    },
+    // Canonical URL of the html page of the chunk
+    MetadataColumn {
+        name: "url",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.url.clone(), len),
+    },
+    // Index of the notebook cell of the chunk
+    MetadataColumn {
+        name: "cell_index",
+        data_type: DataType::Int32,
+        values: |m, len| int32s(m.cell_index, len),
+    },
+    // Type of the notebook cell of the chunk, `code`, `markdown` or `raw`
+    MetadataColumn {
+        name: "cell_type",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.cell_type.clone(), len),
+    },
+    // Path of the file of the chunk relative to the loaded directory
+    MetadataColumn {
+        name: "path",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.path.clone(), len),
+    },
+    // Language detected for the file of the chunk
+    MetadataColumn {
+        name: "language",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.language.clone(), len),
+    },
+    // First line of the chunk in its file, numbered from 1
+    MetadataColumn {
+        name: "start_line",
+        data_type: DataType::Int32,
+        values: |m, len| int32s(m.start_line, len),
+    },
+    // Last line of the chunk in its file
+    MetadataColumn {
+        name: "end_line",
+        data_type: DataType::Int32,
+        values: |m, len| int32s(m.end_line, len),
+    },
+    // Byte offset of the chunk in its file
+    MetadataColumn {
+        name: "byte_start",
+        data_type: DataType::Int64,
+        values: |m, len| int64s(m.byte_start, len),
+    },
+    // Byte offset after the end of the chunk in its file
+    MetadataColumn {
+        name: "byte_end",
+        data_type: DataType::Int64,
+        values: |m, len| int64s(m.byte_end, len),
+    },
+    // Comma separated definitions around and inside a code chunk e.g. `impl:FileChunk,function:new`
+    MetadataColumn {
+        name: "symbols",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(format_symbols(&m.symbols), len),
+    },
+    // Key path of a toml, yaml or json chunk e.g. `workspace.dependencies.lancedb`
+    MetadataColumn {
+        name: "key_path",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.key_path.clone(), len),
+    },
+    // Timestamp of the first entry of a log chunk
+    MetadataColumn {
+        name: "log_timestamp",
+        data_type: DataType::Timestamp(TimeUnit::Millisecond, None),
+        values: |m, len| {
+            Arc::new(TimestampMillisecondArray::from_iter(std::iter::repeat_n(
+                m.log_timestamp,
+                len,
+            )))
+        },
+    },
+    // Most severe level of the entries of a log chunk e.g. `ERROR`
+    MetadataColumn {
+        name: "log_level",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.log_level.clone(), len),
+    },
+    // Id of the spark application of an event log chunk
+    MetadataColumn {
+        name: "spark_app_id",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.spark_app_id.clone(), len),
+    },
+    // Spark job that failed or that the failed stage belongs to
+    MetadataColumn {
+        name: "spark_job_id",
+        data_type: DataType::Int32,
+        values: |m, len| int32s(m.spark_job_id, len),
+    },
+    // Spark stage that failed, null for the chunk of a failed job
+    MetadataColumn {
+        name: "spark_stage_id",
+        data_type: DataType::Int32,
+        values: |m, len| int32s(m.spark_stage_id, len),
+    },
+    // Attempt of the failed spark stage
+    MetadataColumn {
+        name: "spark_stage_attempt",
+        data_type: DataType::Int32,
+        values: |m, len| int32s(m.spark_stage_attempt, len),
+    },
+    // First line of the failure reason of a spark stage or job
+    MetadataColumn {
+        name: "failure_reason",
+        data_type: DataType::Utf8,
+        values: |m, len| strings(m.failure_reason.clone(), len),
+    },
+];
+
+fn strings(value: Option<String>, len: usize) -> ArrayRef {
+    Arc::new(StringArray::from_iter(std::iter::repeat_n(value, len)))
+}
+
+fn int32s(value: Option<u32>, len: usize) -> ArrayRef {
+    Arc::new(Int32Array::from_iter(std::iter::repeat_n(
+        value.map(|v| v as i32),
+        len,
+    )))
+}
+
+fn int64s(value: Option<u64>, len: usize) -> ArrayRef {
+    Arc::new(Int64Array::from_iter(std::iter::repeat_n(
+        value.map(|v| v as i64),
+        len,
+    )))
+}
 
 #[derive(Debug, Clone)]
 pub struct TableSchema {
@@ -17,10 +197,26 @@ pub struct TableSchema {
     pub vector: Arc<Field>,
     pub created_at: Arc<Field>,
     pub chunk_number: Arc<Field>,
+    /// Embedding model stored in the table metadata
+    pub embed_model: Option<String>,
 }
 
 impl TableSchema {
-    pub fn new(table_name: &String) -> Self {
+    /// Table with the configured `vector_dim`
+    pub fn new(table_name: &str) -> Self {
+        let vector_dim = configs::settings::settings().vector_dim.value;
+        TableSchema::with_embedding(table_name, vector_dim, None)
+    }
+
+    /// Table sized for the embeddings of a model
+    /// Arguments:
+    /// - table_name: &str
+    /// - vector_dim: i32 output dimension of the model
+    /// - embed_model: Option<&str> recorded in the table metadata
+    ///
+    /// Returns:
+    /// - TableSchema
+    pub fn with_embedding(table_name: &str, vector_dim: i32, embed_model: Option<&str>) -> Self {
         TableSchema {
             name: table_name.to_string(),
             id: Arc::new(Field::new("id", DataType::Int32, false)),
@@ -30,7 +226,7 @@ impl TableSchema {
                 "vector",
                 DataType::FixedSizeList(
                     Arc::new(Field::new("item", DataType::Float32, true)),
-                    VECTOR_DB_DIM_SIZE,
+                    vector_dim,
                 ),
                 true,
             )),
@@ -41,19 +237,42 @@ impl TableSchema {
                 false,
             )),
             chunk_number: Arc::new(Field::new("chunk_number", DataType::Int32, true)),
+            embed_model: embed_model.map(|m| m.to_string()),
         }
     }
 
     pub fn create_schema(&self) -> ArrowSchema {
-        ArrowSchema::new(vec![
-            Arc::clone(&self.id),
-            Arc::clone(&self.content),
-            Arc::clone(&self.metadata),
-            Arc::clone(&self.vector),
-            Arc::clone(&self.model),
-            Arc::clone(&self.created_at),
-            Arc::clone(&self.chunk_number),
-        ])
+        let mut metadata = HashMap::new();
+        metadata.insert(EMBEDDING_DIM_KEY.to_string(), self.vector_dim().to_string());
+        if let Some(model) = &self.embed_model {
+            metadata.insert(EMBEDDING_MODEL_KEY.to_string(), model.clone());
+        }
+        let fields = [
+            &self.id,
+            &self.content,
+            &self.metadata,
+            &self.vector,
+            &self.model,
+            &self.created_at,
+            &self.chunk_number,
+        ]
+        .into_iter()
+        .cloned()
+        .chain(
+            METADATA_COLUMNS
+                .iter()
+                .map(|column| Arc::new(Field::new(column.name, column.data_type.clone(), true))),
+        )
+        .collect::<Vec<_>>();
+        ArrowSchema::new_with_metadata(fields, metadata)
+    }
+
+    /// Size of the fixed size list in the vector column
+    pub fn vector_dim(&self) -> i32 {
+        match self.vector.data_type() {
+            DataType::FixedSizeList(_, size) => *size,
+            _ => 0,
+        }
     }
 
     fn get_table_name(&self) -> &str {
@@ -68,26 +287,30 @@ impl TableSchema {
     /// Returns:
     /// - Result<RecordBatch> - The RecordBatch (Arrow)
     pub fn empty_batch(&self) -> anyhow::Result<RecordBatch> {
-        RecordBatch::try_new(
-            Arc::new(self.create_schema()),
-            vec![
-                Arc::new(Int32Array::from_iter_values(0..256)),
-                Arc::new(StringArray::from_iter_values((0..256).map(|_| ""))),
-                Arc::new(StringArray::from_iter_values((0..256).map(|_| ""))),
-                Arc::new(
-                    FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
-                        (0..256).map(|_| Some(vec![Some(1.0); VECTOR_DB_DIM_SIZE as usize])),
-                        VECTOR_DB_DIM_SIZE,
-                    ),
+        let vector_dim = self.vector_dim();
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(Int32Array::from_iter_values(0..256)),
+            Arc::new(StringArray::from_iter_values((0..256).map(|_| ""))),
+            Arc::new(StringArray::from_iter_values((0..256).map(|_| ""))),
+            Arc::new(
+                FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                    (0..256).map(|_| Some(vec![Some(1.0); vector_dim as usize])),
+                    vector_dim,
                 ),
-                Arc::new(StringArray::from_iter_values((0..256).map(|_| ""))),
-                Arc::new(TimestampSecondArray::from_iter_values((0..256).map(|_| {
-                    SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs() as i64
-                }))),
-                Arc::new(Int32Array::from_iter_values((0..256).map(|_| 0))),
-            ],
-        )
-        .context("Failed to create a RecordBatch")
+            ),
+            Arc::new(StringArray::from_iter_values((0..256).map(|_| ""))),
+            Arc::new(TimestampSecondArray::from_iter_values((0..256).map(|_| {
+                SystemTime::UNIX_EPOCH.elapsed().unwrap().as_secs() as i64
+            }))),
+            Arc::new(Int32Array::from_iter_values((0..256).map(|_| 0))),
+        ];
+        columns.extend(
+            METADATA_COLUMNS
+                .iter()
+                .map(|column| new_null_array(&column.data_type, 256)),
+        );
+        RecordBatch::try_new(Arc::new(self.create_schema()), columns)
+            .context("Failed to create a RecordBatch")
     }
 }
 
@@ -98,7 +321,10 @@ impl TableSchema {
 ///
 /// Returns:
 /// - Result<(), Box<dyn Error>>
-pub async fn create_lance_table(db: &mut Connection, table_schema: &TableSchema) -> anyhow::Result<()> {
+pub async fn create_lance_table(
+    db: &mut Connection,
+    table_schema: &TableSchema,
+) -> anyhow::Result<()> {
     let table_name = table_schema.get_table_name();
     let all_tables = db.table_names().execute().await?;
     if all_tables.contains(&table_name.to_string()) {
@@ -116,4 +342,41 @@ pub async fn create_lance_table(db: &mut Connection, table_schema: &TableSchema)
     log::debug!("Table created successfully");
 
     anyhow::Ok(())
-}
\ No newline at end of file
+}
+
+/// TableEmbedding the vector size and embedding model of an existing table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableEmbedding {
+    pub vector_dim: i32,
+    pub embed_model: Option<String>,
+}
+
+/// Read the embedding dimension and model from the table metadata, tables loaded
+/// before the metadata was recorded fall back to the size of the vector column
+/// Arguments:
+/// - table: &Table
+///
+/// Returns:
+/// - Result<TableEmbedding>
+pub async fn table_embedding(table: &Table) -> anyhow::Result<TableEmbedding> {
+    let schema = table
+        .schema()
+        .await
+        .context("Failed to read the table schema")?;
+    let metadata = schema.metadata();
+
+    let vector_dim = match metadata.get(EMBEDDING_DIM_KEY) {
+        Some(dim) => dim
+            .parse()
+            .with_context(|| format!("Invalid {} in table metadata: {}", EMBEDDING_DIM_KEY, dim))?,
+        None => match schema.field_with_name("vector")?.data_type() {
+            DataType::FixedSizeList(_, size) => *size,
+            other => anyhow::bail!("Unexpected type of the vector column: {:?}", other),
+        },
+    };
+
+    Ok(TableEmbedding {
+        vector_dim,
+        embed_model: metadata.get(EMBEDDING_MODEL_KEY).cloned(),
+    })
+}
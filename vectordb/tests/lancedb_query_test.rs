@@ -9,16 +9,26 @@ mod test_lancedb_query {
     use configs::constants::EMBEDDING_MODEL;
     use configs::constants::VECTOR_DB_DIM_SIZE;
     use embedder::embed_config::{EmbedRequest, EmbedResponse};
+    use embedder::file_loader::ChunkMetadata;
+    use embedder::symbols::Symbol;
     use futures::StreamExt;
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
+    use arrow_array::types::Float32Type;
+    use arrow_array::{
+        FixedSizeListArray, Int32Array, RecordBatch, RecordBatchIterator, StringArray,
+        TimestampSecondArray,
+    };
+    use arrow_schema::{DataType, Field, Schema as ArrowSchema, TimeUnit};
+
     use vectordb::vector_load::{
         create_record_batch,
         insert_embeddings,
     };
     use vectordb::query;
     use vectordb::query::get_content_from_stream;
+    use vectordb::query::{SparkTarget, SymbolMatch, SymbolQuery, TimeWindow};
     use vectordb::vector_index::{create_index_on_embedding, create_inverted_index};
     use vectordb::vector_schema::{create_lance_table, TableSchema};
 
@@ -32,7 +42,9 @@ mod test_lancedb_query {
 
         vectordb::run_embedding_pipeline(
             path,
-            100,
+            &vectordb::ChunkPolicy::new(vectordb::ChunkSpec::new(100, vectordb::Sizer::Characters)),
+            &vectordb::WalkOptions::default(),
+            &vectordb::EmbedOptions::default(),
             "ollama",
             CHAT_API_URL,
             CHAT_API_KEY,
@@ -47,7 +59,7 @@ mod test_lancedb_query {
 
     // Helper function to create a test table schema
     fn create_test_table_schema(table_name: &str) -> TableSchema {
-        TableSchema::new(&table_name.to_string())
+        TableSchema::new(table_name)
     }
 
     async fn get_query_vector(input_list: &[String]) -> Result<Vec<f32>> {
@@ -66,7 +78,7 @@ mod test_lancedb_query {
 
         let embed_url = query_request_arc.read().await.get_embed_url();
 
-        let query_response = embedder::fetch_embedding(&query_request_arc, &https_client)
+        let query_response = embedder::fetch_embedding(&query_request_arc, &https_client, None)
             .await
             .with_context(|| format!("Failed to fetch embedding response from {}", &embed_url))?;
 
@@ -115,11 +127,13 @@ mod test_lancedb_query {
         let request = Arc::new(RwLock::new(EmbedRequest {
             provider: "test-provider".to_string(),
             api_url: "http://localhost:8000".to_string(),
-            api_key: "test-key".to_string(),
+            api_key: "test-key".into(),
             input: input_texts,
             model: "test-model".to_string(),
             metadata: Some("test-dir".to_string()),
             chunk_number: Some(0),
+            dimensions: None,
+            chunk_metadata: ChunkMetadata::default(),
         }));
 
         let response = EmbedResponse {
@@ -152,11 +166,13 @@ mod test_lancedb_query {
         let request = Arc::new(RwLock::new(EmbedRequest {
             provider: "test-provider".to_string(),
             api_url: "http://localhost:8000".to_string(),
-            api_key: "test-key".to_string(),
+            api_key: "test-key".into(),
             input: vec!["test content".to_string()],
             model: "test-model".to_string(),
             metadata: Some("test-dir".to_string()),
             chunk_number: Some(0),
+            dimensions: None,
+            chunk_metadata: ChunkMetadata::default(),
         }));
 
         let response = EmbedResponse {
@@ -169,7 +185,7 @@ mod test_lancedb_query {
             .unwrap();
 
         assert_eq!(record_batch.num_rows(), 1);
-        assert_eq!(record_batch.num_columns(), 7);
+        assert_eq!(record_batch.num_columns(), 29);
 
         let column_name = "metadata";
         let column_data =
@@ -220,6 +236,69 @@ mod test_lancedb_query {
         }
     }
 
+    #[tokio::test]
+    async fn query_content_based_on_path_test() {
+        let table_name = "test_table_query_content_path";
+        let mut db = create_test_connection(TEST_DB_URI)
+            .await
+            .expect("Failed to create test connection");
+        let table_schema = create_test_table_schema(table_name);
+        create_lance_table(&mut db, &table_schema)
+            .await
+            .expect("Failed to create table");
+
+        // two files named lib.rs in different crates
+        let mut batches = Vec::new();
+        for (i, path) in ["app/src/lib.rs", "vectordb/src/lib.rs"].iter().enumerate() {
+            let request = Arc::new(RwLock::new(EmbedRequest {
+                provider: "test-provider".to_string(),
+                api_url: "http://localhost:8000".to_string(),
+                api_key: "test-key".into(),
+                input: vec![format!("content of {}", path)],
+                model: "test-model".to_string(),
+                metadata: Some("lib.rs".to_string()),
+                chunk_number: Some(0),
+                dimensions: None,
+                chunk_metadata: ChunkMetadata {
+                    path: Some(path.to_string()),
+                    language: Some("rust".to_string()),
+                    start_line: Some(1),
+                    end_line: Some(3),
+                    byte_start: Some(0),
+                    byte_end: Some(42),
+                    ..Default::default()
+                },
+            }));
+            let response = EmbedResponse {
+                model: "test-model".to_string(),
+                embeddings: vec![vec![i as f32; VECTOR_DB_DIM_SIZE as usize]],
+            };
+            batches.push(
+                create_record_batch(i as i32, request, response, &table_schema)
+                    .await
+                    .expect("Failed to create record batch"),
+            );
+        }
+        let table = db
+            .open_table(table_name)
+            .execute()
+            .await
+            .expect("Failed to open table");
+        vectordb::vector_load::insert_record_batches(&table_schema, batches, table.clone())
+            .await
+            .expect("Failed to insert records");
+
+        let file_content =
+            query::query_content_based_on_path(&table, vec!["vectordb/src/lib.rs".to_string()])
+                .await
+                .expect("Failed to query content based on path");
+        let file_batch = file_content.collect::<Vec<_>>().await;
+        let file_data = get_content_from_stream(&file_batch, "content")
+            .expect("Failed to get content from record batch");
+
+        assert_eq!(file_data, vec!["content of vectordb/src/lib.rs"]);
+    }
+
     #[tokio::test]
     async fn get_content_from_stream_test() {
         // table: &Table,
@@ -253,6 +332,458 @@ mod test_lancedb_query {
         assert_eq!(file_data.len(), 120);
     }
 
+    #[test]
+    fn get_content_from_stream_batches_test() {
+        let schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "content",
+            DataType::Utf8,
+            false,
+        )]));
+        let batch = |content: Vec<&str>| {
+            RecordBatch::try_new(schema.clone(), vec![Arc::new(StringArray::from(content))])
+                .map_err(lancedb::error::Error::from)
+        };
+        let batches = vec![batch(vec!["first", "second"]), batch(vec!["third"])];
+
+        let content = get_content_from_stream(&batches, "content")
+            .expect("Failed to get content from record batch");
+        assert_eq!(content, vec!["first", "second", "third"]);
+    }
+
+    #[tokio::test]
+    async fn query_vector_table_symbol_test() {
+        let table_name = "test_table_query_symbol";
+        let db = &mut create_test_connection(TEST_DB_URI)
+            .await
+            .expect("Failed to create test connection");
+        let table_schema = create_test_table_schema(table_name);
+        create_lance_table(db, &table_schema)
+            .await
+            .expect("Failed to create table");
+
+        // the second definition only shares a prefix with the symbol
+        let chunks = [
+            ("fn run_embedding_pipeline() {}", "run_embedding_pipeline"),
+            (
+                "fn run_embedding_pipeline_batches() {}",
+                "run_embedding_pipeline_batches",
+            ),
+            ("struct Other;", "Other"),
+        ];
+        let one_hot = |i: usize| {
+            let mut vector = vec![0.0; VECTOR_DB_DIM_SIZE as usize];
+            vector[i] = 1.0;
+            vector
+        };
+        let mut batches = Vec::new();
+        for (i, (content, name)) in chunks.iter().enumerate() {
+            let request = Arc::new(RwLock::new(EmbedRequest {
+                provider: "test-provider".to_string(),
+                api_url: "http://localhost:8000".to_string(),
+                api_key: "test-key".into(),
+                input: vec![content.to_string()],
+                model: "test-model".to_string(),
+                metadata: Some("lib.rs".to_string()),
+                chunk_number: Some(i as i32),
+                dimensions: None,
+                chunk_metadata: ChunkMetadata {
+                    path: Some("src/lib.rs".to_string()),
+                    symbols: vec![Symbol::new("function", name)],
+                    ..Default::default()
+                },
+            }));
+            let response = EmbedResponse {
+                model: "test-model".to_string(),
+                embeddings: vec![one_hot(i)],
+            };
+            batches.push(
+                create_record_batch(i as i32, request, response, &table_schema)
+                    .await
+                    .expect("Failed to create record batch"),
+            );
+        }
+        let table = db
+            .open_table(table_name)
+            .execute()
+            .await
+            .expect("Failed to open table");
+        vectordb::vector_load::insert_record_batches(&table_schema, batches, table)
+            .await
+            .expect("Failed to insert records");
+
+        let query = |symbol: Option<SymbolQuery>| {
+            let mut db = db.clone();
+            async move {
+                query::query_vector_table(
+                    &mut db,
+                    table_name,
+                    one_hot(2),
+                    false,
+                    false,
+                    symbol.as_ref(),
+                    None,
+                )
+                .await
+                .expect("Failed to query vector table")
+            }
+        };
+
+        let nearest = query(None).await;
+        assert_eq!(nearest.len(), 3);
+        assert_eq!(nearest[0], "struct Other;");
+
+        let filter = SymbolQuery::new("run_embedding_pipeline", SymbolMatch::Filter);
+        let filtered = query(Some(filter)).await;
+        assert_eq!(filtered, vec!["fn run_embedding_pipeline() {}"]);
+
+        let boost = SymbolQuery::new("run_embedding_pipeline", SymbolMatch::Boost);
+        let boosted = query(Some(boost)).await;
+        assert_eq!(boosted.len(), 3);
+        assert_eq!(boosted[0], "fn run_embedding_pipeline() {}");
+        assert_eq!(boosted[1], "struct Other;");
+
+        assert!(SymbolMatch::parse_mode("rank").is_err());
+    }
+
+    #[tokio::test]
+    async fn query_vector_table_time_window_test() {
+        let table_name = "test_table_query_time_window";
+        let db = &mut create_test_connection(TEST_DB_URI)
+            .await
+            .expect("Failed to create test connection");
+        let table_schema = create_test_table_schema(table_name);
+        create_lance_table(db, &table_schema)
+            .await
+            .expect("Failed to create table");
+
+        // 2024-05-01T12:00:00Z, an hour apart, the last chunk is not a log
+        let noon = 1_714_564_800_000;
+        let chunks = [
+            ("INFO started", Some(noon)),
+            ("ERROR failed", Some(noon + 3_600_000)),
+            ("fn main() {}", None),
+        ];
+        let mut batches = Vec::new();
+        for (i, (content, timestamp)) in chunks.iter().enumerate() {
+            let request = Arc::new(RwLock::new(EmbedRequest {
+                provider: "test-provider".to_string(),
+                api_url: "http://localhost:8000".to_string(),
+                api_key: "test-key".into(),
+                input: vec![content.to_string()],
+                model: "test-model".to_string(),
+                metadata: Some("app.log".to_string()),
+                chunk_number: Some(i as i32),
+                dimensions: None,
+                chunk_metadata: ChunkMetadata {
+                    path: Some("app.log".to_string()),
+                    log_timestamp: *timestamp,
+                    ..Default::default()
+                },
+            }));
+            let response = EmbedResponse {
+                model: "test-model".to_string(),
+                embeddings: vec![vec![1.0; VECTOR_DB_DIM_SIZE as usize]],
+            };
+            batches.push(
+                create_record_batch(i as i32, request, response, &table_schema)
+                    .await
+                    .expect("Failed to create record batch"),
+            );
+        }
+        let table = db
+            .open_table(table_name)
+            .execute()
+            .await
+            .expect("Failed to open table");
+        vectordb::vector_load::insert_record_batches(&table_schema, batches, table)
+            .await
+            .expect("Failed to insert records");
+
+        let query = |window: Option<TimeWindow>| {
+            let mut db = db.clone();
+            async move {
+                query::query_vector_table(
+                    &mut db,
+                    table_name,
+                    vec![1.0; VECTOR_DB_DIM_SIZE as usize],
+                    false,
+                    false,
+                    None,
+                    window.as_ref(),
+                )
+                .await
+                .expect("Failed to query vector table")
+            }
+        };
+
+        assert_eq!(query(None).await.len(), 3);
+        let since = TimeWindow::parse_window(Some("2024-05-01T12:30:00Z"), None).unwrap();
+        assert_eq!(query(since).await, vec!["ERROR failed"]);
+        let until = TimeWindow::parse_window(None, Some("2024-05-01 13:00:00")).unwrap();
+        assert_eq!(query(until).await, vec!["INFO started"]);
+
+        assert_eq!(TimeWindow::parse_window(None, None).unwrap(), None);
+        assert!(TimeWindow::parse_window(Some("soon"), None).is_err());
+        assert!(TimeWindow::parse_window(Some("2024-05-02"), Some("2024-05-01")).is_err());
+    }
+
+    #[tokio::test]
+    async fn query_vector_table_file_context_test() {
+        let table_name = "test_table_query_file_context";
+        let db = &mut create_test_connection(TEST_DB_URI)
+            .await
+            .expect("Failed to create test connection");
+        let table_schema = create_test_table_schema(table_name);
+        create_lance_table(db, &table_schema)
+            .await
+            .expect("Failed to create table");
+        let table = db
+            .open_table(table_name)
+            .execute()
+            .await
+            .expect("Failed to open table");
+
+        // every insert is a fragment of its own, the chunks of main.rs are inserted out of order
+        let chunks = [
+            ("src/main.rs", 2, "main chunk 2"),
+            ("src/other.rs", 0, "other chunk 0"),
+            ("src/main.rs", 0, "main chunk 0"),
+            ("src/main.rs", 1, "main chunk 1"),
+        ];
+        for (i, (path, chunk_number, content)) in chunks.iter().enumerate() {
+            let request = Arc::new(RwLock::new(EmbedRequest {
+                provider: "test-provider".to_string(),
+                api_url: "http://localhost:8000".to_string(),
+                api_key: "test-key".into(),
+                input: vec![content.to_string()],
+                model: "test-model".to_string(),
+                metadata: Some(path.rsplit('/').next().unwrap().to_string()),
+                chunk_number: Some(*chunk_number),
+                dimensions: None,
+                chunk_metadata: ChunkMetadata {
+                    path: Some(path.to_string()),
+                    ..Default::default()
+                },
+            }));
+            let response = EmbedResponse {
+                model: "test-model".to_string(),
+                embeddings: vec![vec![1.0; VECTOR_DB_DIM_SIZE as usize]],
+            };
+            let batch = create_record_batch(i as i32, request, response, &table_schema)
+                .await
+                .expect("Failed to create record batch");
+            vectordb::vector_load::insert_record_batches(&table_schema, vec![batch], table.clone())
+                .await
+                .expect("Failed to insert records");
+        }
+
+        let file_data = query::query_vector_table(
+            db,
+            table_name,
+            vec![1.0; VECTOR_DB_DIM_SIZE as usize],
+            false,
+            true,
+            None,
+            None,
+        )
+        .await
+        .expect("Failed to query vector table");
+        assert_eq!(
+            file_data,
+            vec![
+                "main chunk 0",
+                "main chunk 1",
+                "main chunk 2",
+                "other chunk 0"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn query_vector_table_baseline_table_test() {
+        // tables loaded before the path, line and format columns were added have 7 columns
+        let table_name = "test_table_query_baseline";
+        let db = &mut create_test_connection(TEST_DB_URI)
+            .await
+            .expect("Failed to create test connection");
+        let _ = db.drop_table(table_name).await;
+        let dim = VECTOR_DB_DIM_SIZE;
+        let schema = Arc::new(ArrowSchema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("content", DataType::Utf8, false),
+            Field::new("metadata", DataType::Utf8, false),
+            Field::new(
+                "vector",
+                DataType::FixedSizeList(Arc::new(Field::new("item", DataType::Float32, true)), dim),
+                true,
+            ),
+            Field::new("model", DataType::Utf8, false),
+            Field::new(
+                "created_at",
+                DataType::Timestamp(TimeUnit::Second, None),
+                false,
+            ),
+            Field::new("chunk_number", DataType::Int32, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![0, 1])),
+                Arc::new(StringArray::from(vec!["fn main() {}", "fn other() {}"])),
+                Arc::new(StringArray::from(vec!["main.rs", "other.rs"])),
+                Arc::new(
+                    FixedSizeListArray::from_iter_primitive::<Float32Type, _, _>(
+                        (0..2).map(|i| Some(vec![Some(1.0 + i as f32); dim as usize])),
+                        dim,
+                    ),
+                ),
+                Arc::new(StringArray::from(vec!["test-model", "test-model"])),
+                Arc::new(TimestampSecondArray::from(vec![0, 0])),
+                Arc::new(Int32Array::from(vec![0, 0])),
+            ],
+        )
+        .expect("Failed to create record batch");
+        db.create_table(
+            table_name,
+            Box::new(RecordBatchIterator::new(vec![Ok(batch)], schema)),
+        )
+        .execute()
+        .await
+        .expect("Failed to create table");
+
+        for file_context in [false, true] {
+            let content = query::query_vector_table(
+                db,
+                table_name,
+                vec![1.0; dim as usize],
+                false,
+                file_context,
+                None,
+                None,
+            )
+            .await
+            .expect("Failed to query a table without the path column");
+            assert_eq!(content.len(), 2);
+        }
+
+        // filtering on a symbol needs the symbols column, boosting keeps the nearest chunks
+        for mode in [SymbolMatch::Filter, SymbolMatch::Boost] {
+            let symbol = SymbolQuery::new("main", mode);
+            let result = query::query_vector_table(
+                db,
+                table_name,
+                vec![1.0; dim as usize],
+                false,
+                false,
+                Some(&symbol),
+                None,
+            )
+            .await;
+            match mode {
+                SymbolMatch::Filter => assert!(result
+                    .expect_err("Filtered a table without the symbols column")
+                    .to_string()
+                    .contains("has no symbols column, reload it")),
+                SymbolMatch::Boost => assert_eq!(result.expect("Failed to boost").len(), 2),
+            }
+        }
+
+        let window = TimeWindow::parse_window(Some("2024-05-01"), None).unwrap();
+        let error = query::query_vector_table(
+            db,
+            table_name,
+            vec![1.0; dim as usize],
+            false,
+            false,
+            None,
+            window.as_ref(),
+        )
+        .await
+        .expect_err("Filtered a table without the log_timestamp column on a time window");
+        assert!(error
+            .to_string()
+            .contains("has no log_timestamp column, reload it"));
+    }
+
+    #[tokio::test]
+    async fn query_spark_failures_test() {
+        let table_name = "test_table_query_spark";
+        let db = &mut create_test_connection(TEST_DB_URI)
+            .await
+            .expect("Failed to create test connection");
+        let table_schema = create_test_table_schema(table_name);
+        create_lance_table(db, &table_schema)
+            .await
+            .expect("Failed to create table");
+
+        let chunks = [
+            ("Stage 11 attempt 0 of job 3 failed", Some(11), 0),
+            ("Stage 12 attempt 0 of job 3 failed", Some(12), 0),
+            ("Job 3 failed", None, 0),
+            ("Stage 12 attempt 1 of job 3 failed", Some(12), 1),
+        ];
+        let table = db
+            .open_table(table_name)
+            .execute()
+            .await
+            .expect("Failed to open table");
+        // every chunk is inserted on its own, the failures are spread over fragments
+        for (i, (content, stage_id, attempt)) in chunks.iter().enumerate() {
+            let request = Arc::new(RwLock::new(EmbedRequest {
+                provider: "test-provider".to_string(),
+                api_url: "http://localhost:8000".to_string(),
+                api_key: "test-key".into(),
+                input: vec![content.to_string()],
+                model: "test-model".to_string(),
+                metadata: Some("app-20240501120000-0001".to_string()),
+                chunk_number: Some(i as i32),
+                dimensions: None,
+                chunk_metadata: ChunkMetadata {
+                    spark_app_id: Some("app-20240501120000-0001".to_string()),
+                    spark_job_id: Some(3),
+                    spark_stage_id: *stage_id,
+                    spark_stage_attempt: stage_id.map(|_| *attempt),
+                    ..Default::default()
+                },
+            }));
+            let response = EmbedResponse {
+                model: "test-model".to_string(),
+                embeddings: vec![vec![1.0; VECTOR_DB_DIM_SIZE as usize]],
+            };
+            let batch = create_record_batch(i as i32, request, response, &table_schema)
+                .await
+                .expect("Failed to create record batch");
+            vectordb::vector_load::insert_record_batches(&table_schema, vec![batch], table.clone())
+                .await
+                .expect("Failed to insert records");
+        }
+
+        let target = SparkTarget::from_question("Why did Stage 12 fail?").unwrap();
+        assert_eq!(target, SparkTarget::Stage(12));
+        let mut failures = query::query_spark_failures(&table, target, None)
+            .await
+            .expect("Failed to query spark failures");
+        failures.sort();
+        assert_eq!(
+            failures,
+            vec![
+                "Stage 12 attempt 0 of job 3 failed",
+                "Stage 12 attempt 1 of job 3 failed"
+            ]
+        );
+
+        let failures = query::query_spark_failures(&table, SparkTarget::Job(3), None)
+            .await
+            .expect("Failed to query spark failures");
+        assert_eq!(failures, vec!["Job 3 failed"]);
+
+        assert_eq!(
+            SparkTarget::from_question("what failed in job 7"),
+            Some(SparkTarget::Job(7))
+        );
+        assert_eq!(SparkTarget::from_question("which stage failed"), None);
+    }
+
     #[tokio::test]
     async fn query_vector_table_test() {
         // table: &Table,
@@ -281,9 +812,10 @@ mod test_lancedb_query {
             .expect("Failed to create test connection");
 
         let query_vector = vec![0.0; VECTOR_DB_DIM_SIZE as usize];
-        let content = query::query_vector_table(db, table_name, query_vector, false, false)
-            .await
-            .expect("Failed to query vector table");
+        let content =
+            query::query_vector_table(db, table_name, query_vector, false, false, None, None)
+                .await
+                .expect("Failed to query vector table");
 
         assert_eq!(content.len(), 30);
     }
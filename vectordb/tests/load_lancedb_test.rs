@@ -5,6 +5,7 @@ mod load_lancedb_test {
     use arrow::array::{FixedSizeListArray, StringArray};
     use configs::constants::VECTOR_DB_DIM_SIZE;
     use embedder::embed_config::{EmbedRequest, EmbedResponse};
+    use embedder::file_loader::ChunkMetadata;
     use lancedb::connection::Connection;
     use std::sync::Arc;
     use tokio::sync::RwLock;
@@ -12,8 +13,9 @@ mod load_lancedb_test {
         create_record_batch,
         insert_embeddings,
     };
+    use vectordb::query::check_query_embedding;
     use vectordb::vector_index::{create_index_on_embedding, create_inverted_index};
-    use vectordb::vector_schema::{create_lance_table, TableSchema};
+    use vectordb::vector_schema::{create_lance_table, table_embedding, TableSchema};
 
     // Mock constants for testing
     const TEST_DB_URI: &str = "test_db";
@@ -37,7 +39,7 @@ mod load_lancedb_test {
 
     // Helper function to create a test table schema
     fn create_test_table_schema(table_name: &str) -> TableSchema {
-        TableSchema::new(&table_name.to_string())
+        TableSchema::new(table_name)
     }
 
     #[tokio::test]
@@ -74,11 +76,13 @@ mod load_lancedb_test {
         let request = Arc::new(RwLock::new(EmbedRequest {
             provider: "test-provider".to_string(),
             api_url: "http://localhost:8000".to_string(),
-            api_key: "test-key".to_string(),
+            api_key: "test-key".into(),
             input: vec!["test content".to_string()],
             model: "test-model".to_string(),
             metadata: Some("test-dir".to_string()),
             chunk_number: Some(0),
+            dimensions: None,
+            chunk_metadata: ChunkMetadata::default(),
         }));
 
         let response = EmbedResponse {
@@ -89,7 +93,7 @@ mod load_lancedb_test {
         let record_batch = create_record_batch(1, request, response, &table_schema).await?;
 
         assert_eq!(record_batch.num_rows(), 1);
-        assert_eq!(record_batch.num_columns(), 7);
+        assert_eq!(record_batch.num_columns(), 29);
 
         // Verify content
         let content = record_batch
@@ -174,11 +178,13 @@ mod load_lancedb_test {
         let request = Arc::new(RwLock::new(EmbedRequest {
             provider: "test-provider".to_string(),
             api_url: "http://localhost:8000".to_string(),
-            api_key: "test-key".to_string(),
+            api_key: "test-key".into(),
             input: input_texts,
             model: "test-model".to_string(),
             metadata: Some("test-dir".to_string()),
             chunk_number: Some(0),
+            dimensions: None,
+            chunk_metadata: ChunkMetadata::default(),
         }));
 
         let response = EmbedResponse {
@@ -214,7 +220,7 @@ mod load_lancedb_test {
         assert_eq!(table_schema.vector.name(), "vector");
 
         let arrow_schema = table_schema.create_schema();
-        assert_eq!(arrow_schema.fields().len(), 7);
+        assert_eq!(arrow_schema.fields().len(), 29);
     }
 
     #[tokio::test]
@@ -224,7 +230,7 @@ mod load_lancedb_test {
         let batch = table_schema.empty_batch()?;
 
         assert_eq!(batch.num_rows(), 256);
-        assert_eq!(batch.num_columns(), 7);
+        assert_eq!(batch.num_columns(), 29);
         // verify embedding column
         let embedding_col = batch
             .column(3)
@@ -235,6 +241,61 @@ mod load_lancedb_test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_table_embedding_dimension() -> Result<()> {
+        // own database, the shared test_db is dropped by the other tests
+        let db_uri = "test_db_embedding_dim";
+        let _ = std::fs::remove_dir_all(db_uri);
+        let mut db = lancedb::connect(db_uri).execute().await?;
+        let table_name = "TEST_TABLE_NAME_EMBEDDING_DIM";
+        let table_schema = TableSchema::with_embedding(table_name, 4, Some("mxbai-embed-large"));
+        create_lance_table(&mut db, &table_schema).await?;
+
+        let table = db.open_table(table_name).execute().await?;
+        let embedding = table_embedding(&table).await?;
+        assert_eq!(embedding.vector_dim, 4);
+        assert_eq!(embedding.embed_model.as_deref(), Some("mxbai-embed-large"));
+
+        let request = Arc::new(RwLock::new(EmbedRequest {
+            provider: "test-provider".to_string(),
+            api_url: "http://localhost:8000".to_string(),
+            api_key: "test-key".into(),
+            input: vec!["test content".to_string()],
+            model: "mxbai-embed-large".to_string(),
+            metadata: Some("test-dir".to_string()),
+            chunk_number: Some(0),
+            dimensions: None,
+            chunk_metadata: ChunkMetadata::default(),
+        }));
+        let response = EmbedResponse {
+            model: "mxbai-embed-large".to_string(),
+            embeddings: vec![vec![1.0; 4]],
+        };
+        let record_batch = create_record_batch(1, request.clone(), response, &table_schema).await?;
+        insert_embeddings(&table_schema, record_batch, table).await?;
+
+        // embeddings of another size are rejected instead of building a broken batch
+        let response = EmbedResponse {
+            model: "nomic-embed-text".to_string(),
+            embeddings: vec![vec![1.0; 768]],
+        };
+        let error = create_record_batch(2, request, response, &table_schema)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("768 dimensions"));
+
+        check_query_embedding(&mut db, table_name, "mxbai-embed-large", 4).await?;
+        let error = check_query_embedding(&mut db, table_name, "nomic-embed-text", 768)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("nomic-embed-text returns 768"));
+        assert!(error.contains("loaded with mxbai-embed-large which has 4 dimensions"));
+
+        std::fs::remove_dir_all(db_uri)?;
+        Ok(())
+    }
+
     #[tokio::test]
     async fn zz_clean_up() {
         delete_test_db(); // Runs last due to name sorting